@@ -2,7 +2,7 @@ from __future__ import annotations
 from abc import ABC, abstractmethod
 import json
 import pickle
-from typing import Any, Dict, List, Tuple, Union
+from typing import Any, Dict, List, Optional, Tuple, Union
 
 
 JSON = Union[Dict[str, "JSON"], List["JSON"], str, int, float, bool, None]
@@ -38,11 +38,29 @@ class Message:
 
 
 class Context(object):
-    def __init__(self, time: float):
+    def __init__(
+        self,
+        time: float,
+        timers: Optional[Dict[str, float]] = None,
+        proc_name: str = '',
+        node_name: str = '',
+        processes: Optional[List[str]] = None,
+        node_processes: Optional[List[str]] = None,
+        groups: Optional[Dict[str, List[str]]] = None,
+    ):
         self._time = time
+        self._proc_name = proc_name
+        self._node_name = node_name
+        # topology objects are shared between contexts and must not be modified
+        self._processes: List[str] = processes if processes is not None else []
+        self._node_processes: List[str] = node_processes if node_processes is not None else []
+        self._groups: Dict[str, List[str]] = groups if groups is not None else {}
+        self._timers: Dict[str, float] = {name: time + delay for name, delay in (timers or {}).items()}
         self._sent_messages: List[Tuple[str, str, str]] = list()
         self._sent_local_messages: List[tuple[str, str]] = list()
-        self._timer_actions: List[Tuple[str, float, bool]] = list()
+        self._timer_actions: List[Tuple[str, str, float, float]] = list()
+        self._user_events: List[Tuple[str, str]] = list()
+        self._metrics: List[Tuple[str, str, float]] = list()
 
     def send(self, msg: Message, to: str):
         """
@@ -75,7 +93,8 @@ class Context(object):
             raise TypeError('delay argument has to be int or float, not {}'.format(type(delay)))
         if delay < 0:
             raise ValueError('delay argument has to be non-negative')
-        self._timer_actions.append((timer_name, delay, False))
+        self._timers[timer_name] = self._time + delay
+        self._timer_actions.append(('set', timer_name, delay, 0.0))
 
     def set_timer_once(self, timer_name: str, delay: float):
         """
@@ -90,7 +109,28 @@ class Context(object):
             raise TypeError('delay argument has to be int or float, not {}'.format(type(delay)))
         if delay < 0:
             raise ValueError('delay argument has to be non-negative')
-        self._timer_actions.append((timer_name, delay, True))
+        self._timers.setdefault(timer_name, self._time + delay)
+        self._timer_actions.append(('once', timer_name, delay, 0.0))
+
+    def set_periodic_timer(self, timer_name: str, period: float, jitter: float = 0.0):
+        """
+        Sets a timer that will trigger on_timer callback every period until cancelled.
+        If there is an active timer with this name, it is overridden.
+        Each delay is increased by a random value from [0, jitter) drawn from the simulation RNG.
+        """
+        if not isinstance(timer_name, str):
+            raise TypeError('timer_name argument has to be str, not {}'.format(type(timer_name)))
+        if len(timer_name) > 50:
+            raise ValueError('timer_name length exceeds the limit of 50 characters')
+        if not isinstance(period, (int, float)) or not isinstance(jitter, (int, float)):
+            raise TypeError('period and jitter arguments have to be int or float')
+        if period <= 0:
+            raise ValueError('period argument has to be positive')
+        if jitter < 0:
+            raise ValueError('jitter argument has to be non-negative')
+        # the jitter is drawn on the Rust side, so the first firing time is approximate
+        self._timers[timer_name] = self._time + period
+        self._timer_actions.append(('periodic', timer_name, period, jitter))
 
     def cancel_timer(self, timer_name: str):
         """
@@ -98,7 +138,48 @@ class Context(object):
         """
         if not isinstance(timer_name, str):
             raise TypeError('timer_name argument has to be str, not {}'.format(type(timer_name)))
-        self._timer_actions.append((timer_name, -1, False))
+        self._timers.pop(timer_name, None)
+        self._timer_actions.append(('cancel', timer_name, 0.0, 0.0))
+
+    def is_timer_set(self, timer_name: str) -> bool:
+        """
+        Returns true if the timer with the specified name is active.
+        """
+        return timer_name in self._timers
+
+    def timer_remaining(self, timer_name: str) -> Optional[float]:
+        """
+        Returns the remaining time until the timer fires, or None if the timer is not active.
+        """
+        if timer_name not in self._timers:
+            return None
+        return max(self._timers[timer_name] - self._time, 0.0)
+
+    def log(self, kind: str, data: Any = ''):
+        """
+        Records a user-defined event in the trace.
+        String data is recorded as is, other values are serialized to JSON.
+        """
+        if len(kind) > 50:
+            raise ValueError('event kind length exceeds the limit of 50 characters')
+        if not isinstance(data, str):
+            data = json.dumps(data)
+        self._user_events.append((kind, data))
+
+    def increment_counter(self, name: str, delta: float = 1.0):
+        """
+        Increments the process counter with the specified name by a non-negative value.
+        The counter is stored in the system metrics under the name prefixed with `user_`.
+        """
+        if delta < 0:
+            raise ValueError('counter can not be decreased')
+        self._metrics.append((name, 'counter', float(delta)))
+
+    def set_gauge(self, name: str, value: float):
+        """
+        Sets the value of the process gauge with the specified name.
+        """
+        self._metrics.append((name, 'gauge', float(value)))
 
     def time(self) -> float:
         """
@@ -106,6 +187,42 @@ class Context(object):
         """
         return self._time
 
+    def proc_name(self) -> str:
+        """
+        Returns the name of the process.
+        """
+        return self._proc_name
+
+    def node_name(self) -> str:
+        """
+        Returns the name of the node hosting the process.
+        """
+        return self._node_name
+
+    def processes(self) -> List[str]:
+        """
+        Returns the names of all processes in the system, including this process.
+        """
+        return list(self._processes)
+
+    def peers(self) -> List[str]:
+        """
+        Returns the names of all other processes in the system.
+        """
+        return [proc for proc in self._processes if proc != self._proc_name]
+
+    def group(self, name: str) -> List[str]:
+        """
+        Returns the names of processes in the group, or an empty list if there is no such group.
+        """
+        return list(self._groups.get(name, []))
+
+    def node_processes(self) -> List[str]:
+        """
+        Returns the names of processes running on the same node, including this process.
+        """
+        return list(self._node_processes)
+
 
 class Process(ABC):
     @abstractmethod
@@ -0,0 +1,461 @@
+//! Adapter for implementing processes as async functions.
+//!
+//! Instead of implementing [`Process`] callbacks as an explicit state machine, the process logic can be written as
+//! a single async function which sends messages, waits for messages matching a filter, sleeps and selects between
+//! these operations:
+//!
+//! ```
+//! use anysystem::async_process::{select, AsyncProcess, Either};
+//! use anysystem::Message;
+//!
+//! let client = AsyncProcess::new(|ctx| async move {
+//!     let ping = ctx.recv_local(|msg| msg.tip == "PING").await;
+//!     loop {
+//!         ctx.send(ping.clone(), "server");
+//!         let pong = ctx.recv(|msg, from| msg.tip == "PONG" && from == "server");
+//!         if let Either::Left(((pong, _), _)) = select(pong, ctx.sleep(3.)).await {
+//!             ctx.send_local(pong);
+//!             return Ok(());
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! The function is driven by a deterministic single-threaded executor: each event delivered to the process is put
+//! into the process mailbox and then the function is polled until it waits for the next event. Messages which do not
+//! match the filters of current [`AsyncContext::recv`] calls are kept in the mailbox until they are received.
+//!
+//! Since the state of a running async function can not be copied, the process records the history of delivered
+//! events and recreates its state by replaying the history on a fresh instance of the function. This is used for
+//! cloning the process and restoring its state in model checking, where the history plays the role of the process
+//! state. Therefore, the function must be deterministic, i.e. depend only on the delivered events and time.
+//! The replay is lazy: a cloned or restored process runs the function only when it handles the next event, so
+//! creating a [`ModelChecker`](crate::mc::ModelChecker) does not run the user code again.
+//!
+//! By default, the process states are compared by their histories, which grow with each event. Therefore, the states
+//! reached by different sequences of events are never equal and model checking does not benefit from skipping
+//! the visited states. To enable it, the function can declare the part of its state affecting the further behavior
+//! with [`AsyncContext::set_state_view`]. The history is dropped when the function completes, so all states
+//! of the completed process are equal.
+
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::future::{poll_fn, Future};
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context as TaskContext, Poll, Waker};
+
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+
+use crate::process::canonical_json;
+use crate::{Context, Message, Process, ProcessState};
+
+const SLEEP_TIMER_PREFIX: &str = "async-sleep#";
+
+type ProcessFuture = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+type ProcessFactory = Rc<dyn Fn(AsyncContext) -> ProcessFuture>;
+
+/// Event delivered to the async process.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum AsyncEvent {
+    Start,
+    Message { msg: Message, from: String },
+    LocalMessage { msg: Message },
+    Timer { name: String },
+}
+
+/// Action performed by the async process.
+enum AsyncAction {
+    Send { msg: Message, dst: String },
+    SendLocal { msg: Message },
+    SetTimer { name: String, delay: f64 },
+    CancelTimer { name: String },
+}
+
+#[derive(Default)]
+struct Mailbox {
+    time: f64,
+    messages: VecDeque<(Message, String)>,
+    local_messages: VecDeque<Message>,
+    fired_timers: HashSet<u64>,
+    next_timer_id: u64,
+    actions: Vec<AsyncAction>,
+    view: Option<String>,
+}
+
+/// Proxy for interaction of an async process with the system.
+///
+/// The context is passed to the process function and can be cloned to use it in nested futures.
+#[derive(Clone)]
+pub struct AsyncContext {
+    proc_name: String,
+    mailbox: Rc<RefCell<Mailbox>>,
+}
+
+impl AsyncContext {
+    /// Returns the name of the process.
+    pub fn proc_name(&self) -> &str {
+        &self.proc_name
+    }
+
+    /// Returns the time of the event being handled from the local node clock.
+    pub fn time(&self) -> f64 {
+        self.mailbox.borrow().time
+    }
+
+    /// Sends a message to a process.
+    pub fn send(&self, msg: Message, dst: &str) {
+        let action = AsyncAction::Send {
+            msg,
+            dst: dst.to_string(),
+        };
+        self.mailbox.borrow_mut().actions.push(action);
+    }
+
+    /// Sends a local message.
+    pub fn send_local(&self, msg: Message) {
+        self.mailbox.borrow_mut().actions.push(AsyncAction::SendLocal { msg });
+    }
+
+    /// Sets the view of the process state used to compare the process states in model checking.
+    ///
+    /// The view should include everything affecting the further behavior of the function, e.g. the values of its
+    /// variables, and should be updated when they change. Once the view is set, the states with equal views and
+    /// the same messages waiting in the mailbox are considered equal regardless of the histories of delivered events.
+    ///
+    /// Panics if the view can not be serialized to JSON.
+    pub fn set_state_view<V: Serialize>(&self, view: &V) {
+        let view = canonical_json(view).unwrap_or_else(|err| panic!("invalid state view: {err}"));
+        self.mailbox.borrow_mut().view = Some(view);
+    }
+
+    /// Waits for a message matching the filter and returns it along with the sender name.
+    ///
+    /// Messages received earlier and not consumed by other calls are checked first in the order of their arrival.
+    pub async fn recv(&self, filter: impl Fn(&Message, &str) -> bool) -> (Message, String) {
+        poll_fn(|_| {
+            let mut mailbox = self.mailbox.borrow_mut();
+            match mailbox.messages.iter().position(|(msg, from)| filter(msg, from)) {
+                Some(idx) => Poll::Ready(mailbox.messages.remove(idx).unwrap()),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Waits for a local message matching the filter.
+    pub async fn recv_local(&self, filter: impl Fn(&Message) -> bool) -> Message {
+        poll_fn(|_| {
+            let mut mailbox = self.mailbox.borrow_mut();
+            match mailbox.local_messages.iter().position(&filter) {
+                Some(idx) => Poll::Ready(mailbox.local_messages.remove(idx).unwrap()),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Waits for the specified time.
+    ///
+    /// The sleep is implemented with a timer, which is cancelled if the future is dropped before completion,
+    /// e.g. when another branch of [`select`] completes first.
+    pub async fn sleep(&self, delay: f64) {
+        let mut timer = SleepTimer::new(self.mailbox.clone(), delay);
+        poll_fn(|_| {
+            if self.mailbox.borrow_mut().fired_timers.remove(&timer.id) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        timer.fired = true;
+    }
+}
+
+/// Timer set by [`AsyncContext::sleep`].
+struct SleepTimer {
+    mailbox: Rc<RefCell<Mailbox>>,
+    id: u64,
+    fired: bool,
+}
+
+impl SleepTimer {
+    fn new(mailbox: Rc<RefCell<Mailbox>>, delay: f64) -> Self {
+        let id = {
+            let mut mailbox = mailbox.borrow_mut();
+            let id = mailbox.next_timer_id;
+            mailbox.next_timer_id += 1;
+            mailbox.actions.push(AsyncAction::SetTimer {
+                name: format!("{SLEEP_TIMER_PREFIX}{id}"),
+                delay,
+            });
+            id
+        };
+        Self {
+            mailbox,
+            id,
+            fired: false,
+        }
+    }
+}
+
+impl Drop for SleepTimer {
+    fn drop(&mut self) {
+        if !self.fired {
+            let name = format!("{SLEEP_TIMER_PREFIX}{}", self.id);
+            self.mailbox
+                .borrow_mut()
+                .actions
+                .push(AsyncAction::CancelTimer { name });
+        }
+    }
+}
+
+/// Result of [`select`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Either<A, B> {
+    /// The first future completed.
+    Left(A),
+    /// The second future completed.
+    Right(B),
+}
+
+/// Waits for the first of two futures to complete and returns its output along with the other future.
+///
+/// The futures are polled in order, so if both are ready, the first one is selected.
+/// The other future can be awaited later or dropped to cancel it.
+pub async fn select<A: Future, B: Future>(a: A, b: B) -> Either<(A::Output, Pin<Box<B>>), (B::Output, Pin<Box<A>>)> {
+    let mut a = Some(Box::pin(a));
+    let mut b = Some(Box::pin(b));
+    poll_fn(|cx| {
+        if let Poll::Ready(res) = a.as_mut().unwrap().as_mut().poll(cx) {
+            return Poll::Ready(Either::Left((res, b.take().unwrap())));
+        }
+        if let Poll::Ready(res) = b.as_mut().unwrap().as_mut().poll(cx) {
+            return Poll::Ready(Either::Right((res, a.take().unwrap())));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+type History = Vec<(AsyncEvent, OrderedFloat<f64>)>;
+
+/// State of the async process represented by the history of delivered events and their times.
+///
+/// If the process sets the state view, the states are compared by the view and the messages in the mailbox.
+#[derive(Clone, Debug)]
+pub struct AsyncProcessState {
+    history: History,
+    key: Option<StateKey>,
+    finished: bool,
+}
+
+/// Part of the process state used for comparison when the state view is set.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct StateKey {
+    view: String,
+    messages: VecDeque<(Message, String)>,
+    local_messages: VecDeque<Message>,
+}
+
+impl Hash for AsyncProcessState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.finished.hash(state);
+        match &self.key {
+            Some(key) => key.hash(state),
+            None => self.history.hash(state),
+        }
+    }
+}
+
+impl PartialEq for AsyncProcessState {
+    fn eq(&self, other: &Self) -> bool {
+        self.finished == other.finished
+            && self.key == other.key
+            && (self.key.is_some() || self.history == other.history)
+    }
+}
+
+impl Eq for AsyncProcessState {}
+
+/// Process implemented as an async function (see the [module documentation](self)).
+pub struct AsyncProcess {
+    factory: ProcessFactory,
+    future: Option<ProcessFuture>,
+    ctx: AsyncContext,
+    history: History,
+    key: Option<StateKey>,
+    finished: bool,
+    /// The future does not correspond to the history and should be recreated before delivering the next event.
+    stale: bool,
+}
+
+impl AsyncProcess {
+    /// Creates a process which runs the future returned by `f` after the process is started.
+    ///
+    /// The process stops receiving events when the future completes. If the future returns an error,
+    /// it is returned from the handler of the current event.
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn(AsyncContext) -> Fut + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        Self::with_factory(Rc::new(move |ctx| Box::pin(f(ctx))))
+    }
+
+    fn with_factory(factory: ProcessFactory) -> Self {
+        Self {
+            factory,
+            future: None,
+            ctx: AsyncContext {
+                proc_name: String::new(),
+                mailbox: Rc::default(),
+            },
+            history: Vec::new(),
+            key: None,
+            finished: false,
+            stale: false,
+        }
+    }
+
+    /// Recreates the future by replaying the history on a fresh instance of the process if needed.
+    fn replay(&mut self) {
+        if !self.stale {
+            return;
+        }
+        let mut proc = Self::with_factory(self.factory.clone());
+        proc.ctx.proc_name.clone_from(&self.ctx.proc_name);
+        for (event, time) in &self.history {
+            // the actions and errors were already handled when the events were delivered originally
+            let _ = proc.deliver(event.clone(), time.0);
+        }
+        *self = proc;
+    }
+
+    /// Delivers the event to the mailbox and polls the future until it waits for the next event.
+    fn deliver(&mut self, event: AsyncEvent, time: f64) -> Result<Vec<AsyncAction>, String> {
+        if self.finished {
+            return Ok(Vec::new());
+        }
+        self.replay();
+        self.history.push((event.clone(), OrderedFloat(time)));
+        let is_start = event == AsyncEvent::Start;
+        {
+            let mut mailbox = self.ctx.mailbox.borrow_mut();
+            mailbox.time = time;
+            match event {
+                AsyncEvent::Start => {}
+                AsyncEvent::Message { msg, from } => mailbox.messages.push_back((msg, from)),
+                AsyncEvent::LocalMessage { msg } => mailbox.local_messages.push_back(msg),
+                AsyncEvent::Timer { name } => {
+                    if let Some(id) = name.strip_prefix(SLEEP_TIMER_PREFIX).and_then(|id| id.parse().ok()) {
+                        mailbox.fired_timers.insert(id);
+                    }
+                }
+            }
+        }
+        if is_start {
+            self.future = Some((self.factory)(self.ctx.clone()));
+        }
+        let mut res = Ok(());
+        if let Some(future) = self.future.as_mut() {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut TaskContext::from_waker(Waker::noop())) {
+                // the completed process does not react to events, so its history is not needed
+                self.future = None;
+                self.finished = true;
+                self.history.clear();
+                res = output;
+            }
+        }
+        let mut mailbox = self.ctx.mailbox.borrow_mut();
+        self.key = match (&mailbox.view, self.finished) {
+            (Some(view), false) => Some(StateKey {
+                view: view.clone(),
+                messages: mailbox.messages.clone(),
+                local_messages: mailbox.local_messages.clone(),
+            }),
+            _ => None,
+        };
+        let actions = mailbox.actions.drain(..).collect();
+        res.map(|_| actions)
+    }
+
+    fn handle_event(&mut self, event: AsyncEvent, ctx: &mut Context) -> Result<(), String> {
+        for action in self.deliver(event, ctx.time())? {
+            match action {
+                AsyncAction::Send { msg, dst } => ctx.send(msg, dst),
+                AsyncAction::SendLocal { msg } => ctx.send_local(msg),
+                AsyncAction::SetTimer { name, delay } => ctx.set_timer(&name, delay),
+                AsyncAction::CancelTimer { name } => ctx.cancel_timer(&name),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Clone for AsyncProcess {
+    /// Copies the history, the function is replayed when the copy handles the next event.
+    fn clone(&self) -> Self {
+        let mut proc = Self::with_factory(self.factory.clone());
+        proc.ctx.proc_name.clone_from(&self.ctx.proc_name);
+        proc.history.clone_from(&self.history);
+        proc.key.clone_from(&self.key);
+        proc.finished = self.finished;
+        proc.stale = !self.finished;
+        proc
+    }
+}
+
+impl Process for AsyncProcess {
+    fn on_start(&mut self, ctx: &mut Context) -> Result<(), String> {
+        self.ctx.proc_name = ctx.proc_name().to_string();
+        self.handle_event(AsyncEvent::Start, ctx)
+    }
+
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        self.handle_event(AsyncEvent::Message { msg, from }, ctx)
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        self.handle_event(AsyncEvent::LocalMessage { msg }, ctx)
+    }
+
+    fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
+        self.handle_event(AsyncEvent::Timer { name: timer }, ctx)
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(AsyncProcessState {
+            history: self.history.clone(),
+            key: self.key.clone(),
+            finished: self.finished,
+        }))
+    }
+
+    fn set_state(&mut self, state: Rc<dyn ProcessState>) -> Result<(), String> {
+        let state = state
+            .downcast_rc::<AsyncProcessState>()
+            .map_err(|_| "invalid async process state".to_string())?;
+        if state.finished == self.finished && state.history == self.history {
+            return Ok(());
+        }
+        // the current future is kept if it precedes the restored state, otherwise it is replayed from scratch
+        let continues = !self.stale && !self.finished && !state.finished && state.history.starts_with(&self.history);
+        if continues {
+            for (event, time) in state.history[self.history.len()..].iter() {
+                let _ = self.deliver(event.clone(), time.0);
+            }
+        } else {
+            self.future = None;
+            self.history.clone_from(&state.history);
+            self.key.clone_from(&state.key);
+            self.finished = state.finished;
+            self.stale = !state.finished;
+        }
+        Ok(())
+    }
+}
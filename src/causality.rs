@@ -0,0 +1,280 @@
+//! Logical clocks and causality analysis.
+//!
+//! In simulation mode each process transparently maintains a Lamport clock and a vector clock.
+//! The clocks are advanced when the process sends or receives a message, the sender clocks are delivered
+//! along with the message without changing its payload. The clocks are attached to
+//! [`LogEntry::MessageSent`] and [`LogEntry::MessageReceived`] entries and are available to the process via
+//! [`Context::lamport_time`](crate::Context::lamport_time) and
+//! [`Context::vector_clock`](crate::Context::vector_clock).
+//!
+//! [`CausalityGraph`] allows to analyze the happens-before relation between the events of an arbitrary trace,
+//! including the traces produced by the model checker.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logger::LogEntry;
+use crate::Message;
+
+/// Vector clock mapping process names to their logical times.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+impl VectorClock {
+    /// Creates an empty vector clock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the time of the process.
+    pub fn get(&self, proc: &str) -> u64 {
+        self.0.get(proc).copied().unwrap_or(0)
+    }
+
+    /// Increments the time of the process.
+    pub fn increment(&mut self, proc: &str) {
+        *self.0.entry(proc.to_string()).or_insert(0) += 1;
+    }
+
+    /// Updates the clock to the element-wise maximum of this and other clock.
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (proc, time) in other.0.iter() {
+            let entry = self.0.entry(proc.clone()).or_insert(0);
+            *entry = (*entry).max(*time);
+        }
+    }
+
+    /// Returns true if the event with this clock happened before the event with other clock.
+    pub fn happens_before(&self, other: &VectorClock) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Returns true if the events with this and other clock are concurrent.
+    pub fn concurrent_with(&self, other: &VectorClock) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+
+    /// Returns an iterator over process times.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.0.iter().map(|(proc, time)| (proc.as_str(), *time))
+    }
+}
+
+impl PartialOrd for VectorClock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let mut less = false;
+        let mut greater = false;
+        for proc in self.0.keys().chain(other.0.keys()) {
+            match self.get(proc).cmp(&other.get(proc)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+/// Process event from a trace with its position in the causal order.
+#[derive(Clone, Debug)]
+pub struct CausalEvent {
+    /// Index of event in the trace.
+    pub index: usize,
+    /// Process in which the event happened.
+    pub proc: String,
+    /// Vector clock of the event.
+    pub clock: VectorClock,
+}
+
+/// Happens-before graph of process events built from a trace.
+///
+/// The graph includes events happened in processes, i.e. sending and receiving of messages, local messages,
+/// timer events and state updates. Events are ordered by the order of events in each process and by message
+/// edges from sending to receiving a message. Other events (e.g. network changes) are not included.
+///
+/// Messages are matched by their ids in simulation traces. In model checking traces, where messages have no ids,
+/// a received message is matched with the earliest unmatched sending of the same message between the same processes.
+pub struct CausalityGraph {
+    events: Vec<CausalEvent>,
+    positions: HashMap<usize, usize>,
+    message_edges: Vec<(usize, usize)>,
+}
+
+enum TraceEvent<'a> {
+    Send(&'a str, MessageKey<'a>),
+    Receive(&'a str, MessageKey<'a>),
+    Local(&'a str),
+    Corrupt(MessageKey<'a>, MessageKey<'a>),
+    None,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum MessageKey<'a> {
+    Id(&'a str),
+    Content(&'a str, &'a str, &'a Message),
+}
+
+fn classify<'a>(entry: &'a LogEntry, dst_by_id: &HashMap<&'a str, &'a str>) -> TraceEvent<'a> {
+    match entry {
+        LogEntry::MessageSent { msg_id, src_proc, .. } => TraceEvent::Send(src_proc, MessageKey::Id(msg_id)),
+        LogEntry::MessageReceived { msg_id, dst_proc, .. } => {
+            // destination is not serialized, so it is restored from the sent message for traces read from files
+            let dst = if dst_proc.is_empty() {
+                dst_by_id.get(msg_id.as_str()).copied().unwrap_or("")
+            } else {
+                dst_proc
+            };
+            TraceEvent::Receive(dst, MessageKey::Id(msg_id))
+        }
+        LogEntry::LocalMessageSent { proc, .. }
+        | LogEntry::LocalMessageReceived { proc, .. }
+        | LogEntry::TimerSet { proc, .. }
+        | LogEntry::ProcessStateUpdated { proc, .. }
+        | LogEntry::UserEvent { proc, .. }
+        | LogEntry::ProcessFailed { proc, .. }
+        | LogEntry::McUserEvent { proc, .. }
+        | LogEntry::McLocalMessageSent { proc, .. }
+        | LogEntry::McLocalMessageReceived { proc, .. }
+        | LogEntry::McTimerSet { proc, .. }
+        | LogEntry::McTimerFired { proc, .. }
+        | LogEntry::McTimerCancelled { proc, .. } => TraceEvent::Local(proc),
+        LogEntry::TimerFired { proc, .. } | LogEntry::TimerCancelled { proc, .. } if !proc.is_empty() => {
+            TraceEvent::Local(proc)
+        }
+        LogEntry::McMessageSent { msg, src, dst } => TraceEvent::Send(src, MessageKey::Content(src, dst, msg)),
+        LogEntry::McMessageReceived { msg, src, dst } => TraceEvent::Receive(dst, MessageKey::Content(src, dst, msg)),
+        LogEntry::McMessageCorrupted {
+            msg,
+            corrupted_msg,
+            src,
+            dst,
+        } => TraceEvent::Corrupt(
+            MessageKey::Content(src, dst, msg),
+            MessageKey::Content(src, dst, corrupted_msg),
+        ),
+        _ => TraceEvent::None,
+    }
+}
+
+impl CausalityGraph {
+    /// Builds the graph from a trace (see [`Logger::trace`](crate::logger::Logger::trace)).
+    pub fn from_trace(trace: &[LogEntry]) -> Self {
+        let mut dst_by_id = HashMap::new();
+        for entry in trace {
+            if let LogEntry::MessageSent { msg_id, dst_proc, .. } = entry {
+                dst_by_id.insert(msg_id.as_str(), dst_proc.as_str());
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut positions = HashMap::new();
+        let mut message_edges = Vec::new();
+        let mut proc_clocks: HashMap<&str, VectorClock> = HashMap::new();
+        // unmatched sent messages as (trace index, clock)
+        let mut in_flight: HashMap<MessageKey, VecDeque<(usize, VectorClock)>> = HashMap::new();
+        // last matched sent message, used for duplicated messages
+        let mut delivered: HashMap<MessageKey, (usize, VectorClock)> = HashMap::new();
+
+        for (index, entry) in trace.iter().enumerate() {
+            let (proc, sender, sent_key) = match classify(entry, &dst_by_id) {
+                TraceEvent::Send(proc, key) => (proc, None, Some(key)),
+                TraceEvent::Local(proc) => (proc, None, None),
+                TraceEvent::Receive(proc, key) => {
+                    let sender = match in_flight.get_mut(&key).and_then(|queue| queue.pop_front()) {
+                        Some(sent) => {
+                            delivered.insert(key, sent.clone());
+                            Some(sent)
+                        }
+                        None => delivered.get(&key).cloned(),
+                    };
+                    (proc, sender, None)
+                }
+                TraceEvent::Corrupt(key, corrupted_key) => {
+                    if let Some(sent) = in_flight.get_mut(&key).and_then(|queue| queue.pop_front()) {
+                        in_flight.entry(corrupted_key).or_default().push_back(sent);
+                    }
+                    continue;
+                }
+                TraceEvent::None => continue,
+            };
+            if proc.is_empty() {
+                continue;
+            }
+
+            let clock = proc_clocks.entry(proc).or_default();
+            if let Some((send_index, send_clock)) = sender {
+                clock.merge(&send_clock);
+                message_edges.push((send_index, index));
+            }
+            clock.increment(proc);
+            let clock = clock.clone();
+
+            if let Some(key) = sent_key {
+                in_flight.entry(key).or_default().push_back((index, clock.clone()));
+            }
+            positions.insert(index, events.len());
+            events.push(CausalEvent {
+                index,
+                proc: proc.to_string(),
+                clock,
+            });
+        }
+
+        Self {
+            events,
+            positions,
+            message_edges,
+        }
+    }
+
+    /// Returns the process events in trace order.
+    pub fn events(&self) -> &[CausalEvent] {
+        &self.events
+    }
+
+    /// Returns the process event with the specified trace index.
+    pub fn event(&self, index: usize) -> Option<&CausalEvent> {
+        self.positions.get(&index).map(|pos| &self.events[*pos])
+    }
+
+    /// Returns pairs of trace indices of matched message send and receive events.
+    pub fn message_edges(&self) -> &[(usize, usize)] {
+        &self.message_edges
+    }
+
+    /// Returns true if the event `a` is causally before the event `b` (both are specified by trace indices).
+    ///
+    /// Returns false if some event is not a process event.
+    pub fn happens_before(&self, a: usize, b: usize) -> bool {
+        match (self.event(a), self.event(b)) {
+            (Some(a), Some(b)) => a.index != b.index && a.clock.get(&a.proc) <= b.clock.get(&a.proc),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the events `a` and `b` are concurrent, i.e. neither happened before the other.
+    pub fn concurrent(&self, a: usize, b: usize) -> bool {
+        a != b
+            && self.event(a).is_some()
+            && self.event(b).is_some()
+            && !self.happens_before(a, b)
+            && !self.happens_before(b, a)
+    }
+
+    /// Returns trace indices of events concurrent with the specified event.
+    pub fn concurrent_events(&self, index: usize) -> Vec<usize> {
+        self.events
+            .iter()
+            .filter(|e| self.concurrent(index, e.index))
+            .map(|e| e.index)
+            .collect()
+    }
+}
@@ -1,20 +1,71 @@
 //! Process context.
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use indexmap::IndexMap;
 use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64;
 use simcore::SimulationContext;
 
+use crate::causality::VectorClock;
+use crate::metrics::MetricUpdate;
+use crate::rpc::{self, CallTag};
 use crate::{Message, ProcessEvent, TimerBehavior};
 
+/// Names of processes and nodes in the system, available to processes via [`Context`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Topology {
+    /// Process names mapped to names of hosting nodes in the order of adding processes.
+    pub proc_nodes: IndexMap<String, String>,
+    /// Named groups of processes.
+    pub groups: HashMap<String, Vec<String>>,
+    /// Identifies the topology contents, changes on every modification.
+    pub version: u64,
+}
+
+static TOPOLOGY_VERSION: AtomicU64 = AtomicU64::new(0);
+
+impl Topology {
+    /// Adds a process hosted on the node.
+    pub fn add_process(&mut self, proc: &str, node: &str) {
+        self.proc_nodes.insert(proc.to_string(), node.to_string());
+        self.update_version();
+    }
+
+    /// Removes all processes hosted on the node.
+    pub fn remove_node_processes(&mut self, node: &str) {
+        self.proc_nodes.retain(|_, proc_node| proc_node != node);
+        self.update_version();
+    }
+
+    /// Adds a named group of processes replacing the existing one.
+    pub fn add_group(&mut self, name: &str, procs: Vec<String>) {
+        self.groups.insert(name.to_string(), procs);
+        self.update_version();
+    }
+
+    fn update_version(&mut self) {
+        // versions are unique across all topologies, so equal versions always mean equal contents
+        self.version = TOPOLOGY_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+    }
+}
+
 /// Proxy for interaction of a process with the system.
 pub struct Context {
     proc_name: String,
+    node_name: String,
+    topology: Rc<RefCell<Topology>>,
     time: f64,
     rng: Box<dyn RandomProvider>,
     actions: Vec<ProcessEvent>,
+    lamport_time: u64,
+    vector_clock: VectorClock,
+    timers: HashMap<String, f64>,
+    next_call_id: u64,
+    current_call: Option<(String, u64)>,
 }
 
 trait RandomProvider {
@@ -43,9 +94,16 @@ impl Context {
         let time = sim_ctx.borrow().time() + clock_skew;
         Self {
             proc_name,
+            node_name: String::new(),
+            topology: Rc::default(),
             time,
             rng: Box::new(SimulationRng { sim_ctx }),
             actions: Vec::new(),
+            lamport_time: 0,
+            vector_clock: VectorClock::new(),
+            timers: HashMap::new(),
+            next_call_id: 0,
+            current_call: None,
         }
     }
 
@@ -53,17 +111,108 @@ impl Context {
     pub fn basic(proc_name: String, time: f64, clock_skew: f64, random_seed: u64) -> Self {
         Self {
             proc_name,
+            node_name: String::new(),
+            topology: Rc::default(),
             time: time + clock_skew,
             rng: Box::new(Pcg64::seed_from_u64(random_seed)),
             actions: Vec::new(),
+            lamport_time: 0,
+            vector_clock: VectorClock::new(),
+            timers: HashMap::new(),
+            next_call_id: 0,
+            current_call: None,
         }
     }
 
+    /// Returns the name of the process.
+    pub fn proc_name(&self) -> &str {
+        &self.proc_name
+    }
+
+    /// Returns the name of the node hosting the process.
+    pub fn node_name(&self) -> &str {
+        &self.node_name
+    }
+
+    /// Returns the names of all processes in the system in the order they were added, including this process.
+    pub fn processes(&self) -> Vec<String> {
+        self.topology.borrow().proc_nodes.keys().cloned().collect()
+    }
+
+    /// Returns the names of all other processes in the system.
+    pub fn peers(&self) -> Vec<String> {
+        let topology = self.topology.borrow();
+        topology
+            .proc_nodes
+            .keys()
+            .filter(|proc| **proc != self.proc_name)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the names of processes in the group (see [`System::add_group`](crate::System::add_group)).
+    ///
+    /// Returns an empty list if there is no such group.
+    pub fn group(&self, name: &str) -> Vec<String> {
+        self.topology.borrow().groups.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Returns the names of processes running on the same node, including this process.
+    pub fn node_processes(&self) -> Vec<String> {
+        let topology = self.topology.borrow();
+        topology
+            .proc_nodes
+            .iter()
+            .filter(|(_, node)| **node == self.node_name)
+            .map(|(proc, _)| proc.clone())
+            .collect()
+    }
+
+    /// Returns all named groups of processes.
+    pub(crate) fn groups(&self) -> HashMap<String, Vec<String>> {
+        self.topology.borrow().groups.clone()
+    }
+
+    /// Returns the version of the system topology, which changes whenever the topology is modified.
+    pub(crate) fn topology_version(&self) -> u64 {
+        self.topology.borrow().version
+    }
+
+    pub(crate) fn set_topology(&mut self, node_name: &str, topology: Rc<RefCell<Topology>>) {
+        self.node_name = node_name.to_string();
+        self.topology = topology;
+    }
+
     /// Returns the current time from the local node clock.
     pub fn time(&self) -> f64 {
         self.time
     }
 
+    /// Returns the current Lamport time of the process.
+    ///
+    /// Logical clocks are maintained only in simulation mode, in model checking mode zero is returned.
+    pub fn lamport_time(&self) -> u64 {
+        self.lamport_time
+    }
+
+    /// Returns the current vector clock of the process.
+    ///
+    /// Logical clocks are maintained only in simulation mode, in model checking mode an empty clock is returned.
+    pub fn vector_clock(&self) -> &VectorClock {
+        &self.vector_clock
+    }
+
+    pub(crate) fn set_logical_clocks(&mut self, lamport_time: u64, vector_clock: VectorClock) {
+        self.lamport_time = lamport_time;
+        self.vector_clock = vector_clock;
+    }
+
+    /// Sets the remaining delays of active process timers.
+    pub(crate) fn set_timers(&mut self, timers: impl Iterator<Item = (String, f64)>) {
+        // deadlines are stored in local time to account for the clock skew
+        self.timers = timers.map(|(name, delay)| (name, self.time + delay)).collect();
+    }
+
     /// Returns a random float in the range `[0, 1)`.
     pub fn rand(&mut self) -> f64 {
         self.rng.as_mut().rand()
@@ -79,6 +228,7 @@ impl Context {
             msg,
             src: self.proc_name.clone(),
             dst,
+            call: None,
         });
     }
 
@@ -94,6 +244,7 @@ impl Context {
     /// Sets a timer with overriding delay of existing active timer.
     pub fn set_timer(&mut self, name: &str, delay: f64) {
         assert!(name.len() <= 50, "Timer name length exceeds the limit of 50 characters");
+        self.timers.insert(name.to_string(), self.time + delay);
         self.actions.push(ProcessEvent::TimerSet {
             name: name.to_string(),
             delay,
@@ -104,6 +255,7 @@ impl Context {
     /// Sets a timer without overriding delay of existing active timer.
     pub fn set_timer_once(&mut self, name: &str, delay: f64) {
         assert!(name.len() <= 50, "Timer name length exceeds the limit of 50 characters");
+        self.timers.entry(name.to_string()).or_insert(self.time + delay);
         self.actions.push(ProcessEvent::TimerSet {
             name: name.to_string(),
             delay,
@@ -111,12 +263,166 @@ impl Context {
         });
     }
 
+    /// Sets a periodic timer which fires every `period` until cancelled, overriding existing active timer.
+    ///
+    /// If `jitter` is positive, each delay between firings is increased by a random value uniformly distributed
+    /// in `[0, jitter)`, which is drawn from the deterministic simulation RNG. In model checking mode, the timer is
+    /// re-armed with `period` delay a bounded number of times (see
+    /// [`McSystem::set_periodic_timer_limit`](crate::mc::McSystem::set_periodic_timer_limit)).
+    ///
+    /// The timer is re-armed before calling [`Process::on_timer`](crate::Process::on_timer), so it can be cancelled
+    /// or overridden by the handler.
+    pub fn set_periodic_timer(&mut self, name: &str, period: f64, jitter: f64) {
+        assert!(name.len() <= 50, "Timer name length exceeds the limit of 50 characters");
+        assert!(period > 0., "Timer period must be positive");
+        assert!(jitter >= 0., "Timer jitter must be non-negative");
+        let delay = if jitter > 0. {
+            period + jitter * self.rand()
+        } else {
+            period
+        };
+        self.timers.insert(name.to_string(), self.time + delay);
+        self.actions.push(ProcessEvent::TimerSet {
+            name: name.to_string(),
+            delay,
+            behavior: TimerBehavior::Periodic { period, jitter },
+        });
+    }
+
+    /// Sends a request to a process and returns the id of the call used to match the response.
+    ///
+    /// The response sent by the called process with [`Self::reply`] is passed to
+    /// [`Process::on_response`](crate::Process::on_response). If the response is not received within `timeout`,
+    /// [`Process::on_call_timeout`](crate::Process::on_call_timeout) is called instead and the late response is
+    /// ignored. The timeout is implemented with a timer named `call-timeout#<call id>`.
+    ///
+    /// The pending calls are a part of the process state, so they are restored along with it in model checking.
+    pub fn call(&mut self, dst: &str, msg: Message, timeout: f64) -> u64 {
+        assert!(
+            msg.tip.len() <= 50,
+            "Message type length exceeds the limit of 50 characters"
+        );
+        let call_id = self.next_call_id;
+        self.next_call_id += 1;
+        self.actions.push(ProcessEvent::CallStarted {
+            call_id,
+            dst: dst.to_string(),
+            msg: msg.clone(),
+        });
+        self.actions.push(ProcessEvent::MessageSent {
+            msg,
+            src: self.proc_name.clone(),
+            dst: dst.to_string(),
+            call: Some(CallTag::Request(call_id)),
+        });
+        self.set_timer(&rpc::timeout_timer(call_id), timeout);
+        call_id
+    }
+
+    /// Returns the id of the call if the message handled by [`Process::on_message`](crate::Process::on_message)
+    /// is a request sent with [`Self::call`].
+    pub fn current_call(&self) -> Option<u64> {
+        self.current_call.as_ref().map(|(_, call_id)| *call_id)
+    }
+
+    /// Sends a response to the request handled by [`Process::on_message`](crate::Process::on_message).
+    ///
+    /// Panics if the handled message is not a request sent with [`Self::call`].
+    pub fn reply(&mut self, msg: Message) {
+        let (dst, call_id) = self
+            .current_call
+            .clone()
+            .expect("Reply can only be sent when handling a request");
+        self.reply_to(&dst, call_id, msg);
+    }
+
+    /// Sends a response to the call with the specified id made by `dst` process.
+    ///
+    /// Can be used to reply to the request later, e.g. after receiving other messages.
+    pub fn reply_to(&mut self, dst: &str, call_id: u64, msg: Message) {
+        assert!(
+            msg.tip.len() <= 50,
+            "Message type length exceeds the limit of 50 characters"
+        );
+        self.actions.push(ProcessEvent::MessageSent {
+            msg,
+            src: self.proc_name.clone(),
+            dst: dst.to_string(),
+            call: Some(CallTag::Response(call_id)),
+        });
+    }
+
+    pub(crate) fn set_next_call_id(&mut self, call_id: u64) {
+        self.next_call_id = call_id;
+    }
+
+    pub(crate) fn set_current_call(&mut self, from: &str, call_id: u64) {
+        self.current_call = Some((from.to_string(), call_id));
+    }
+
+    /// Returns true if the timer with the specified name is active, i.e. is set and has not fired yet.
+    pub fn is_timer_set(&self, name: &str) -> bool {
+        self.timers.contains_key(name)
+    }
+
+    /// Returns the remaining time until the timer with the specified name fires, or `None` if it is not active.
+    ///
+    /// In model checking mode, the time is not modeled precisely, so the returned value is approximate.
+    pub fn timer_remaining(&self, name: &str) -> Option<f64> {
+        self.timers.get(name).map(|deadline| (deadline - self.time).max(0.))
+    }
+
+    /// Returns the remaining delays of active timers.
+    pub(crate) fn timers_remaining(&self) -> HashMap<String, f64> {
+        self.timers
+            .iter()
+            .map(|(name, deadline)| (name.clone(), (deadline - self.time).max(0.)))
+            .collect()
+    }
+
     /// Cancels a timer.
     pub fn cancel_timer(&mut self, name: &str) {
+        self.timers.remove(name);
         self.actions
             .push(ProcessEvent::TimerCancelled { name: name.to_string() });
     }
 
+    /// Records a user-defined event in the trace.
+    ///
+    /// The event is logged as [`LogEntry::UserEvent`](crate::logger::LogEntry::UserEvent) in simulation mode
+    /// and as [`LogEntry::McUserEvent`](crate::logger::LogEntry::McUserEvent) in model checking mode, so it can be
+    /// used to mark important milestones such as electing a leader or committing a value.
+    pub fn log(&mut self, kind: &str, data: &str) {
+        assert!(kind.len() <= 50, "Event kind length exceeds the limit of 50 characters");
+        self.actions.push(ProcessEvent::UserEvent {
+            kind: kind.to_string(),
+            data: data.to_string(),
+        });
+    }
+
+    /// Increments the process counter with the specified name by a non-negative value.
+    ///
+    /// The counter is stored in the system [metrics registry](crate::metrics) under the name prefixed with `user_`
+    /// with `node` and `proc` labels. Updating the gauge with the same name fails the process handler.
+    /// Metrics are collected only in simulation mode and are ignored in model checking mode.
+    pub fn increment_counter(&mut self, name: &str, delta: f64) {
+        assert!(delta >= 0., "Counter can not be decreased");
+        self.actions.push(ProcessEvent::MetricUpdated {
+            name: name.to_string(),
+            update: MetricUpdate::Increment(delta),
+        });
+    }
+
+    /// Sets the value of the process gauge with the specified name.
+    ///
+    /// The gauge is stored in the same way as counters (see [`Context::increment_counter`]).
+    pub fn set_gauge(&mut self, name: &str, value: f64) {
+        self.actions.push(ProcessEvent::MetricUpdated {
+            name: name.to_string(),
+            update: MetricUpdate::Set(value),
+        });
+    }
+
     pub(crate) fn actions(&mut self) -> Vec<ProcessEvent> {
         self.actions.drain(..).collect()
     }
@@ -0,0 +1,191 @@
+//! Errors raised by processes during the simulation.
+
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::hooks::Interrupted;
+use crate::logger::LogEntry;
+use crate::Message;
+
+/// Event which was handled by the process when it failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HandledEvent {
+    /// Process start (see [`Process::on_start`](crate::Process::on_start)).
+    Start,
+    /// Local message from the user.
+    LocalMessage {
+        /// Received message.
+        msg: Message,
+    },
+    /// Message from another process.
+    Message {
+        /// Received message.
+        msg: Message,
+        /// Name of the sender process.
+        from: String,
+    },
+    /// Timer firing.
+    Timer {
+        /// Timer name.
+        name: String,
+    },
+    /// Reading the process state.
+    GetState,
+    /// Restoring the process state.
+    SetState,
+}
+
+impl Display for HandledEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandledEvent::Start => write!(f, "start"),
+            HandledEvent::LocalMessage { msg } => write!(f, "local message {msg:?}"),
+            HandledEvent::Message { msg, from } => write!(f, "message {msg:?} from {from}"),
+            HandledEvent::Timer { name } => write!(f, "timer {name}"),
+            HandledEvent::GetState => write!(f, "state reading"),
+            HandledEvent::SetState => write!(f, "state restoring"),
+        }
+    }
+}
+
+/// Error returned by a process handler or a panic caught in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessError {
+    /// Name of the failed process.
+    pub proc: String,
+    /// Name of the node hosting the process.
+    pub node: String,
+    /// Event handled by the process.
+    pub event: HandledEvent,
+    /// Error returned by the process or the panic message.
+    pub error: String,
+    /// Whether the process handler panicked instead of returning an error.
+    pub panicked: bool,
+    /// Simulation time of the failure.
+    pub time: f64,
+    /// Execution trace up to the failure (empty if the in-memory trace is disabled).
+    ///
+    /// For errors returned by [`System::process_errors`](crate::System::process_errors),
+    /// only the events which are still kept in memory are included (see `LoggerConfig::trace_limit`).
+    pub trace: Vec<LogEntry>,
+}
+
+impl Display for ProcessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "process '{}' on node '{}' {} at {:.3} when handling {}: {}",
+            self.proc,
+            self.node,
+            if self.panicked { "panicked" } else { "failed" },
+            self.time,
+            self.event,
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Violation of a system invariant checked during the simulation (see [`System::add_invariant`](crate::System::add_invariant)).
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvariantViolation {
+    /// Name of the violated invariant.
+    pub invariant: String,
+    /// Error returned by the invariant.
+    pub error: String,
+    /// Simulation time of the violation.
+    pub time: f64,
+    /// Simulation time of the last successful invariant check.
+    pub checkpoint_time: f64,
+    /// Execution trace since the last successful invariant check (empty if the in-memory trace is disabled).
+    pub trace: Vec<LogEntry>,
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invariant '{}' violated at {:.3} (last checked at {:.3}): {}",
+            self.invariant, self.time, self.checkpoint_time, self.error
+        )
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Error which stops the simulation, returned by `System::try_step*` methods.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepError {
+    /// Process handler failed with [`ProcessErrorPolicy::Fail`].
+    Process(ProcessError),
+    /// System invariant is violated.
+    InvariantViolation(InvariantViolation),
+}
+
+impl Display for StepError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Process(err) => err.fmt(f),
+            Self::InvariantViolation(violation) => violation.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// Specifies how the simulation handles process errors.
+///
+/// Panics in process handlers are caught and handled in the same way as returned errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ProcessErrorPolicy {
+    /// Stop the simulation.
+    ///
+    /// The error is returned by `System::try_step*` methods as [`StepError::Process`]
+    /// and causes a panic in other methods.
+    #[default]
+    Fail,
+    /// Crash the failed process and keep the simulation running.
+    ///
+    /// The crashed process does not receive any events, its pending timers are cancelled.
+    /// The errors can be examined with [`System::process_errors`](crate::System::process_errors).
+    CrashProcess,
+}
+
+/// Process errors shared by the system and its nodes.
+#[derive(Default)]
+pub(crate) struct ProcessErrors {
+    pub policy: ProcessErrorPolicy,
+    /// Error which stopped the simulation, taken by the system after the simulation step.
+    pub pending: Option<ProcessError>,
+    /// All errors without traces along with the trace positions after the failures.
+    pub errors: Vec<(ProcessError, u64)>,
+}
+
+/// Failure of a process handler.
+pub(crate) enum HandlerFailure {
+    Error(String),
+    Panic(String),
+}
+
+/// Calls the process handler catching a panic in it.
+pub(crate) fn call_handler<R>(f: impl FnOnce() -> Result<R, String>) -> Result<R, HandlerFailure> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res.map_err(HandlerFailure::Error),
+        // interruptions of the test are not process failures
+        Err(payload) if payload.is::<Interrupted>() => panic::resume_unwind(payload),
+        Err(payload) => Err(HandlerFailure::Panic(
+            panic_message(&*payload).unwrap_or_else(|| "unknown panic".to_string()),
+        )),
+    }
+}
+
+/// Returns the message of the caught panic if the payload is a string.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some(message.to_string())
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
+}
@@ -2,6 +2,8 @@
 
 use serde::Serialize;
 
+use crate::causality::VectorClock;
+use crate::rpc::CallTag;
 use crate::Message;
 
 /// Message is received.
@@ -19,6 +21,12 @@ pub struct MessageReceived {
     pub dst: String,
     /// Name of destination node.
     pub dst_node: String,
+    /// Lamport time of sender process.
+    pub lamport_time: u64,
+    /// Vector clock of sender process.
+    pub vector_clock: VectorClock,
+    /// Role of the message in a request/response call.
+    pub call: Option<CallTag>,
 }
 
 /// Timer is fired.
@@ -0,0 +1,262 @@
+//! Checkers for consistency models weaker than linearizability.
+//!
+//! Each checker searches for sequential orders of operations which are valid according to the sequential model and
+//! respect the ordering constraints of the consistency model:
+//!
+//! - [sequential consistency](check_sequential_consistency) requires a single order of all operations which respects
+//!   the order of operations of each client (session order), but not the real-time order;
+//! - [causal consistency](check_causal_consistency) requires, for each client, an order of its operations and all
+//!   updates which respects the causal order of operations derived from the trace;
+//! - [session guarantees](check_session_guarantees) require, for each client, an order of its operations and all
+//!   updates which respects the session order of operations as specified by the checked guarantees.
+//!
+//! Reads and updates are distinguished by [`Model::is_read_only`]. Pending operations may take effect or be omitted.
+//! If the history violates the consistency model, the checkers return an error with a minimal set of conflicting
+//! completed operations and the client sessions involved, in the same sense as [`check_linearizability`].
+//!
+//! [`check_linearizability`]: crate::history::check_linearizability
+
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::causality::CausalityGraph;
+use crate::history::search::{check_view, View};
+use crate::history::{format_ops, History, Model};
+
+/// Describes a violation of consistency model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsistencyError {
+    ops: Vec<usize>,
+    clients: Vec<String>,
+    message: String,
+}
+
+impl ConsistencyError {
+    /// Returns the indices of operations forming a minimal set of conflicting operations.
+    pub fn ops(&self) -> &[usize] {
+        &self.ops
+    }
+
+    /// Returns the names of clients whose sessions are involved in the violation.
+    ///
+    /// For per-client checks the client whose session is violated goes first.
+    pub fn clients(&self) -> &[String] {
+        &self.clients
+    }
+
+    /// Returns the error description with the listing of conflicting operations.
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl Display for ConsistencyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Session guarantee for operations of a single client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SessionGuarantee {
+    /// Reads observe the preceding updates of the same client.
+    ReadYourWrites,
+    /// Reads observe the updates observed by the preceding reads of the same client.
+    MonotonicReads,
+    /// Updates of a client are observed in the order they were performed.
+    MonotonicWrites,
+    /// Updates of a client are observed after the updates observed by the preceding reads of this client.
+    WritesFollowReads,
+}
+
+impl Display for SessionGuarantee {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionGuarantee::ReadYourWrites => write!(f, "read-your-writes"),
+            SessionGuarantee::MonotonicReads => write!(f, "monotonic reads"),
+            SessionGuarantee::MonotonicWrites => write!(f, "monotonic writes"),
+            SessionGuarantee::WritesFollowReads => write!(f, "writes-follow-reads"),
+        }
+    }
+}
+
+/// Returns the names of clients in the order of their first operations.
+fn clients<I, O>(history: &History<I, O>) -> Vec<&str> {
+    let mut clients: Vec<&str> = Vec::new();
+    for op in history.ops() {
+        if !clients.contains(&op.client.as_str()) {
+            clients.push(&op.client);
+        }
+    }
+    clients
+}
+
+fn error<I, O>(history: &History<I, O>, ops: Vec<usize>, client: Option<&str>, message: String) -> ConsistencyError
+where
+    I: Debug,
+    O: Debug,
+{
+    let mut clients: Vec<String> = client.into_iter().map(|c| c.to_string()).collect();
+    for op in &ops {
+        let client = &history.ops()[*op].client;
+        if !clients.contains(client) {
+            clients.push(client.clone());
+        }
+    }
+    let message = format!("{message}:\n{}", format_ops(history, &ops));
+    ConsistencyError { ops, clients, message }
+}
+
+/// Checks the history for sequential consistency against the sequential model.
+///
+/// Returns the found order of operations as a sequence of operation indices. Pending operations which are omitted
+/// in the order are not included.
+pub fn check_sequential_consistency<M>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+) -> Result<Vec<usize>, ConsistencyError>
+where
+    M: Model,
+    M::Input: Debug,
+    M::Output: Debug,
+{
+    let ops = history.ops();
+    let view = View {
+        ops: (0..ops.len()).collect(),
+        checked: &|_| true,
+        before: &|a, b| a < b && ops[a].client == ops[b].client,
+    };
+    check_view(model, history, &view).map_err(|ops| {
+        let message = "history is not sequentially consistent, conflicting operations".to_string();
+        error(history, ops, None, message)
+    })
+}
+
+/// Checks the history for causal consistency against the sequential model.
+///
+/// An operation causally precedes another operation if it precedes it in the same client session, or if its response
+/// happened before the invocation of another operation according to the happens-before graph of the trace (e.g.
+/// a client notified another client after completing the operation), or transitively. The history is causally
+/// consistent if for each client there is an order of its operations and updates of other clients which respects
+/// the causal order. Outputs of updates of other clients are not checked.
+///
+/// The graph must be built from the same trace as the history, so that the trace indices of operations refer to its
+/// events (see [`History::from_trace`]). For operations without trace indices only the session order is used.
+pub fn check_causal_consistency<M>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+    graph: &CausalityGraph,
+) -> Result<(), ConsistencyError>
+where
+    M: Model,
+    M::Input: Debug,
+    M::Output: Debug,
+{
+    let ops = history.ops();
+    let n = ops.len();
+    let direct = |a: usize, b: usize| {
+        if a == b || ops[a].is_pending() {
+            return false;
+        }
+        if ops[a].client == ops[b].client {
+            return a < b;
+        }
+        match (ops[a].ret_index, ops[b].call_index) {
+            (Some(ret), Some(call)) => graph.happens_before(ret, call),
+            _ => false,
+        }
+    };
+    // direct predecessors precede the operation in this order, so the transitive closure is built in a single pass
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| {
+        ops[*a]
+            .call
+            .total_cmp(&ops[*b].call)
+            .then(ops[*a].call_index.cmp(&ops[*b].call_index))
+            .then(a.cmp(b))
+    });
+    let mut preds = vec![vec![false; n]; n];
+    for (i, b) in order.iter().enumerate() {
+        for a in order[..i].iter() {
+            if direct(*a, *b) {
+                let transitive = preds[*a].clone();
+                for (pred, is_pred) in preds[*b].iter_mut().zip(transitive) {
+                    *pred |= is_pred;
+                }
+                preds[*b][*a] = true;
+            }
+        }
+    }
+
+    for client in clients(history) {
+        let view = View {
+            ops: (0..n)
+                .filter(|op| ops[*op].client == client || !model.is_read_only(&ops[*op].input))
+                .collect(),
+            checked: &|op| ops[op].client == client,
+            before: &|a, b| preds[b][a],
+        };
+        if let Err(ops) = check_view(model, history, &view) {
+            let message = format!(
+                "history is not causally consistent, operations in session of client {client} can not be ordered"
+            );
+            return Err(error(history, ops, Some(client), message));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the history for the session guarantees against the sequential model.
+///
+/// The guarantees hold if for each client there is an order of its operations and updates of other clients which
+/// respects the session order as follows:
+///
+/// - [`ReadYourWrites`](SessionGuarantee::ReadYourWrites): updates of the client precede its subsequent reads;
+/// - [`MonotonicReads`](SessionGuarantee::MonotonicReads): reads of the client are ordered as in the session;
+/// - [`MonotonicWrites`](SessionGuarantee::MonotonicWrites): updates of each client are ordered as in its session;
+/// - [`WritesFollowReads`](SessionGuarantee::WritesFollowReads): reads of each client precede its subsequent updates,
+///   reads of other clients are also included in the order and their outputs are checked.
+///
+/// Outputs of updates of other clients are not checked. The guarantees can be checked separately or together.
+pub fn check_session_guarantees<M>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+    guarantees: &[SessionGuarantee],
+) -> Result<(), ConsistencyError>
+where
+    M: Model,
+    M::Input: Debug,
+    M::Output: Debug,
+{
+    let ops = history.ops();
+    let read_only: Vec<bool> = ops.iter().map(|op| model.is_read_only(&op.input)).collect();
+    let wfr = guarantees.contains(&SessionGuarantee::WritesFollowReads);
+
+    for client in clients(history) {
+        let own = |op: usize| ops[op].client == client;
+        let before = |a: usize, b: usize| {
+            if a >= b || ops[a].client != ops[b].client {
+                return false;
+            }
+            guarantees.iter().any(|guarantee| match guarantee {
+                SessionGuarantee::ReadYourWrites => own(a) && !read_only[a] && read_only[b],
+                SessionGuarantee::MonotonicReads => own(a) && read_only[a] && read_only[b],
+                SessionGuarantee::MonotonicWrites => !read_only[a] && !read_only[b],
+                SessionGuarantee::WritesFollowReads => read_only[a] && !read_only[b],
+            })
+        };
+        let view = View {
+            ops: (0..ops.len()).filter(|op| own(*op) || !read_only[*op] || wfr).collect(),
+            checked: &|op| own(op) || read_only[op],
+            before: &before,
+        };
+        if let Err(ops) = check_view(model, history, &view) {
+            let guarantees: Vec<String> = guarantees.iter().map(|g| g.to_string()).collect();
+            let message = format!(
+                "session guarantees ({}) are violated in session of client {client}, conflicting operations",
+                guarantees.join(", ")
+            );
+            return Err(error(history, ops, Some(client), message));
+        }
+    }
+    Ok(())
+}
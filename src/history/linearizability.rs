@@ -0,0 +1,142 @@
+//! Linearizability checker.
+//!
+//! The checker searches for a linearization of the history, i.e. a sequential order of operations which respects
+//! the real-time order (an operation which completed before the invocation of another operation goes first) and is
+//! valid according to the sequential model. Pending operations may be linearized at any point after their invocation
+//! or omitted.
+//!
+//! Linearizability is local: a history is linearizable if and only if its sub-histories for each partition of
+//! independent operations (see [`Model::partition`]) are linearizable. So the checker searches for a linearization
+//! of each partition separately and merges them into the linearization of the whole history.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::history::search::{check_view, View};
+use crate::history::{format_ops, History, Model};
+
+/// Describes a non-linearizable history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearizabilityError {
+    ops: Vec<usize>,
+    message: String,
+}
+
+impl LinearizabilityError {
+    /// Returns the indices of operations forming a minimal non-linearizable sub-history.
+    ///
+    /// The sub-history is minimal in the sense that removing any of its operations makes it linearizable.
+    pub fn ops(&self) -> &[usize] {
+        &self.ops
+    }
+
+    /// Returns the error description with the listing of sub-history operations.
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl Display for LinearizabilityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Checks the history for linearizability against the sequential model.
+///
+/// Returns the found linearization as a sequence of operation indices. Pending operations which are omitted
+/// in the linearization are not included.
+///
+/// If the history is not linearizable, returns an error describing a minimal non-linearizable sub-history. It is
+/// a minimal set of completed operations which can not be linearized together, provided that other operations
+/// may or may not take effect (as if they were pending). Any operation of this set can be relaxed in this way
+/// to make the history linearizable. The operations of this set belong to the same partition.
+pub fn check_linearizability<M>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+) -> Result<Vec<usize>, LinearizabilityError>
+where
+    M: Model,
+    M::Input: Debug,
+    M::Output: Debug,
+{
+    let ops = history.ops();
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    let mut partition_ids = HashMap::new();
+    for (i, op) in ops.iter().enumerate() {
+        let id = *partition_ids.entry(model.partition(&op.input)).or_insert_with(|| {
+            partitions.push(Vec::new());
+            partitions.len() - 1
+        });
+        partitions[id].push(i);
+    }
+
+    let mut orders = Vec::with_capacity(partitions.len());
+    for partition in partitions {
+        let view = View {
+            ops: partition,
+            checked: &|_| true,
+            before: &|a, b| ops[a].ret.is_some_and(|ret| ret < ops[b].call),
+        };
+        match check_view(model, history, &view) {
+            Ok(order) => orders.push(order),
+            Err(ops) => return Err(error(history, ops)),
+        }
+    }
+    Ok(merge_orders(history, orders))
+}
+
+fn error<I: Debug, O: Debug>(history: &History<I, O>, ops: Vec<usize>) -> LinearizabilityError {
+    let message = format!(
+        "history is not linearizable, minimal non-linearizable sub-history:\n{}",
+        format_ops(history, &ops)
+    );
+    LinearizabilityError { ops, message }
+}
+
+/// Merges linearizations of partitions into a linearization of the whole history.
+///
+/// Repeatedly takes the first remaining operation of some partition which is not preceded in real time by any other
+/// remaining operation. By locality of linearizability such operation always exists.
+fn merge_orders<I, O>(history: &History<I, O>, orders: Vec<Vec<usize>>) -> Vec<usize> {
+    if orders.len() == 1 {
+        return orders.into_iter().next().unwrap();
+    }
+    let ops = history.ops();
+    // completed operations of linearizations ordered by response time
+    let mut by_ret: Vec<usize> = orders
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|op| !ops[*op].is_pending())
+        .collect();
+    by_ret.sort_by(|a, b| ops[*a].ret.unwrap().total_cmp(&ops[*b].ret.unwrap()));
+    let total = orders.iter().map(|order| order.len()).sum();
+    let mut placed = vec![false; ops.len()];
+    let mut first_remaining = 0;
+    let mut heads = vec![0; orders.len()];
+    let mut result = Vec::with_capacity(total);
+
+    while result.len() < total {
+        while first_remaining < by_ret.len() && placed[by_ret[first_remaining]] {
+            first_remaining += 1;
+        }
+        let next = (0..orders.len())
+            .filter(|p| heads[*p] < orders[*p].len())
+            .find(|p| {
+                let op = orders[*p][heads[*p]];
+                // the earliest response among other remaining operations must not precede the invocation
+                let earliest = by_ret[first_remaining..]
+                    .iter()
+                    .find(|other| !placed[**other] && **other != op)
+                    .map(|other| ops[*other].ret.unwrap());
+                earliest.is_none_or(|ret| ret >= ops[op].call)
+            })
+            .expect("linearizations of partitions should be compatible with real-time order");
+        let op = orders[next][heads[next]];
+        heads[next] += 1;
+        placed[op] = true;
+        result.push(op);
+    }
+    result
+}
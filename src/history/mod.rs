@@ -0,0 +1,270 @@
+//! Recording and checking of client operation histories.
+//!
+//! A history consists of operations performed by clients. Each operation has an input specified in the invocation,
+//! an output returned in the response and the times of invocation and response. Histories can be recorded from a
+//! system trace by mapping local messages received by client processes to operation invocations and local messages
+//! sent by these processes to responses (see [`History::from_trace`] and [`HistoryRecorder`]), or built manually.
+//!
+//! Recorded histories can be checked against a sequential [`Model`] for linearizability (see [`check_linearizability`])
+//! or weaker consistency models: sequential consistency, causal consistency and session guarantees
+//! (see [`consistency`]).
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::logger::{LogEntry, LogSink};
+use crate::Message;
+
+pub mod consistency;
+pub mod linearizability;
+pub mod model;
+mod search;
+
+pub use consistency::{
+    check_causal_consistency, check_sequential_consistency, check_session_guarantees, ConsistencyError,
+    SessionGuarantee,
+};
+pub use linearizability::{check_linearizability, LinearizabilityError};
+pub use model::{KvOp, KvStore, Model, Queue, QueueOp, Register, RegisterOp};
+
+/// Operation performed by a client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation<I, O> {
+    /// Name of client (process) which performed the operation.
+    pub client: String,
+    /// Operation input.
+    pub input: I,
+    /// Operation output, `None` if the operation is pending (response is not received).
+    pub output: Option<O>,
+    /// Time of invocation.
+    pub call: f64,
+    /// Time of response, `None` if the operation is pending.
+    pub ret: Option<f64>,
+    /// Index of invocation event in the trace (if recorded from a trace).
+    pub call_index: Option<usize>,
+    /// Index of response event in the trace (if recorded from a trace).
+    pub ret_index: Option<usize>,
+}
+
+impl<I, O> Operation<I, O> {
+    /// Returns true if the response is not received.
+    pub fn is_pending(&self) -> bool {
+        self.ret.is_none()
+    }
+}
+
+/// History of client operations.
+///
+/// Each client is assumed to have at most one pending operation at a time. If a client invokes a new operation
+/// before receiving the response for the previous one, the responses are matched with the operations in the order
+/// of invocation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct History<I, O> {
+    ops: Vec<Operation<I, O>>,
+    pending: HashMap<String, VecDeque<usize>>,
+}
+
+impl<I, O> Default for History<I, O> {
+    fn default() -> Self {
+        Self {
+            ops: Vec::new(),
+            pending: HashMap::new(),
+        }
+    }
+}
+
+impl<I, O> History<I, O> {
+    /// Creates an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an operation invocation, returns the operation index.
+    pub fn invoke(&mut self, client: &str, input: I, time: f64) -> usize {
+        self.invoke_at(client, input, time, None)
+    }
+
+    /// Records a response for the earliest pending operation of the client.
+    ///
+    /// Returns the operation index or `None` if there is no pending operation.
+    pub fn respond(&mut self, client: &str, output: O, time: f64) -> Option<usize> {
+        self.respond_at(client, output, time, None)
+    }
+
+    /// Adds a completed operation, returns the operation index.
+    pub fn add(&mut self, client: &str, input: I, output: O, call: f64, ret: f64) -> usize {
+        self.ops.push(Operation {
+            client: client.to_string(),
+            input,
+            output: Some(output),
+            call,
+            ret: Some(ret),
+            call_index: None,
+            ret_index: None,
+        });
+        self.ops.len() - 1
+    }
+
+    fn invoke_at(&mut self, client: &str, input: I, time: f64, index: Option<usize>) -> usize {
+        self.ops.push(Operation {
+            client: client.to_string(),
+            input,
+            output: None,
+            call: time,
+            ret: None,
+            call_index: index,
+            ret_index: None,
+        });
+        let op = self.ops.len() - 1;
+        self.pending.entry(client.to_string()).or_default().push_back(op);
+        op
+    }
+
+    fn respond_at(&mut self, client: &str, output: O, time: f64, index: Option<usize>) -> Option<usize> {
+        let op = self.pending.get_mut(client)?.pop_front()?;
+        let entry = &mut self.ops[op];
+        entry.output = Some(output);
+        entry.ret = Some(time);
+        entry.ret_index = index;
+        Some(op)
+    }
+
+    /// Returns the recorded operations.
+    pub fn ops(&self) -> &[Operation<I, O>] {
+        &self.ops
+    }
+
+    /// Returns the number of operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns true if the history is empty.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Processes a trace entry with the specified index.
+    fn record<FI, FO>(&mut self, index: usize, entry: &LogEntry, invocation: FI, response: FO)
+    where
+        FI: Fn(&Message) -> Option<I>,
+        FO: Fn(&Message) -> Option<O>,
+    {
+        // model checking entries are not timestamped, so the trace index is used as a time
+        let time = entry.time().unwrap_or(index as f64);
+        match entry {
+            LogEntry::LocalMessageReceived { proc, msg, .. } | LogEntry::McLocalMessageReceived { proc, msg } => {
+                if let Some(input) = invocation(msg) {
+                    self.invoke_at(proc, input, time, Some(index));
+                }
+            }
+            LogEntry::LocalMessageSent { proc, msg, .. } | LogEntry::McLocalMessageSent { proc, msg } => {
+                if let Some(output) = response(msg) {
+                    self.respond_at(proc, output, time, Some(index));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Builds a history from a trace.
+    ///
+    /// Local messages received by processes are mapped to operation invocations by the `invocation` function,
+    /// local messages sent by processes are mapped to responses by the `response` function. Messages for which
+    /// the functions return `None` are ignored. For model checking traces the entry index is used as a time.
+    pub fn from_trace<FI, FO>(trace: &[LogEntry], invocation: FI, response: FO) -> Self
+    where
+        FI: Fn(&Message) -> Option<I>,
+        FO: Fn(&Message) -> Option<O>,
+    {
+        let mut history = Self::new();
+        for (index, entry) in trace.iter().enumerate() {
+            history.record(index, entry, &invocation, &response);
+        }
+        history
+    }
+}
+
+impl<I: Clone, O: Clone> History<I, O> {
+    /// Returns a history consisting of operations with the specified indices.
+    pub fn subset(&self, ops: &[usize]) -> Self {
+        let mut history = Self::new();
+        for op in ops {
+            history.ops.push(self.ops[*op].clone());
+        }
+        history
+    }
+}
+
+type MessageMapping<T> = Box<dyn Fn(&Message) -> Option<T>>;
+
+/// Records a history from logged events as a [`LogSink`].
+///
+/// Allows to record the history without keeping the whole trace in memory. Trace indices of operations are counted
+/// from the first event received by the recorder.
+///
+/// ```ignore
+/// let recorder = HistoryRecorder::new(parse_request, parse_response);
+/// let history = recorder.history();
+/// sys.logger().add_sink(Box::new(recorder));
+/// ```
+pub struct HistoryRecorder<I, O> {
+    history: Rc<RefCell<History<I, O>>>,
+    invocation: MessageMapping<I>,
+    response: MessageMapping<O>,
+    index: usize,
+}
+
+impl<I, O> HistoryRecorder<I, O> {
+    /// Creates a recorder with the specified mapping functions (see [`History::from_trace`]).
+    pub fn new<FI, FO>(invocation: FI, response: FO) -> Self
+    where
+        FI: Fn(&Message) -> Option<I> + 'static,
+        FO: Fn(&Message) -> Option<O> + 'static,
+    {
+        Self {
+            history: Rc::new(RefCell::new(History::new())),
+            invocation: Box::new(invocation),
+            response: Box::new(response),
+            index: 0,
+        }
+    }
+
+    /// Returns a shared reference to the recorded history.
+    pub fn history(&self) -> Rc<RefCell<History<I, O>>> {
+        self.history.clone()
+    }
+}
+
+impl<I, O> LogSink for HistoryRecorder<I, O> {
+    fn write(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        self.history
+            .borrow_mut()
+            .record(self.index, entry, &self.invocation, &self.response);
+        self.index += 1;
+        Ok(())
+    }
+}
+
+/// Formats the operations for error reports.
+pub(crate) fn format_ops<I: Debug, O: Debug>(history: &History<I, O>, ops: &[usize]) -> String {
+    let mut lines = Vec::new();
+    for op in ops {
+        let entry = &history.ops[*op];
+        let output = match &entry.output {
+            Some(output) => format!("{output:?}"),
+            None => "?".to_string(),
+        };
+        let ret = match entry.ret {
+            Some(ret) => format!("{ret:.3}"),
+            None => "-".to_string(),
+        };
+        lines.push(format!(
+            "  #{op} [{:.3}, {ret}] {}: {:?} -> {output}",
+            entry.call, entry.client, entry.input
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,220 @@
+//! Sequential specifications of data types.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Sequential specification of a data type used to check histories.
+pub trait Model {
+    /// Model state.
+    type State: Clone + Eq + Hash;
+    /// Operation input.
+    type Input;
+    /// Operation output.
+    type Output;
+
+    /// Returns the initial state.
+    fn init(&self) -> Self::State;
+
+    /// Applies the operation to the state.
+    ///
+    /// Returns the new state if the operation can produce the specified output in this state, and `None` otherwise.
+    /// Output is `None` for pending operations, in this case any output is allowed.
+    fn step(&self, state: &Self::State, input: &Self::Input, output: Option<&Self::Output>) -> Option<Self::State>;
+
+    /// Returns true if the operation does not modify the state.
+    ///
+    /// Used by the checkers of weaker consistency models to distinguish reads from updates,
+    /// by default all operations are treated as updates.
+    fn is_read_only(&self, _input: &Self::Input) -> bool {
+        false
+    }
+
+    /// Returns the partition of the operation.
+    ///
+    /// Operations from different partitions must be independent, i.e. access disjoint parts of the state, such as
+    /// different keys of a key-value store. The linearizability checker checks each partition separately, which
+    /// is much faster for large histories. Using the same partition for independent operations is allowed but
+    /// gives no speedup. By default all operations belong to a single partition.
+    fn partition(&self, _input: &Self::Input) -> u64 {
+        0
+    }
+}
+
+/// Register operation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RegisterOp<V> {
+    /// Reads the value, the output is the read value.
+    Read,
+    /// Writes the value, the output is ignored.
+    Write(V),
+}
+
+/// Read-write register.
+///
+/// Operation output is `Some(value)` for reads and is ignored for writes.
+pub struct Register<V> {
+    initial: V,
+}
+
+impl<V> Register<V> {
+    /// Creates a register with the specified initial value.
+    pub fn new(initial: V) -> Self {
+        Self { initial }
+    }
+}
+
+impl<V: Clone + Eq + Hash> Model for Register<V> {
+    type State = V;
+    type Input = RegisterOp<V>;
+    type Output = Option<V>;
+
+    fn init(&self) -> V {
+        self.initial.clone()
+    }
+
+    fn step(&self, state: &V, input: &RegisterOp<V>, output: Option<&Option<V>>) -> Option<V> {
+        match input {
+            RegisterOp::Read => match output {
+                None => Some(state.clone()),
+                Some(Some(value)) if value == state => Some(state.clone()),
+                _ => None,
+            },
+            RegisterOp::Write(value) => Some(value.clone()),
+        }
+    }
+
+    fn is_read_only(&self, input: &RegisterOp<V>) -> bool {
+        matches!(input, RegisterOp::Read)
+    }
+}
+
+/// Key-value store operation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KvOp<K, V> {
+    /// Reads the value of key, the output is the value or `None` if the key is missing.
+    Get(K),
+    /// Writes the value of key, the output is ignored.
+    Put(K, V),
+    /// Deletes the key, the output is ignored.
+    Delete(K),
+}
+
+/// Key-value store with initially empty state.
+///
+/// Operations are partitioned by key. Operation output is the read value for `Get` and is ignored for other operations.
+#[derive(Default)]
+pub struct KvStore<K, V> {
+    initial: BTreeMap<K, V>,
+}
+
+impl<K: Ord, V> KvStore<K, V> {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self {
+            initial: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a store with the specified initial contents.
+    pub fn with_data(data: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self {
+            initial: data.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Clone + Ord + Hash, V: Clone + Eq + Hash> Model for KvStore<K, V> {
+    type State = BTreeMap<K, V>;
+    type Input = KvOp<K, V>;
+    type Output = Option<V>;
+
+    fn init(&self) -> Self::State {
+        self.initial.clone()
+    }
+
+    fn step(&self, state: &Self::State, input: &KvOp<K, V>, output: Option<&Option<V>>) -> Option<Self::State> {
+        match input {
+            KvOp::Get(key) => match output {
+                Some(value) if value.as_ref() != state.get(key) => None,
+                _ => Some(state.clone()),
+            },
+            KvOp::Put(key, value) => {
+                let mut state = state.clone();
+                state.insert(key.clone(), value.clone());
+                Some(state)
+            }
+            KvOp::Delete(key) => {
+                let mut state = state.clone();
+                state.remove(key);
+                Some(state)
+            }
+        }
+    }
+
+    fn is_read_only(&self, input: &KvOp<K, V>) -> bool {
+        matches!(input, KvOp::Get(_))
+    }
+
+    fn partition(&self, input: &KvOp<K, V>) -> u64 {
+        let key = match input {
+            KvOp::Get(key) | KvOp::Put(key, _) | KvOp::Delete(key) => key,
+        };
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Queue operation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum QueueOp<V> {
+    /// Adds the value to the queue, the output is ignored.
+    Enqueue(V),
+    /// Removes the first value from the queue, the output is the value or `None` if the queue is empty.
+    Dequeue,
+}
+
+/// FIFO queue with initially empty state.
+///
+/// Operation output is the removed value for `Dequeue` and is ignored for `Enqueue`.
+#[derive(Default)]
+pub struct Queue<V> {
+    _marker: std::marker::PhantomData<V>,
+}
+
+impl<V> Queue<V> {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<V: Clone + Eq + Hash> Model for Queue<V> {
+    type State = VecDeque<V>;
+    type Input = QueueOp<V>;
+    type Output = Option<V>;
+
+    fn init(&self) -> VecDeque<V> {
+        VecDeque::new()
+    }
+
+    fn step(&self, state: &VecDeque<V>, input: &QueueOp<V>, output: Option<&Option<V>>) -> Option<VecDeque<V>> {
+        let mut state = state.clone();
+        match input {
+            QueueOp::Enqueue(value) => {
+                state.push_back(value.clone());
+                Some(state)
+            }
+            QueueOp::Dequeue => {
+                let value = state.pop_front();
+                match output {
+                    Some(expected) if *expected != value => None,
+                    _ => Some(state),
+                }
+            }
+        }
+    }
+}
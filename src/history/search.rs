@@ -0,0 +1,185 @@
+//! Search for a valid serialization of operations shared by history checkers.
+//!
+//! Each checker defines a view, i.e. a set of history operations, and a precedence relation between them. The search
+//! looks for a sequential order of view operations which respects the precedence relation and is valid according to
+//! the sequential model. It follows the Wing-Gong-Lowe algorithm with memoization of visited (serialized operations,
+//! model state) pairs as in Porcupine.
+
+use std::collections::HashSet;
+
+use crate::history::{History, Model};
+
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(n: usize) -> Self {
+        Self(vec![0; n.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn contains_all(&self, other: &Bits) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(a, b)| a & b == *b)
+    }
+}
+
+/// Operation prepared for the search.
+struct Candidate<'a, I, O> {
+    input: &'a I,
+    /// Expected output, `None` if any output is allowed.
+    output: Option<&'a O>,
+    /// Operations which must be serialized before this operation.
+    preds: Bits,
+}
+
+struct Frame<S> {
+    serialized: Bits,
+    state: S,
+    candidates: Vec<usize>,
+    next: usize,
+    op: Option<usize>,
+}
+
+/// Searches for a serialization of candidates which includes all required candidates, returns their order.
+fn search<M: Model>(model: &M, ops: &[Candidate<M::Input, M::Output>], required: &Bits) -> Option<Vec<usize>> {
+    let n = ops.len();
+    let candidates = |serialized: &Bits| -> Vec<usize> {
+        (0..n)
+            .filter(|i| !serialized.get(*i) && serialized.contains_all(&ops[*i].preds))
+            .collect()
+    };
+
+    let serialized = Bits::new(n);
+    if serialized.contains_all(required) {
+        return Some(Vec::new());
+    }
+    let mut visited: HashSet<(Vec<u64>, M::State)> = HashSet::new();
+    let mut stack = vec![Frame {
+        candidates: candidates(&serialized),
+        serialized,
+        state: model.init(),
+        next: 0,
+        op: None,
+    }];
+
+    while let Some(top) = stack.last_mut() {
+        if top.next >= top.candidates.len() {
+            stack.pop();
+            continue;
+        }
+        let i = top.candidates[top.next];
+        top.next += 1;
+        let Some(state) = model.step(&top.state, ops[i].input, ops[i].output) else {
+            continue;
+        };
+        let mut serialized = Bits(top.serialized.0.clone());
+        serialized.set(i);
+        if serialized.contains_all(required) {
+            let mut result: Vec<usize> = stack.iter().filter_map(|f| f.op).collect();
+            result.push(i);
+            return Some(result);
+        }
+        if visited.insert((serialized.0.clone(), state.clone())) {
+            stack.push(Frame {
+                candidates: candidates(&serialized),
+                serialized,
+                state,
+                next: 0,
+                op: Some(i),
+            });
+        }
+    }
+    None
+}
+
+/// Set of history operations to be serialized together.
+pub(crate) struct View<'a> {
+    /// Indices of included operations.
+    pub ops: Vec<usize>,
+    /// Returns true if the output of operation must be checked, otherwise any output is allowed.
+    pub checked: &'a dyn Fn(usize) -> bool,
+    /// Returns true if the first operation must be serialized before the second one.
+    pub before: &'a dyn Fn(usize, usize) -> bool,
+}
+
+/// Searches for a serialization of view operations, returns their order.
+///
+/// Completed operations which are constrained must be included and precede the operations following them.
+/// Other operations are treated as pending: they may be included at any point allowed by preceding operations
+/// or omitted, and any output is allowed for them.
+fn serialize<M: Model>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+    view: &View,
+    constrained: &[bool],
+) -> Option<Vec<usize>> {
+    let ops = history.ops();
+    // operations are sorted by invocation time, so that candidates are tried in this order
+    let mut ids: Vec<usize> = (0..view.ops.len()).collect();
+    ids.sort_by(|a, b| ops[view.ops[*a]].call.total_cmp(&ops[view.ops[*b]].call));
+
+    let n = ids.len();
+    let mut required = Bits::new(n);
+    let mut candidates = Vec::with_capacity(n);
+    for (i, k) in ids.iter().enumerate() {
+        let op = &ops[view.ops[*k]];
+        let mut preds = Bits::new(n);
+        for (j, other) in ids.iter().enumerate() {
+            if constrained[*other] && (view.before)(view.ops[*other], view.ops[*k]) {
+                preds.set(j);
+            }
+        }
+        if constrained[*k] {
+            required.set(i);
+        }
+        let checked = constrained[*k] && (view.checked)(view.ops[*k]);
+        candidates.push(Candidate {
+            input: &op.input,
+            output: if checked { op.output.as_ref() } else { None },
+            preds,
+        });
+    }
+
+    search(model, &candidates, &required).map(|order| order.into_iter().map(|i| view.ops[ids[i]]).collect())
+}
+
+/// Checks that the view operations can be serialized, returns their order.
+///
+/// If there is no valid serialization, returns indices of operations forming a minimal set of conflicting operations.
+/// It is a minimal set of completed operations which can not be serialized together, provided that other operations
+/// may or may not take effect (as if they were pending).
+pub(crate) fn check_view<M: Model>(
+    model: &M,
+    history: &History<M::Input, M::Output>,
+    view: &View,
+) -> Result<Vec<usize>, Vec<usize>> {
+    let mut constrained: Vec<bool> = view.ops.iter().map(|op| !history.ops()[*op].is_pending()).collect();
+    if let Some(order) = serialize(model, history, view, &constrained) {
+        return Ok(order);
+    }
+
+    // Relaxing an operation can only extend the set of valid serializations,
+    // so a single pass is enough to obtain a minimal set of conflicting operations
+    let mut positions: Vec<usize> = (0..view.ops.len()).collect();
+    positions.sort_by_key(|k| view.ops[*k]);
+    for k in positions {
+        if constrained[k] {
+            constrained[k] = false;
+            if serialize(model, history, view, &constrained).is_some() {
+                constrained[k] = true;
+            }
+        }
+    }
+    let mut ops: Vec<usize> = (0..view.ops.len())
+        .filter(|k| constrained[*k])
+        .map(|k| view.ops[k])
+        .collect();
+    ops.sort();
+    Err(ops)
+}
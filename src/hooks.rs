@@ -0,0 +1,49 @@
+//! Hooks for observing the system execution in the current thread.
+//!
+//! The simulation, model checking and logger report steps and printed lines here without depending on the code
+//! which consumes them, e.g. the guard of [`TestSuite`](crate::test::TestSuite) enforcing the test limits.
+
+use std::cell::RefCell;
+
+/// Panic payload used to interrupt the execution by the hooks.
+pub(crate) struct Interrupted(pub String);
+
+/// Hooks called during the system execution.
+pub(crate) trait ExecutionHooks {
+    /// Called on each simulation or model checking step, returns an error to interrupt the execution.
+    fn on_step(&self) -> Result<(), String>;
+
+    /// Returns true if the printed output is captured.
+    fn is_capturing(&self) -> bool;
+
+    /// Captures the printed line, returns false if the output is not captured.
+    fn capture(&self, line: &str) -> bool;
+}
+
+thread_local! {
+    static HOOKS: RefCell<Option<Box<dyn ExecutionHooks>>> = const { RefCell::new(None) };
+}
+
+/// Sets the hooks for the current thread, `None` removes the previously set hooks.
+pub(crate) fn set(hooks: Option<Box<dyn ExecutionHooks>>) {
+    HOOKS.with(|h| *h.borrow_mut() = hooks);
+}
+
+/// Reports a step, interrupts the execution by unwinding with [`Interrupted`] if requested by the hooks.
+pub(crate) fn on_step() {
+    let res = HOOKS.with(|h| h.borrow().as_ref().map_or(Ok(()), |hooks| hooks.on_step()));
+    if let Err(message) = res {
+        // resume_unwind does not invoke the panic hook, so nothing is printed
+        std::panic::resume_unwind(Box::new(Interrupted(message)));
+    }
+}
+
+/// Returns true if the printed output is captured.
+pub(crate) fn is_capturing() -> bool {
+    HOOKS.with(|h| h.borrow().as_ref().is_some_and(|hooks| hooks.is_capturing()))
+}
+
+/// Passes the printed line to the hooks, returns false if it is not captured and should be printed.
+pub(crate) fn capture(line: &str) -> bool {
+    HOOKS.with(|h| h.borrow().as_ref().is_some_and(|hooks| hooks.capture(line)))
+}
@@ -42,18 +42,28 @@
 #![warn(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+pub mod async_process;
+pub mod causality;
 pub mod context;
+pub mod error;
 pub mod events;
+pub mod history;
+mod hooks;
 pub mod logger;
 pub mod mc;
 pub mod message;
+pub mod metrics;
 pub mod network;
 pub mod node;
 pub mod process;
 pub mod python;
+pub mod rpc;
+pub mod size;
+pub mod stats;
 pub mod system;
 pub mod test;
 mod util;
+pub mod workload;
 
 pub use context::Context;
 pub use message::Message;
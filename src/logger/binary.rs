@@ -0,0 +1,332 @@
+//! Compact binary log format.
+//!
+//! The binary format stores the same data as the JSON lines log file, i.e. the serialized
+//! representation of [`LogEntry`], but uses a more compact encoding:
+//!
+//! - the file starts with the `ASLOG` magic bytes followed by the format version;
+//! - each entry is encoded as a tagged value, integers are stored as variable-length integers
+//!   and floats as 8-byte little-endian numbers;
+//! - short strings (object keys, event kinds, process and node names, etc.) are interned:
+//!   the first occurrence of a string is stored inline and added to the string table,
+//!   subsequent occurrences are stored as references to the table.
+//!
+//! Use [`json_to_binary`] and [`binary_to_json`] to convert logs between the formats.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+
+use serde_json::{Map, Number, Value};
+
+use crate::logger::LogEntry;
+
+const MAGIC: &[u8; 5] = b"ASLOG";
+const VERSION: u8 = 1;
+
+const MAX_INTERNED_LEN: usize = 64;
+const MAX_INTERNED_COUNT: usize = 1 << 16;
+/// Maximum buffer size allocated before reading a string, larger strings grow the buffer as they are read.
+const MAX_STR_PREALLOC: u64 = 1 << 16;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_UINT: u8 = 3;
+const TAG_INT: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_STRING: u8 = 6;
+const TAG_ARRAY: u8 = 7;
+const TAG_OBJECT: u8 = 8;
+
+// String references: 0 - inline string, 1 - inline string added to table, n - table[n - 2].
+const STR_INLINE: u64 = 0;
+const STR_INTERNED: u64 = 1;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error)
+}
+
+// Writer --------------------------------------------------------------------------------------------------------------
+
+/// Writes log entries in the binary format.
+pub struct BinaryLogWriter<W: Write> {
+    writer: W,
+    strings: HashMap<String, u64>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> BinaryLogWriter<W> {
+    /// Creates a writer and writes the format header.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self {
+            writer,
+            strings: HashMap::new(),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Writes a log entry.
+    pub fn write_entry(&mut self, entry: &LogEntry) -> io::Result<()> {
+        let value = serde_json::to_value(entry).map_err(invalid_data)?;
+        self.write_value(&value)
+    }
+
+    /// Writes an arbitrary JSON value as a log record.
+    pub fn write_value(&mut self, value: &Value) -> io::Result<()> {
+        self.buf.clear();
+        let mut buf = std::mem::take(&mut self.buf);
+        self.encode_value(value, &mut buf);
+        let res = self.writer.write_all(&buf);
+        self.buf = buf;
+        res
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn encode_value(&mut self, value: &Value, buf: &mut Vec<u8>) {
+        match value {
+            Value::Null => buf.push(TAG_NULL),
+            Value::Bool(false) => buf.push(TAG_FALSE),
+            Value::Bool(true) => buf.push(TAG_TRUE),
+            Value::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    buf.push(TAG_UINT);
+                    write_varint(buf, u);
+                } else if let Some(i) = n.as_i64() {
+                    buf.push(TAG_INT);
+                    write_varint(buf, ((i << 1) ^ (i >> 63)) as u64);
+                } else {
+                    buf.push(TAG_FLOAT);
+                    buf.extend_from_slice(&n.as_f64().unwrap().to_le_bytes());
+                }
+            }
+            Value::String(s) => {
+                buf.push(TAG_STRING);
+                self.encode_str(s, buf);
+            }
+            Value::Array(items) => {
+                buf.push(TAG_ARRAY);
+                write_varint(buf, items.len() as u64);
+                for item in items {
+                    self.encode_value(item, buf);
+                }
+            }
+            Value::Object(map) => {
+                buf.push(TAG_OBJECT);
+                write_varint(buf, map.len() as u64);
+                for (key, item) in map {
+                    self.encode_str(key, buf);
+                    self.encode_value(item, buf);
+                }
+            }
+        }
+    }
+
+    fn encode_str(&mut self, s: &str, buf: &mut Vec<u8>) {
+        if let Some(idx) = self.strings.get(s) {
+            write_varint(buf, idx + 2);
+            return;
+        }
+        if s.len() <= MAX_INTERNED_LEN && self.strings.len() < MAX_INTERNED_COUNT {
+            let idx = self.strings.len() as u64;
+            self.strings.insert(s.to_string(), idx);
+            write_varint(buf, STR_INTERNED);
+        } else {
+            write_varint(buf, STR_INLINE);
+        }
+        write_varint(buf, s.len() as u64);
+        buf.extend_from_slice(s.as_bytes());
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+// Reader --------------------------------------------------------------------------------------------------------------
+
+/// Reads log entries in the binary format.
+///
+/// Can be used as an iterator over log entries.
+pub struct BinaryLogReader<R: Read> {
+    reader: R,
+    strings: Vec<String>,
+}
+
+impl<R: Read> BinaryLogReader<R> {
+    /// Creates a reader and checks the format header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header)?;
+        if &header[..5] != MAGIC {
+            return Err(invalid_data("not a binary log file"));
+        }
+        if header[5] != VERSION {
+            return Err(invalid_data(format!("unsupported binary log version {}", header[5])));
+        }
+        Ok(Self {
+            reader,
+            strings: Vec::new(),
+        })
+    }
+
+    /// Reads the next log record as a JSON value, returns `None` at the end of log.
+    pub fn read_value(&mut self) -> io::Result<Option<Value>> {
+        let mut tag = [0u8; 1];
+        loop {
+            match self.reader.read(&mut tag) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.decode_value(tag[0]).map(Some)
+    }
+
+    /// Reads the next log entry, returns `None` at the end of log.
+    pub fn read_entry(&mut self) -> io::Result<Option<LogEntry>> {
+        match self.read_value()? {
+            Some(value) => serde_json::from_value(value).map(Some).map_err(invalid_data),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_value(&mut self, tag: u8) -> io::Result<Value> {
+        let value = match tag {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Bool(false),
+            TAG_TRUE => Value::Bool(true),
+            TAG_UINT => Value::Number(self.read_varint()?.into()),
+            TAG_INT => {
+                let z = self.read_varint()?;
+                Value::Number((((z >> 1) as i64) ^ -((z & 1) as i64)).into())
+            }
+            TAG_FLOAT => {
+                let mut bytes = [0u8; 8];
+                self.reader.read_exact(&mut bytes)?;
+                let f = f64::from_le_bytes(bytes);
+                Value::Number(Number::from_f64(f).ok_or_else(|| invalid_data("invalid float value"))?)
+            }
+            TAG_STRING => Value::String(self.decode_str()?),
+            TAG_ARRAY => {
+                let len = self.read_varint()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    let tag = self.read_byte()?;
+                    items.push(self.decode_value(tag)?);
+                }
+                Value::Array(items)
+            }
+            TAG_OBJECT => {
+                let len = self.read_varint()?;
+                let mut map = Map::new();
+                for _ in 0..len {
+                    let key = self.decode_str()?;
+                    let tag = self.read_byte()?;
+                    map.insert(key, self.decode_value(tag)?);
+                }
+                Value::Object(map)
+            }
+            _ => return Err(invalid_data(format!("unknown value tag {tag}"))),
+        };
+        Ok(value)
+    }
+
+    fn decode_str(&mut self) -> io::Result<String> {
+        let reference = self.read_varint()?;
+        if reference >= 2 {
+            return self
+                .strings
+                .get((reference - 2) as usize)
+                .cloned()
+                .ok_or_else(|| invalid_data("invalid string reference"));
+        }
+        // the length is read from the input, so it is not trusted for allocating the buffer
+        let len = self.read_varint()?;
+        let mut bytes = Vec::with_capacity(len.min(MAX_STR_PREALLOC) as usize);
+        if (&mut self.reader).take(len).read_to_end(&mut bytes)? as u64 != len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let s = String::from_utf8(bytes).map_err(invalid_data)?;
+        if reference == STR_INTERNED {
+            self.strings.push(s.clone());
+        }
+        Ok(s)
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8; 1];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if shift >= 64 {
+                return Err(invalid_data("varint is too long"));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+}
+
+impl<R: Read> Iterator for BinaryLogReader<R> {
+    type Item = io::Result<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+// Converters ----------------------------------------------------------------------------------------------------------
+
+/// Converts a log in JSON lines format to the binary format, returns the number of converted entries.
+pub fn json_to_binary<R: BufRead, W: Write>(input: R, output: W) -> io::Result<usize> {
+    let mut writer = BinaryLogWriter::new(output)?;
+    let mut count = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(&line).map_err(invalid_data)?;
+        writer.write_value(&value)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Converts a log in the binary format to JSON lines format, returns the number of converted entries.
+pub fn binary_to_json<R: Read, W: Write>(input: R, mut output: W) -> io::Result<usize> {
+    let mut reader = BinaryLogReader::new(input)?;
+    let mut count = 0;
+    while let Some(value) = reader.read_value()? {
+        serde_json::to_writer(&mut output, &value).map_err(invalid_data)?;
+        output.write_all(b"\n")?;
+        count += 1;
+    }
+    output.flush()?;
+    Ok(count)
+}
@@ -0,0 +1,202 @@
+//! Logger configuration.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use log::Level;
+
+use crate::logger::LogEntry;
+
+/// Specifies where the logged events are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleOutput {
+    /// Events are not printed.
+    Disabled,
+    /// Events are passed to the [`log`] crate with the specified level.
+    Log(Level),
+    /// Events are printed to stdout.
+    Stdout,
+}
+
+/// Format of the log file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// JSON lines, one serialized [`LogEntry`] per line.
+    #[default]
+    Json,
+    /// Compact [binary format](crate::logger::binary).
+    Binary,
+}
+
+/// Logger configuration.
+///
+/// By default, the events are passed to the [`log`] crate with `Debug` level using colored output,
+/// all events are kept in memory and no log file is written.
+#[derive(Clone, Debug)]
+pub struct LoggerConfig {
+    pub(crate) console: ConsoleOutput,
+    pub(crate) colored: bool,
+    pub(crate) filter: LogFilter,
+    pub(crate) trace: bool,
+    pub(crate) trace_limit: Option<usize>,
+    pub(crate) process_states: bool,
+    pub(crate) log_file: Option<PathBuf>,
+    pub(crate) log_format: LogFormat,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            console: ConsoleOutput::Log(Level::Debug),
+            colored: true,
+            filter: LogFilter::default(),
+            trace: true,
+            trace_limit: None,
+            process_states: false,
+            log_file: None,
+            log_format: LogFormat::Json,
+        }
+    }
+}
+
+impl LoggerConfig {
+    /// Sets the console output destination.
+    pub fn console(mut self, console: ConsoleOutput) -> Self {
+        self.console = console;
+        self
+    }
+
+    /// Disables printing of events (quiet mode).
+    pub fn quiet(self) -> Self {
+        self.console(ConsoleOutput::Disabled)
+    }
+
+    /// Enables or disables colored console output.
+    pub fn colored(mut self, colored: bool) -> Self {
+        self.colored = colored;
+        self
+    }
+
+    /// Sets the filter for events printed to console.
+    ///
+    /// The filter does not affect the trace and the log file.
+    pub fn filter(mut self, filter: LogFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Enables or disables keeping the logged events in memory (see [`Logger::trace`](crate::logger::Logger::trace)).
+    ///
+    /// Note that the model checker starts with the trace of simulation, so disabling it results in shorter error
+    /// traces.
+    pub fn trace(mut self, enabled: bool) -> Self {
+        self.trace = enabled;
+        self
+    }
+
+    /// Limits the number of events kept in memory, only the last `limit` events are kept.
+    pub fn trace_limit(mut self, limit: usize) -> Self {
+        self.trace_limit = Some(limit);
+        self
+    }
+
+    /// Enables or disables logging of [`LogEntry::ProcessStateUpdated`] events.
+    ///
+    /// These events are always logged if the log file is used.
+    pub fn process_states(mut self, enabled: bool) -> Self {
+        self.process_states = enabled;
+        self
+    }
+
+    /// Sets the file for writing the logged events.
+    pub fn log_file(mut self, log_path: &Path) -> Self {
+        self.log_file = Some(log_path.to_path_buf());
+        self
+    }
+
+    /// Sets the format of the log file (JSON lines by default).
+    pub fn log_format(mut self, format: LogFormat) -> Self {
+        self.log_format = format;
+        self
+    }
+}
+
+/// Selects the events printed to console.
+///
+/// An event is selected if it passes all specified conditions. Each condition is satisfied if the event
+/// involves at least one of the listed items. Events which do not carry the checked data
+/// (e.g. node events in case of message type condition) do not pass the condition.
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    kinds: Option<HashSet<String>>,
+    procs: Option<HashSet<String>>,
+    nodes: Option<HashSet<String>>,
+    msg_types: Option<HashSet<String>>,
+}
+
+impl LogFilter {
+    /// Selects events of the specified kinds (see [`LogEntry::kind`]).
+    pub fn kinds<I, S>(mut self, kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.kinds = Some(kinds.into_iter().map(|s| s.into()).collect());
+        self
+    }
+
+    /// Selects events involving the specified processes.
+    pub fn procs<I, S>(mut self, procs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.procs = Some(procs.into_iter().map(|s| s.into()).collect());
+        self
+    }
+
+    /// Selects events involving the specified nodes.
+    pub fn nodes<I, S>(mut self, nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.nodes = Some(nodes.into_iter().map(|s| s.into()).collect());
+        self
+    }
+
+    /// Selects events with messages of the specified types.
+    pub fn msg_types<I, S>(mut self, msg_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.msg_types = Some(msg_types.into_iter().map(|s| s.into()).collect());
+        self
+    }
+
+    /// Checks if the event is selected by the filter.
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(entry.kind()) {
+                return false;
+            }
+        }
+        if let Some(procs) = &self.procs {
+            if !entry.procs().iter().any(|proc| procs.contains(*proc)) {
+                return false;
+            }
+        }
+        if let Some(nodes) = &self.nodes {
+            if !entry.nodes().iter().any(|node| nodes.contains(*node)) {
+                return false;
+            }
+        }
+        if let Some(msg_types) = &self.msg_types {
+            if !entry.msg().is_some_and(|msg| msg_types.contains(&msg.tip)) {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,373 @@
+//! Export of traces to a self-contained HTML viewer.
+//!
+//! The produced file embeds the trace as JSON together with a small viewer written in plain JavaScript,
+//! so it can be opened offline in any browser without installing additional tools. The viewer supports
+//! filtering events by process, message type and event kind, stepping through the events, inspecting
+//! message payloads and the process states reported by [`LogEntry::ProcessStateUpdated`] at each point.
+//!
+//! Both simulation traces ([`Logger::trace`](crate::logger::Logger::trace)) and model checking traces
+//! ([`McError::trace`](crate::mc::McError::trace)) are supported.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::logger::LogEntry;
+
+/// Writes the trace to the specified file as a self-contained HTML page with a trace viewer.
+pub fn export_html(trace: &[LogEntry], path: &Path) -> std::io::Result<()> {
+    let title = path
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "trace".to_string());
+    fs::write(path, render_html(trace, &title))
+}
+
+/// Renders the trace as a self-contained HTML page with a trace viewer.
+pub fn render_html(trace: &[LogEntry], title: &str) -> String {
+    let records: Vec<Value> = trace
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| entry_record(idx, entry))
+        .collect();
+    let data = escape_script_json(&serde_json::to_string(&records).unwrap());
+    VIEWER_TEMPLATE
+        .replace("{{TITLE}}", &escape_html(title))
+        .replace("{{TRACE_DATA}}", &data)
+}
+
+fn entry_record(idx: usize, entry: &LogEntry) -> Value {
+    // entries are serialized with external tagging, i.e. as {"Kind": {...fields}}
+    let details = match serde_json::to_value(entry).unwrap() {
+        Value::Object(mut map) => map.remove(entry.kind()).unwrap_or(Value::Null),
+        other => other,
+    };
+    let state = match entry {
+        LogEntry::ProcessStateUpdated { state, .. } => Some(state),
+        _ => None,
+    };
+    json!({
+        "i": idx,
+        "kind": entry.kind(),
+        "time": entry.time(),
+        "procs": entry.procs(),
+        "nodes": entry.nodes(),
+        "msg": entry.msg().map(|msg| json!({"tip": msg.tip, "data": msg.data})),
+        "state": state,
+        "details": details,
+    })
+}
+
+/// Escapes JSON embedded in a script element.
+///
+/// Escaping `<` prevents closing the element or opening a comment (`</script>`, `<!--`) from within the data,
+/// while U+2028 and U+2029 are line terminators in older JavaScript engines though valid inside JSON strings.
+fn escape_script_json(json: &str) -> String {
+    json.replace('<', "\\u003c")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const VIEWER_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{TITLE}}</title>
+<style>
+  body { margin: 0; font: 13px/1.4 monospace; color: #222; display: flex; flex-direction: column; height: 100vh; }
+  header { padding: 8px 12px; background: #2d3e50; color: #fff; display: flex; gap: 16px; align-items: center; flex-wrap: wrap; }
+  header h1 { font-size: 15px; margin: 0 12px 0 0; }
+  header label { display: flex; gap: 4px; align-items: center; }
+  header button { font: inherit; }
+  main { flex: 1; display: flex; min-height: 0; }
+  #events { flex: 3; overflow: auto; border-right: 1px solid #ccc; }
+  #side { flex: 2; overflow: auto; padding: 8px 12px; }
+  table { border-collapse: collapse; width: 100%; }
+  th { position: sticky; top: 0; background: #eee; text-align: left; }
+  td, th { padding: 2px 8px; white-space: nowrap; }
+  tr.event { cursor: pointer; }
+  tr.event:hover { background: #f3f3f3; }
+  tr.selected, tr.selected:hover { background: #ffe9a8; }
+  .k-MessageSent, .k-McMessageSent { color: #222; }
+  .k-MessageReceived, .k-McMessageReceived { color: #555; }
+  .k-LocalMessageSent, .k-McLocalMessageSent { color: #1b7a1b; }
+  .k-LocalMessageReceived, .k-McLocalMessageReceived { color: #12808a; }
+  .k-MessageDropped, .k-McMessageDropped, .k-NodeCrashed, .k-McNodeCrashed, .k-ProcessFailed, .k-NodeDisconnected,
+  .k-LinkDisabled, .k-NetworkPartition, .k-McNetworkPartition, .k-DropIncoming, .k-DropOutgoing { color: #b31d1d; }
+  .k-UserEvent, .k-McUserEvent { color: #8e24aa; font-weight: bold; }
+  .k-TimerFired, .k-McTimerFired, .k-TimerSet, .k-McTimerSet, .k-TimerCancelled, .k-McTimerCancelled { color: #9a7b00; }
+  .k-McMessageDuplicated, .k-McMessageCorrupted { color: #1f4fb3; }
+  h2 { font-size: 14px; margin: 12px 0 4px; }
+  pre { background: #f6f6f6; padding: 6px; margin: 0; white-space: pre-wrap; word-break: break-all; }
+  .state { margin-bottom: 8px; }
+  .state.updated pre { background: #fff4cc; }
+  .muted { color: #888; }
+</style>
+</head>
+<body>
+<header>
+  <h1>{{TITLE}}</h1>
+  <label>Process <select id="f-proc"><option value="">all</option></select></label>
+  <label>Message type <select id="f-tip"><option value="">all</option></select></label>
+  <label>Event kind <select id="f-kind"><option value="">all</option></select></label>
+  <span>
+    <button id="b-first" title="First event (Home)">&#x23EE;</button>
+    <button id="b-prev" title="Previous event (Left)">&#x25C0;</button>
+    <button id="b-next" title="Next event (Right)">&#x25B6;</button>
+    <button id="b-last" title="Last event (End)">&#x23ED;</button>
+  </span>
+  <span id="position"></span>
+</header>
+<main>
+  <div id="events">
+    <table>
+      <thead><tr><th>#</th><th>time</th><th>kind</th><th>event</th></tr></thead>
+      <tbody id="rows"></tbody>
+    </table>
+  </div>
+  <div id="side">
+    <h2>Event</h2>
+    <div id="details" class="muted">Select an event</div>
+    <h2>Process states</h2>
+    <div id="states" class="muted">No process states recorded up to this point</div>
+  </div>
+</main>
+<script id="trace-data" type="application/json">{{TRACE_DATA}}</script>
+<script>
+(function () {
+  "use strict";
+  var trace = JSON.parse(document.getElementById("trace-data").textContent);
+  var visible = [];
+  var current = -1;
+
+  function $(id) { return document.getElementById(id); }
+
+  function text(value) { return value === null || value === undefined ? "" : String(value); }
+
+  function fillSelect(select, values) {
+    values.sort().forEach(function (value) {
+      var option = document.createElement("option");
+      option.value = value;
+      option.textContent = value;
+      select.appendChild(option);
+    });
+  }
+
+  function unique(getter) {
+    var seen = {};
+    trace.forEach(function (e) { getter(e).forEach(function (v) { seen[v] = true; }); });
+    return Object.keys(seen);
+  }
+
+  function summary(e) {
+    var procs = e.procs;
+    var msg = e.msg ? e.msg.tip + " " + e.msg.data : "";
+    var d = e.details || {};
+    switch (e.kind) {
+      case "MessageSent": case "McMessageSent": return procs[0] + " --> " + procs[1] + "  " + msg;
+      case "MessageReceived": case "McMessageReceived": return procs[1] + " <-- " + procs[0] + "  " + msg;
+      case "MessageDropped": case "McMessageDropped": return procs[0] + " --x " + procs[1] + "  " + msg;
+      case "McMessageDuplicated": return procs[0] + " -=≡ " + procs[1] + "  " + msg;
+      case "McMessageCorrupted":
+        return procs[0] + " -x- " + procs[1] + "  " + msg + " ~~> " + d.corrupted_msg.tip + " " + d.corrupted_msg.data;
+      case "LocalMessageSent": case "McLocalMessageSent": return procs[0] + " >>> local  " + msg;
+      case "LocalMessageReceived": case "McLocalMessageReceived": return procs[0] + " <<< local  " + msg;
+      case "TimerSet": return procs[0] + " +++ " + d.timer_name + " (" + d.delay + ")";
+      case "McTimerSet": return procs[0] + " +++ " + d.timer;
+      case "TimerFired": return procs[0] + " !-- " + (d.timer_name || d.timer_id);
+      case "McTimerFired": return procs[0] + " !-- " + d.timer;
+      case "TimerCancelled": return procs[0] + " xxx " + (d.timer_name || d.timer_id);
+      case "McTimerCancelled": return procs[0] + " xxx " + d.timer;
+      case "ProcessStateUpdated": return procs[0] + " state updated";
+      case "ProcessFailed": return procs[0] + " failed: " + d.error;
+      case "NetworkPartition": case "McNetworkPartition":
+        return JSON.stringify(d.group1) + " -x- " + JSON.stringify(d.group2);
+      case "LinkDisabled": case "LinkEnabled": return d.from + " --> " + d.to;
+      default: return procs.concat(e.nodes).join(" ");
+    }
+  }
+
+  function matches(e) {
+    var proc = $("f-proc").value, tip = $("f-tip").value, kind = $("f-kind").value;
+    if (proc && e.procs.indexOf(proc) < 0) return false;
+    if (tip && (!e.msg || e.msg.tip !== tip)) return false;
+    if (kind && e.kind !== kind) return false;
+    return true;
+  }
+
+  function render() {
+    var selected = current >= 0 ? visible[current] : -1;
+    visible = trace.filter(matches).map(function (e) { return e.i; });
+    current = visible.indexOf(selected);
+    if (current < 0 && visible.length > 0) current = 0;
+    var rows = $("rows");
+    rows.textContent = "";
+    visible.forEach(function (i, pos) {
+      var e = trace[i];
+      var row = document.createElement("tr");
+      row.className = "event k-" + e.kind;
+      row.id = "row-" + i;
+      [i, e.time === null ? "-" : e.time.toFixed(3), e.kind, summary(e)].forEach(function (value) {
+        var cell = document.createElement("td");
+        cell.textContent = text(value);
+        row.appendChild(cell);
+      });
+      row.addEventListener("click", function () { select(pos); });
+      rows.appendChild(row);
+    });
+    select(current);
+  }
+
+  function pretty(data) {
+    try { return JSON.stringify(JSON.parse(data), null, 2); } catch (err) { return text(data); }
+  }
+
+  function block(title, content) {
+    var wrapper = document.createElement("div");
+    var label = document.createElement("div");
+    label.textContent = title;
+    var pre = document.createElement("pre");
+    pre.textContent = content;
+    wrapper.appendChild(label);
+    wrapper.appendChild(pre);
+    return wrapper;
+  }
+
+  function showDetails(e) {
+    var details = $("details");
+    details.textContent = "";
+    details.className = "";
+    if (!e) {
+      details.className = "muted";
+      details.textContent = "Select an event";
+      return;
+    }
+    details.appendChild(block("#" + e.i + " " + e.kind + (e.time === null ? "" : " @ " + e.time.toFixed(3)),
+      summary(e)));
+    if (e.msg) details.appendChild(block("Message " + e.msg.tip, pretty(e.msg.data)));
+    details.appendChild(block("Fields", JSON.stringify(e.details, null, 2)));
+  }
+
+  function showStates(e) {
+    var states = $("states");
+    states.textContent = "";
+    var latest = {};
+    var limit = e ? e.i : -1;
+    for (var i = 0; i <= limit; i++) {
+      if (trace[i].kind === "ProcessStateUpdated") latest[trace[i].procs[0]] = trace[i];
+    }
+    var procs = Object.keys(latest).sort();
+    states.className = procs.length ? "" : "muted";
+    if (!procs.length) {
+      states.textContent = "No process states recorded up to this point";
+      return;
+    }
+    procs.forEach(function (proc) {
+      var update = latest[proc];
+      var item = block(proc + " (updated at #" + update.i + ")", pretty(update.state));
+      item.className = "state" + (update.i === limit ? " updated" : "");
+      states.appendChild(item);
+    });
+  }
+
+  function select(pos) {
+    var old = document.querySelector("tr.selected");
+    if (old) old.classList.remove("selected");
+    current = visible.length ? Math.max(0, Math.min(pos, visible.length - 1)) : -1;
+    var e = current >= 0 ? trace[visible[current]] : null;
+    if (e) {
+      var row = $("row-" + e.i);
+      row.classList.add("selected");
+      row.scrollIntoView({ block: "nearest" });
+    }
+    $("position").textContent = visible.length ? (current + 1) + " / " + visible.length : "no events";
+    showDetails(e);
+    showStates(e);
+  }
+
+  fillSelect($("f-proc"), unique(function (e) { return e.procs; }));
+  fillSelect($("f-tip"), unique(function (e) { return e.msg ? [e.msg.tip] : []; }));
+  fillSelect($("f-kind"), unique(function (e) { return [e.kind]; }));
+  ["f-proc", "f-tip", "f-kind"].forEach(function (id) { $(id).addEventListener("change", render); });
+  $("b-first").addEventListener("click", function () { select(0); });
+  $("b-prev").addEventListener("click", function () { select(current - 1); });
+  $("b-next").addEventListener("click", function () { select(current + 1); });
+  $("b-last").addEventListener("click", function () { select(visible.length - 1); });
+  document.addEventListener("keydown", function (event) {
+    if (event.target.tagName === "SELECT") return;
+    if (event.key === "ArrowLeft" || event.key === "ArrowUp") { select(current - 1); event.preventDefault(); }
+    if (event.key === "ArrowRight" || event.key === "ArrowDown") { select(current + 1); event.preventDefault(); }
+    if (event.key === "Home") select(0);
+    if (event.key === "End") select(visible.length - 1);
+  });
+  render();
+})();
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use crate::logger::LogEntry;
+    use crate::Message;
+
+    use super::render_html;
+
+    #[test]
+    fn test_render_html_embeds_trace() {
+        let trace = vec![
+            LogEntry::MessageSent {
+                time: 1.5,
+                msg_id: "0".to_string(),
+                src_node: "node1".to_string(),
+                src_proc: "proc1".to_string(),
+                dst_node: "node2".to_string(),
+                dst_proc: "proc2".to_string(),
+                msg: Message::new("PING", "{\"value\": \"</script><!--<b>\u{2028}\u{2029}\"}"),
+                lamport_time: 1,
+                vector_clock: Default::default(),
+                call: None,
+            },
+            LogEntry::ProcessStateUpdated {
+                time: 1.5,
+                node: "node1".to_string(),
+                proc: "proc1".to_string(),
+                state: "Some(PING)".to_string(),
+            },
+            LogEntry::McTimerFired {
+                proc: "proc2".to_string(),
+                timer: "check".to_string(),
+            },
+        ];
+        let html = render_html(&trace, "<test>");
+
+        assert!(html.contains("<title>&lt;test&gt;</title>"));
+        // the payload must not terminate the embedded script element
+        assert_eq!(html.matches("</script>").count(), 2);
+        let start = html.find(r#"type="application/json">"#).unwrap() + r#"type="application/json">"#.len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let data = &html[start..end];
+        assert!(!data.contains('<'));
+        assert!(!data.contains(['\u{2028}', '\u{2029}']));
+        let records: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(records.as_array().unwrap().len(), 3);
+        assert_eq!(records[0]["kind"], "MessageSent");
+        assert_eq!(records[0]["procs"], serde_json::json!(["proc1", "proc2"]));
+        assert_eq!(
+            records[0]["msg"]["data"],
+            "{\"value\": \"</script><!--<b>\u{2028}\u{2029}\"}"
+        );
+        assert_eq!(records[1]["state"], "Some(PING)");
+        assert_eq!(records[2]["time"], serde_json::Value::Null);
+        assert_eq!(records[2]["details"]["timer"], "check");
+    }
+}
@@ -0,0 +1,816 @@
+//! Logging facilities.
+
+use std::io;
+use std::path::Path;
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use simcore::Id;
+
+use crate::causality::VectorClock;
+use crate::hooks;
+use crate::rpc::CallTag;
+use crate::{util::t, Message};
+
+pub mod binary;
+pub mod config;
+pub mod html;
+pub mod sink;
+
+pub use config::{ConsoleOutput, LogFilter, LogFormat, LoggerConfig};
+pub use sink::{BinarySink, JsonLinesSink, LogSink};
+
+/// Implements logging of events to console and optionally to a file or other sinks.
+/// Also provides the access to the list of logged events (trace).  
+pub struct Logger {
+    config: LoggerConfig,
+    file_sink: Option<SinkEntry>,
+    sinks: Vec<SinkEntry>,
+    /// First error returned by a sink.
+    sink_error: Option<io::Error>,
+    trace: Vec<LogEntry>,
+    /// Total number of events added to the trace including the dropped ones.
+    trace_count: u64,
+}
+
+impl Logger {
+    /// Creates a new console-only logger.
+    pub(crate) fn new() -> Self {
+        Self::with_config(LoggerConfig::default())
+    }
+
+    /// Creates a new logger with the specified configuration.
+    pub(crate) fn with_config(config: LoggerConfig) -> Self {
+        let mut logger = Self {
+            config: LoggerConfig::default(),
+            file_sink: None,
+            sinks: Vec::new(),
+            sink_error: None,
+            trace: vec![],
+            trace_count: 0,
+        };
+        logger
+            .set_config(config)
+            .unwrap_or_else(|err| panic!("Cannot create log file: {err}"));
+        logger
+    }
+
+    /// Returns the logger configuration.
+    pub fn config(&self) -> &LoggerConfig {
+        &self.config
+    }
+
+    /// Updates the logger configuration.
+    ///
+    /// The log file is reopened if its path or format is changed.
+    /// If the new log file can not be created, the error is returned and the configuration is not changed.
+    pub fn set_config(&mut self, config: LoggerConfig) -> io::Result<()> {
+        if config.log_file != self.config.log_file || config.log_format != self.config.log_format {
+            if let Some(sink) = self.file_sink.as_mut() {
+                sink.apply(&mut self.sink_error, |sink| sink.flush());
+            }
+            self.file_sink = match &config.log_file {
+                Some(log_path) => Some(SinkEntry::new(match config.log_format {
+                    LogFormat::Json => Box::new(JsonLinesSink::create(log_path)?),
+                    LogFormat::Binary => Box::new(BinarySink::create(log_path)?),
+                })),
+                None => None,
+            };
+        }
+        self.config = config;
+        self.truncate_trace();
+        Ok(())
+    }
+
+    /// Adds a sink which receives all subsequently logged events.
+    pub fn add_sink(&mut self, sink: Box<dyn LogSink>) {
+        self.sinks.push(SinkEntry::new(sink));
+    }
+
+    /// Flushes the log file and other sinks.
+    ///
+    /// Returns the first error returned by a sink so far. The failed sink does not receive subsequent events.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for sink in self.file_sink.iter_mut().chain(self.sinks.iter_mut()) {
+            sink.apply(&mut self.sink_error, |sink| sink.flush());
+        }
+        match &self.sink_error {
+            Some(err) => Err(io::Error::new(err.kind(), err.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if [`LogEntry::ProcessStateUpdated`] events should be logged.
+    pub(crate) fn logs_process_states(&self) -> bool {
+        self.file_sink.is_some() || self.config.process_states
+    }
+
+    pub(crate) fn log(&mut self, event: LogEntry) {
+        for sink in self.file_sink.iter_mut().chain(self.sinks.iter_mut()) {
+            sink.apply(&mut self.sink_error, |sink| sink.write(&event));
+        }
+
+        if self.config.console != ConsoleOutput::Disabled && self.config.filter.matches(&event) {
+            // captured output is not colored
+            let colored = self.config.colored && !hooks::is_capturing();
+            if let Some(line) = event.console_line(colored) {
+                if !hooks::capture(&line) {
+                    match self.config.console {
+                        ConsoleOutput::Log(level) => log::log!(level, "{}", line),
+                        ConsoleOutput::Stdout => println!("{line}"),
+                        ConsoleOutput::Disabled => {}
+                    }
+                }
+            }
+        }
+
+        if self.config.trace && self.config.trace_limit != Some(0) {
+            self.trace.push(event);
+            self.trace_count += 1;
+            // Drop old events in batches to keep the amortized cost constant
+            if let Some(limit) = self.config.trace_limit {
+                if self.trace.len() >= 2 * limit {
+                    self.truncate_trace();
+                }
+            }
+        }
+    }
+
+    fn truncate_trace(&mut self) {
+        if !self.config.trace {
+            self.trace.clear();
+        } else if let Some(limit) = self.config.trace_limit {
+            if self.trace.len() > limit {
+                self.trace.drain(..self.trace.len() - limit);
+            }
+        }
+    }
+
+    /// Returns the logged events kept in memory.
+    ///
+    /// If the trace limit is set (see [`LoggerConfig::trace_limit`]), only the last logged events are returned.
+    pub fn trace(&self) -> &[LogEntry] {
+        match self.config.trace_limit {
+            Some(limit) if self.trace.len() > limit => &self.trace[self.trace.len() - limit..],
+            _ => &self.trace,
+        }
+    }
+
+    /// Returns the position of the next event in the trace, which can be passed to [`Self::trace_since`].
+    pub(crate) fn trace_position(&self) -> u64 {
+        self.trace_count
+    }
+
+    /// Returns the logged events kept in memory starting from the specified trace position.
+    pub(crate) fn trace_since(&self, position: u64) -> &[LogEntry] {
+        let trace = self.trace();
+        let first_position = self.trace_count - trace.len() as u64;
+        &trace[(position.saturating_sub(first_position) as usize).min(trace.len())..]
+    }
+
+    /// Writes the logged events to a self-contained HTML file with a trace viewer.
+    ///
+    /// See [`html::export_html`] for details.
+    pub fn export_html(&self, path: &Path) -> std::io::Result<()> {
+        html::export_html(self.trace(), path)
+    }
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Log sink which is not used after the first error.
+struct SinkEntry {
+    sink: Box<dyn LogSink>,
+    failed: bool,
+}
+
+impl SinkEntry {
+    fn new(sink: Box<dyn LogSink>) -> Self {
+        Self { sink, failed: false }
+    }
+
+    /// Applies the operation to the sink unless it failed before, stores the error if it is the first one.
+    fn apply(&mut self, error: &mut Option<io::Error>, f: impl FnOnce(&mut dyn LogSink) -> io::Result<()>) {
+        if self.failed {
+            return;
+        }
+        if let Err(err) = f(&mut *self.sink) {
+            self.failed = true;
+            error.get_or_insert(err);
+        }
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents a logged event.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LogEntry {
+    NodeStarted {
+        time: f64,
+        node: String,
+        node_id: Id,
+    },
+    ProcessStarted {
+        time: f64,
+        node: String,
+        proc: String,
+    },
+    LocalMessageSent {
+        time: f64,
+        msg_id: String,
+        node: String,
+        proc: String,
+        msg: Message,
+    },
+    LocalMessageReceived {
+        time: f64,
+        msg_id: String,
+        node: String,
+        proc: String,
+        msg: Message,
+    },
+    MessageSent {
+        time: f64,
+        msg_id: String,
+        src_node: String,
+        src_proc: String,
+        dst_node: String,
+        dst_proc: String,
+        msg: Message,
+        /// Lamport time of sender process.
+        #[serde(default)]
+        lamport_time: u64,
+        /// Vector clock of sender process.
+        #[serde(default)]
+        vector_clock: VectorClock,
+        /// Role of the message in a request/response call.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        call: Option<CallTag>,
+    },
+    MessageReceived {
+        time: f64,
+        msg_id: String,
+        #[serde(skip_serializing, default)]
+        src_node: String,
+        #[serde(skip_serializing, default)]
+        src_proc: String,
+        #[serde(skip_serializing, default)]
+        dst_node: String,
+        #[serde(skip_serializing, default)]
+        dst_proc: String,
+        #[serde(skip_serializing, default)]
+        msg: Message,
+        /// Lamport time of receiver process after receiving the message.
+        #[serde(default)]
+        lamport_time: u64,
+        /// Vector clock of receiver process after receiving the message.
+        #[serde(default)]
+        vector_clock: VectorClock,
+    },
+    MessageDropped {
+        time: f64,
+        msg_id: String,
+        #[serde(skip_serializing, default)]
+        src_node: String,
+        #[serde(skip_serializing, default)]
+        src_proc: String,
+        #[serde(skip_serializing, default)]
+        dst_node: String,
+        #[serde(skip_serializing, default)]
+        dst_proc: String,
+        #[serde(skip_serializing, default)]
+        msg: Message,
+    },
+    NodeDisconnected {
+        time: f64,
+        node: String,
+    },
+    NodeConnected {
+        time: f64,
+        node: String,
+    },
+    NodeCrashed {
+        time: f64,
+        node: String,
+    },
+    NodeRecovered {
+        time: f64,
+        node: String,
+    },
+    TimerSet {
+        time: f64,
+        timer_id: String,
+        timer_name: String,
+        node: String,
+        proc: String,
+        delay: f64,
+    },
+    TimerFired {
+        time: f64,
+        timer_id: String,
+        #[serde(skip_serializing, default)]
+        timer_name: String,
+        #[serde(skip_serializing, default)]
+        node: String,
+        #[serde(skip_serializing, default)]
+        proc: String,
+    },
+    TimerCancelled {
+        time: f64,
+        timer_id: String,
+        #[serde(skip_serializing, default)]
+        timer_name: String,
+        #[serde(skip_serializing, default)]
+        node: String,
+        #[serde(skip_serializing, default)]
+        proc: String,
+    },
+    /// Link between a pair of nodes is disabled.
+    LinkDisabled {
+        time: f64,
+        from: String,
+        to: String,
+    },
+    /// Link between a pair of nodes is enabled.
+    LinkEnabled {
+        time: f64,
+        from: String,
+        to: String,
+    },
+    /// Dropping of incoming messages for a node is enabled.
+    DropIncoming {
+        time: f64,
+        node: String,
+    },
+    /// Dropping of incoming messages for a node is disabled.
+    PassIncoming {
+        time: f64,
+        node: String,
+    },
+    /// Dropping of outgoing messages for a node is enabled.
+    DropOutgoing {
+        time: f64,
+        node: String,
+    },
+    /// Dropping of outgoing messages for a node is enabled.
+    PassOutgoing {
+        time: f64,
+        node: String,
+    },
+    /// Network partition is occurred between two groups of nodes.
+    NetworkPartition {
+        time: f64,
+        /// First group of nodes.
+        group1: Vec<String>,
+        /// Second group of nodes.
+        group2: Vec<String>,
+    },
+    /// Network is reset to normal state (all links are working).
+    NetworkReset {
+        time: f64,
+    },
+    /// Process state is updated.
+    ProcessStateUpdated {
+        time: f64,
+        node: String,
+        proc: String,
+        /// String representation of process state.
+        state: String,
+    },
+    /// User-defined event recorded by a process (see [`Context::log`](crate::Context::log)).
+    UserEvent {
+        time: f64,
+        node: String,
+        proc: String,
+        /// User-defined event kind.
+        kind: String,
+        /// User-defined event data.
+        data: String,
+    },
+    /// Process handler returned an error (see [`ProcessError`](crate::error::ProcessError)).
+    ProcessFailed {
+        time: f64,
+        node: String,
+        proc: String,
+        error: String,
+    },
+    /// Model checking session is started.
+    McStarted {},
+    McLocalMessageSent {
+        msg: Message,
+        proc: String,
+    },
+    McLocalMessageReceived {
+        msg: Message,
+        proc: String,
+    },
+    McMessageSent {
+        msg: Message,
+        src: String,
+        dst: String,
+    },
+    McMessageReceived {
+        msg: Message,
+        src: String,
+        dst: String,
+    },
+    McMessageDropped {
+        msg: Message,
+        src: String,
+        dst: String,
+    },
+    McMessageCorrupted {
+        msg: Message,
+        corrupted_msg: Message,
+        src: String,
+        dst: String,
+    },
+    McMessageDuplicated {
+        msg: Message,
+        src: String,
+        dst: String,
+    },
+    McTimerSet {
+        proc: String,
+        timer: String,
+    },
+    McTimerFired {
+        proc: String,
+        timer: String,
+    },
+    McTimerCancelled {
+        proc: String,
+        timer: String,
+    },
+    McNodeCrashed {
+        node: String,
+    },
+    McNetworkReset {},
+    McNetworkPartition {
+        group1: Vec<String>,
+        group2: Vec<String>,
+    },
+    /// User-defined event recorded by a process during model checking.
+    McUserEvent {
+        proc: String,
+        kind: String,
+        data: String,
+    },
+}
+
+impl LogEntry {
+    /// Prints log entry to console.
+    pub fn print(&self) {
+        if let Some(line) = self.console_line(true) {
+            t!(line);
+        }
+    }
+
+    /// Returns the console representation of log entry, optionally with colors.
+    ///
+    /// Returns `None` for entries which are not printed to console.
+    pub fn console_line(&self, colored: bool) -> Option<String> {
+        let line = match self {
+            LogEntry::NodeStarted { .. } => {
+                // format!("{:>9.3} - node started: {}", time, node)
+                return None;
+            }
+            LogEntry::ProcessStarted { .. } => {
+                // format!("{:>9.3} - process started: {} @ {}", time, proc, node)
+                return None;
+            }
+            LogEntry::LocalMessageSent {
+                time,
+                msg_id: _,
+                node: _,
+                proc,
+                msg,
+            } => format!("{:>9.3} {:>10} >>> {:<10} {:?}", time, proc, "local", msg).green(),
+            LogEntry::LocalMessageReceived {
+                time,
+                msg_id: _,
+                node: _,
+                proc,
+                msg,
+            } => format!("{:>9.3} {:>10} <<< {:<10} {:?}", time, proc, "local", msg).cyan(),
+            LogEntry::MessageSent {
+                time,
+                msg_id: _,
+                src_node: _,
+                src_proc,
+                dst_node: _,
+                dst_proc,
+                msg,
+                ..
+            } => format!("{:>9.3} {:>10} --> {:<10} {:?}", time, src_proc, dst_proc, msg).normal(),
+            LogEntry::MessageReceived {
+                time,
+                msg_id: _,
+                src_proc,
+                src_node: _,
+                dst_proc,
+                dst_node: _,
+                msg,
+                ..
+            } => format!("{:>9.3} {:>10} <-- {:<10} {:?}", time, dst_proc, src_proc, msg).normal(),
+            LogEntry::MessageDropped {
+                time: _,
+                msg_id: _,
+                src_proc,
+                src_node: _,
+                dst_proc,
+                dst_node: _,
+                msg,
+            } => format!(
+                "{:>9} {:>10} --x {:<10} {:?} <-- message dropped",
+                "!!!", src_proc, dst_proc, msg
+            )
+            .red(),
+            LogEntry::NodeConnected { time, node } => format!("{time:>9.3} - connected node: {node}").green(),
+            LogEntry::NodeDisconnected { time, node } => format!("{time:>9.3} - disconnected node: {node}").red(),
+            LogEntry::NodeCrashed { time, node } => format!("{time:>9.3} - node crashed: {node}").red(),
+            LogEntry::NodeRecovered { time, node } => format!("{time:>9.3} - node recovered: {node}").green(),
+            LogEntry::TimerSet { .. } => return None,
+            LogEntry::TimerFired {
+                time,
+                timer_id: _,
+                timer_name,
+                node: _,
+                proc,
+            } => format!("{time:>9.3} {proc:>10} !-- {timer_name:<10}").yellow(),
+            LogEntry::TimerCancelled { .. } => return None,
+            LogEntry::LinkDisabled { time, from, to } => {
+                format!("{time:>9.3} - disabled link: {from:>10} --> {to:<10}").red()
+            }
+            LogEntry::LinkEnabled { time, from, to } => {
+                format!("{time:>9.3} - enabled link: {from:>10} --> {to:<10}").green()
+            }
+            LogEntry::DropIncoming { time, node } => format!("{time:>9.3} - drop messages to {node}").red(),
+            LogEntry::PassIncoming { time, node } => format!("{time:>9.3} - pass messages to {node}").green(),
+            LogEntry::DropOutgoing { time, node } => format!("{time:>9.3} - drop messages from {node}").red(),
+            LogEntry::PassOutgoing { time, node } => format!("{time:>9.3} - pass messages from {node}").green(),
+            LogEntry::NetworkPartition { time, group1, group2 } => {
+                format!("{time:>9.3} - network partition: {group1:?} -x- {group2:?}").red()
+            }
+            LogEntry::NetworkReset { time } => format!("{time:>9.3} - network reset, all problems healed").green(),
+            LogEntry::ProcessStateUpdated { .. } => return None,
+            LogEntry::UserEvent {
+                time, proc, kind, data, ..
+            } => format!("{time:>9.3} {proc:>10} *** {kind:<10} {data}").magenta(),
+            LogEntry::ProcessFailed { time, proc, error, .. } => {
+                format!("{time:>9.3} {proc:>10} !!! process failed: {error}").red()
+            }
+            LogEntry::McStarted { .. } => {
+                // "MODEL CHECKING STARTED"
+                return None;
+            }
+            LogEntry::McLocalMessageSent { msg, proc } => format!("{:>10} >>> {:<10} {:?}", proc, "local", msg).green(),
+            LogEntry::McLocalMessageReceived { msg, proc } => {
+                format!("{:>10} <<< {:<10} {:?}", "local", proc, msg).cyan()
+            }
+            LogEntry::McMessageSent { msg, src, dst } => format!("{:>10} --> {:<10} {:?}", src, dst, msg).normal(),
+            LogEntry::McMessageReceived { msg, src, dst } => format!("{:>10} <-- {:<10} {:?}", dst, src, msg).normal(),
+            LogEntry::McMessageDropped { msg, src, dst } => {
+                format!("{src:>10} --x {dst:<10} {msg:?} <-- message dropped").red()
+            }
+            LogEntry::McMessageCorrupted {
+                msg,
+                corrupted_msg,
+                src,
+                dst,
+            } => format!("{src:>10} -x- {dst:<10} {msg:?} ~~> {corrupted_msg:?} <-- message corrupted").blue(),
+            LogEntry::McMessageDuplicated { msg, src, dst } => format!(
+                "{:>9} {:>10} -=≡ {:<10} {:?} <-- message duplicated",
+                "~~~", src, dst, msg
+            )
+            .blue(),
+            LogEntry::McTimerSet { proc, timer } => format!("{:>10} +++ {:<10} <-- timer set", proc, timer).normal(),
+            LogEntry::McTimerFired { proc, timer } => format!("{proc:>10} !-- {timer:<10} <-- timer fired").yellow(),
+            LogEntry::McTimerCancelled { proc, timer } => {
+                format!("{proc:>10} xxx {timer:<10} <-- timer cancelled").yellow()
+            }
+            LogEntry::McNodeCrashed { node } => format!("node crashed: {node}").red(),
+            LogEntry::McNetworkReset {} => "network reset, all problems healed".to_string().green(),
+            LogEntry::McNetworkPartition { group1, group2 } => {
+                format!("network partition: {group1:?} -x- {group2:?}").red()
+            }
+            LogEntry::McUserEvent { proc, kind, data } => format!("{proc:>10} *** {kind:<10} {data}").magenta(),
+        };
+        if colored {
+            Some(line.to_string())
+        } else {
+            Some(line.clear().to_string())
+        }
+    }
+
+    /// Returns the name of the entry kind (i.e. the variant name).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LogEntry::NodeStarted { .. } => "NodeStarted",
+            LogEntry::ProcessStarted { .. } => "ProcessStarted",
+            LogEntry::LocalMessageSent { .. } => "LocalMessageSent",
+            LogEntry::LocalMessageReceived { .. } => "LocalMessageReceived",
+            LogEntry::MessageSent { .. } => "MessageSent",
+            LogEntry::MessageReceived { .. } => "MessageReceived",
+            LogEntry::MessageDropped { .. } => "MessageDropped",
+            LogEntry::NodeDisconnected { .. } => "NodeDisconnected",
+            LogEntry::NodeConnected { .. } => "NodeConnected",
+            LogEntry::NodeCrashed { .. } => "NodeCrashed",
+            LogEntry::NodeRecovered { .. } => "NodeRecovered",
+            LogEntry::TimerSet { .. } => "TimerSet",
+            LogEntry::TimerFired { .. } => "TimerFired",
+            LogEntry::TimerCancelled { .. } => "TimerCancelled",
+            LogEntry::LinkDisabled { .. } => "LinkDisabled",
+            LogEntry::LinkEnabled { .. } => "LinkEnabled",
+            LogEntry::DropIncoming { .. } => "DropIncoming",
+            LogEntry::PassIncoming { .. } => "PassIncoming",
+            LogEntry::DropOutgoing { .. } => "DropOutgoing",
+            LogEntry::PassOutgoing { .. } => "PassOutgoing",
+            LogEntry::NetworkPartition { .. } => "NetworkPartition",
+            LogEntry::NetworkReset { .. } => "NetworkReset",
+            LogEntry::ProcessStateUpdated { .. } => "ProcessStateUpdated",
+            LogEntry::UserEvent { .. } => "UserEvent",
+            LogEntry::ProcessFailed { .. } => "ProcessFailed",
+            LogEntry::McStarted { .. } => "McStarted",
+            LogEntry::McLocalMessageSent { .. } => "McLocalMessageSent",
+            LogEntry::McLocalMessageReceived { .. } => "McLocalMessageReceived",
+            LogEntry::McMessageSent { .. } => "McMessageSent",
+            LogEntry::McMessageReceived { .. } => "McMessageReceived",
+            LogEntry::McMessageDropped { .. } => "McMessageDropped",
+            LogEntry::McMessageCorrupted { .. } => "McMessageCorrupted",
+            LogEntry::McMessageDuplicated { .. } => "McMessageDuplicated",
+            LogEntry::McTimerSet { .. } => "McTimerSet",
+            LogEntry::McTimerFired { .. } => "McTimerFired",
+            LogEntry::McTimerCancelled { .. } => "McTimerCancelled",
+            LogEntry::McNodeCrashed { .. } => "McNodeCrashed",
+            LogEntry::McNetworkReset { .. } => "McNetworkReset",
+            LogEntry::McNetworkPartition { .. } => "McNetworkPartition",
+            LogEntry::McUserEvent { .. } => "McUserEvent",
+        }
+    }
+
+    /// Returns the event time.
+    ///
+    /// Returns `None` for model checking entries which are not timestamped.
+    pub fn time(&self) -> Option<f64> {
+        match self {
+            LogEntry::NodeStarted { time, .. }
+            | LogEntry::ProcessStarted { time, .. }
+            | LogEntry::LocalMessageSent { time, .. }
+            | LogEntry::LocalMessageReceived { time, .. }
+            | LogEntry::MessageSent { time, .. }
+            | LogEntry::MessageReceived { time, .. }
+            | LogEntry::MessageDropped { time, .. }
+            | LogEntry::NodeDisconnected { time, .. }
+            | LogEntry::NodeConnected { time, .. }
+            | LogEntry::NodeCrashed { time, .. }
+            | LogEntry::NodeRecovered { time, .. }
+            | LogEntry::TimerSet { time, .. }
+            | LogEntry::TimerFired { time, .. }
+            | LogEntry::TimerCancelled { time, .. }
+            | LogEntry::LinkDisabled { time, .. }
+            | LogEntry::LinkEnabled { time, .. }
+            | LogEntry::DropIncoming { time, .. }
+            | LogEntry::PassIncoming { time, .. }
+            | LogEntry::DropOutgoing { time, .. }
+            | LogEntry::PassOutgoing { time, .. }
+            | LogEntry::NetworkPartition { time, .. }
+            | LogEntry::NetworkReset { time }
+            | LogEntry::ProcessStateUpdated { time, .. }
+            | LogEntry::UserEvent { time, .. }
+            | LogEntry::ProcessFailed { time, .. } => Some(*time),
+            _ => None,
+        }
+    }
+
+    /// Returns the message associated with the entry, if any.
+    pub fn msg(&self) -> Option<&Message> {
+        match self {
+            LogEntry::LocalMessageSent { msg, .. }
+            | LogEntry::LocalMessageReceived { msg, .. }
+            | LogEntry::MessageSent { msg, .. }
+            | LogEntry::MessageReceived { msg, .. }
+            | LogEntry::MessageDropped { msg, .. }
+            | LogEntry::McLocalMessageSent { msg, .. }
+            | LogEntry::McLocalMessageReceived { msg, .. }
+            | LogEntry::McMessageSent { msg, .. }
+            | LogEntry::McMessageReceived { msg, .. }
+            | LogEntry::McMessageDropped { msg, .. }
+            | LogEntry::McMessageCorrupted { msg, .. }
+            | LogEntry::McMessageDuplicated { msg, .. } => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of processes involved in the event.
+    ///
+    /// For message events the sender goes first, followed by the receiver.
+    pub fn procs(&self) -> Vec<&str> {
+        match self {
+            LogEntry::ProcessStarted { proc, .. }
+            | LogEntry::LocalMessageSent { proc, .. }
+            | LogEntry::LocalMessageReceived { proc, .. }
+            | LogEntry::TimerSet { proc, .. }
+            | LogEntry::TimerFired { proc, .. }
+            | LogEntry::TimerCancelled { proc, .. }
+            | LogEntry::ProcessStateUpdated { proc, .. }
+            | LogEntry::UserEvent { proc, .. }
+            | LogEntry::ProcessFailed { proc, .. }
+            | LogEntry::McLocalMessageSent { proc, .. }
+            | LogEntry::McLocalMessageReceived { proc, .. }
+            | LogEntry::McTimerSet { proc, .. }
+            | LogEntry::McTimerFired { proc, .. }
+            | LogEntry::McTimerCancelled { proc, .. }
+            | LogEntry::McUserEvent { proc, .. } => vec![proc],
+            LogEntry::MessageSent { src_proc, dst_proc, .. }
+            | LogEntry::MessageReceived { src_proc, dst_proc, .. }
+            | LogEntry::MessageDropped { src_proc, dst_proc, .. } => vec![src_proc, dst_proc],
+            LogEntry::McMessageSent { src, dst, .. }
+            | LogEntry::McMessageReceived { src, dst, .. }
+            | LogEntry::McMessageDropped { src, dst, .. }
+            | LogEntry::McMessageCorrupted { src, dst, .. }
+            | LogEntry::McMessageDuplicated { src, dst, .. } => vec![src, dst],
+            _ => vec![],
+        }
+    }
+
+    /// Returns the names of nodes involved in the event.
+    ///
+    /// Model checking entries do not carry node names except for node crashes and network partitions.
+    pub fn nodes(&self) -> Vec<&str> {
+        match self {
+            LogEntry::NodeStarted { node, .. }
+            | LogEntry::ProcessStarted { node, .. }
+            | LogEntry::LocalMessageSent { node, .. }
+            | LogEntry::LocalMessageReceived { node, .. }
+            | LogEntry::NodeDisconnected { node, .. }
+            | LogEntry::NodeConnected { node, .. }
+            | LogEntry::NodeCrashed { node, .. }
+            | LogEntry::NodeRecovered { node, .. }
+            | LogEntry::TimerSet { node, .. }
+            | LogEntry::TimerFired { node, .. }
+            | LogEntry::TimerCancelled { node, .. }
+            | LogEntry::DropIncoming { node, .. }
+            | LogEntry::PassIncoming { node, .. }
+            | LogEntry::DropOutgoing { node, .. }
+            | LogEntry::PassOutgoing { node, .. }
+            | LogEntry::ProcessStateUpdated { node, .. }
+            | LogEntry::UserEvent { node, .. }
+            | LogEntry::ProcessFailed { node, .. }
+            | LogEntry::McNodeCrashed { node } => vec![node],
+            LogEntry::MessageSent { src_node, dst_node, .. }
+            | LogEntry::MessageReceived { src_node, dst_node, .. }
+            | LogEntry::MessageDropped { src_node, dst_node, .. } => vec![src_node, dst_node],
+            LogEntry::LinkDisabled { from, to, .. } | LogEntry::LinkEnabled { from, to, .. } => vec![from, to],
+            LogEntry::NetworkPartition { group1, group2, .. } | LogEntry::McNetworkPartition { group1, group2 } => {
+                group1.iter().chain(group2.iter()).map(|s| s.as_str()).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Checks if event is [`LogEntry::McMessageDropped`].
+    pub fn is_mc_message_dropped(&self) -> bool {
+        matches!(self, Self::McMessageDropped { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McMessageDuplicated`].
+    pub fn is_mc_message_duplicated(&self) -> bool {
+        matches!(self, Self::McMessageDuplicated { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McMessageSent`].
+    pub fn is_mc_message_sent(&self) -> bool {
+        matches!(self, Self::McMessageSent { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McLocalMessageSent`].
+    pub fn is_mc_local_message_sent(&self) -> bool {
+        matches!(self, Self::McLocalMessageSent { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McLocalMessageReceived`].
+    pub fn is_mc_message_received(&self) -> bool {
+        matches!(self, Self::McMessageReceived { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McTimerSet`].
+    pub fn is_mc_timer_set(&self) -> bool {
+        matches!(self, Self::McTimerSet { .. })
+    }
+
+    /// Checks if event is [`LogEntry::McTimerFired`].
+    pub fn is_mc_timer_fired(&self) -> bool {
+        matches!(self, Self::McTimerFired { .. })
+    }
+}
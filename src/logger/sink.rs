@@ -0,0 +1,87 @@
+//! Destinations for logged events.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::logger::binary::BinaryLogWriter;
+use crate::logger::LogEntry;
+
+/// Receives logged events as they are produced.
+///
+/// Sinks allow to process long simulation traces in a streaming fashion without keeping them in memory.
+pub trait LogSink {
+    /// Processes a logged event.
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()>;
+
+    /// Flushes buffered data, if any.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn create_file(path: &Path) -> io::Result<BufWriter<File>> {
+    let file = OpenOptions::new().create(true).truncate(true).write(true).open(path)?;
+    Ok(BufWriter::new(file))
+}
+
+/// Writes events in JSON lines format (one serialized [`LogEntry`] per line).
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    /// Creates a sink writing to the specified writer.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl JsonLinesSink<BufWriter<File>> {
+    /// Creates a sink writing to the specified file.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(create_file(path)?))
+    }
+}
+
+impl<W: Write> LogSink for JsonLinesSink<W> {
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes events in the compact [binary format](crate::logger::binary).
+pub struct BinarySink<W: Write> {
+    writer: BinaryLogWriter<W>,
+}
+
+impl<W: Write> BinarySink<W> {
+    /// Creates a sink writing to the specified writer.
+    pub fn new(writer: W) -> io::Result<Self> {
+        Ok(Self {
+            writer: BinaryLogWriter::new(writer)?,
+        })
+    }
+}
+
+impl BinarySink<BufWriter<File>> {
+    /// Creates a sink writing to the specified file.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Self::new(create_file(path)?)
+    }
+}
+
+impl<W: Write> LogSink for BinarySink<W> {
+    fn write(&mut self, entry: &LogEntry) -> io::Result<()> {
+        self.writer.write_entry(entry)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -1,8 +1,9 @@
 //! Model checking error.
 
 use std::fmt::Debug;
+use std::path::Path;
 
-use crate::logger::LogEntry;
+use crate::logger::{html, LogEntry};
 
 /// Stores information about an error found by model checking.
 #[derive(PartialEq, Debug)]
@@ -35,4 +36,11 @@ impl McError {
             entry.print();
         }
     }
+
+    /// Writes error trace to a self-contained HTML file with a trace viewer.
+    ///
+    /// See [`html::export_html`] for details.
+    pub fn export_html(&self, path: &Path) -> std::io::Result<()> {
+        html::export_html(&self.trace, path)
+    }
 }
@@ -3,6 +3,7 @@
 use serde::Serialize;
 
 use crate::logger::LogEntry;
+use crate::rpc::CallTag;
 use crate::Message;
 
 use crate::mc::network::DeliveryOptions;
@@ -27,6 +28,9 @@ pub enum McEvent {
 
         /// Network delivery options for the message.
         options: DeliveryOptions,
+
+        /// Role of the message in a request/response call.
+        call: Option<CallTag>,
     },
 
     /// The event of a timer expiration.
@@ -115,6 +119,7 @@ impl McEvent {
                         max_dupl_count,
                         can_be_corrupted,
                     },
+                call,
             } => Some(McEvent::MessageReceived {
                 msg: msg.clone(),
                 src: src.clone(),
@@ -124,6 +129,7 @@ impl McEvent {
                     max_dupl_count: max_dupl_count - 1,
                     can_be_corrupted: *can_be_corrupted,
                 },
+                call: *call,
             }),
             _ => None,
         }
@@ -143,12 +149,7 @@ impl McEvent {
     /// Converts McEvent to LogEntry.
     pub fn to_log_entry(&self) -> LogEntry {
         match self {
-            Self::MessageReceived {
-                msg,
-                src,
-                dst,
-                options: _,
-            } => LogEntry::McMessageReceived {
+            Self::MessageReceived { msg, src, dst, .. } => LogEntry::McMessageReceived {
                 msg: msg.clone(),
                 src: src.clone(),
                 dst: dst.clone(),
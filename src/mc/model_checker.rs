@@ -30,9 +30,12 @@ impl ModelChecker {
 
         let mc_net = McNetwork::new(sys.network());
 
-        let trace = sys.logger().trace().clone();
+        let trace = sys.logger().trace().to_vec();
         let trace_handler = Rc::new(RefCell::new(TraceHandler::new(trace)));
 
+        // the topology does not change during model checking
+        let topology = Rc::new(RefCell::new(sys.topology().borrow().clone()));
+
         let mut nodes: HashMap<String, McNode> = HashMap::new();
         for node in sys.nodes() {
             let node = sys.get_node(&node).unwrap();
@@ -42,6 +45,7 @@ impl ModelChecker {
                     node.name.clone(),
                     node.processes(),
                     trace_handler.clone(),
+                    topology.clone(),
                     node.clock_skew(),
                 ),
             );
@@ -50,12 +54,15 @@ impl ModelChecker {
         let mut events = PendingEvents::new();
         for event in sim.dump_events() {
             cast!(match event.data {
-                MessageReceived { msg, src, dst, .. } => {
+                MessageReceived {
+                    msg, src, dst, call, ..
+                } => {
                     events.push(McEvent::MessageReceived {
                         msg,
                         src,
                         dst,
                         options: DeliveryOptions::NoFailures(McTime::from(mc_net.max_delay())),
+                        call,
                     });
                 }
                 TimerFired { proc, timer } => {
@@ -77,14 +84,19 @@ impl ModelChecker {
     where
         S: Strategy,
     {
-        let initial_state = self.system.get_state();
+        let initial_state = self.system.get_state()?;
         self.system.trace_handler.borrow_mut().push(LogEntry::McStarted {});
         preliminary_callback(&mut self.system);
-        strategy.mark_visited(self.system.get_state());
-        let res = strategy.run(&mut self.system);
+        let res = match self.system.take_local_message_error() {
+            Some(err) => Err(self.system.error(err)),
+            None => self.system.get_state().and_then(|state| {
+                strategy.mark_visited(state);
+                strategy.run(&mut self.system)
+            }),
+        };
         strategy.reset();
         // McSystem is always rolled back to the state before MC run
-        self.system.set_state(initial_state);
+        self.system.set_state(initial_state)?;
         res
     }
 
@@ -132,7 +144,7 @@ impl ModelChecker {
         // sort starting states by increasing depth to produce shorter error traces
         states.sort_by_key(|x| x.depth);
         for state in states {
-            self.system.set_state(state);
+            self.system.set_state(state)?;
             let stats = self.run_impl(&mut strategy, &preliminary_callback)?;
             total_stats.combine(stats);
         }
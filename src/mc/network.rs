@@ -5,6 +5,7 @@ use std::collections::{HashMap, HashSet};
 
 use serde::Serialize;
 
+use crate::rpc::CallTag;
 use crate::{Message, Network};
 
 use crate::mc::{McEvent, McTime};
@@ -124,7 +125,7 @@ impl McNetwork {
         self.drop_outgoing.clear();
     }
 
-    pub(crate) fn send_message(&mut self, msg: Message, src: String, dst: String) -> McEvent {
+    pub(crate) fn send_message(&mut self, msg: Message, src: String, dst: String, call: Option<CallTag>) -> McEvent {
         let src_node = self.get_proc_node(&src).clone();
         let dst_node = self.get_proc_node(&dst).clone();
 
@@ -134,6 +135,7 @@ impl McNetwork {
                 src,
                 dst,
                 options: DeliveryOptions::NoFailures(McTime::from(self.max_delay)),
+                call,
             }
         } else if !self.drop_outgoing.contains(&src_node)
             && !self.drop_incoming.contains(&dst_node)
@@ -148,6 +150,7 @@ impl McNetwork {
                     max_dupl_count: if self.dupl_rate == 0. { 0 } else { DUPL_COUNT },
                     can_be_corrupted: self.corrupt_rate > 0.,
                 },
+                call,
             }
         } else {
             McEvent::MessageDropped {
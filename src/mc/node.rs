@@ -5,8 +5,11 @@ use std::rc::Rc;
 
 use colored::Colorize;
 
+use crate::context::Topology;
+use crate::error::{call_handler, HandlerFailure};
 use crate::logger::LogEntry;
-use crate::node::ProcessEntry;
+use crate::node::{PeriodicTimer, ProcessEntry};
+use crate::rpc::{self, CallTag, PendingCall};
 use crate::{Context, EventLogEntry, Message, ProcessEvent, ProcessState, TimerBehavior};
 
 use crate::mc::network::DeliveryOptions;
@@ -18,27 +21,42 @@ pub struct ProcessEntryState {
     pub event_log: Vec<EventLogEntry>,
     pub local_outbox: Vec<Message>,
     pub pending_timers: HashMap<String, u64>,
+    pub timer_deadlines: HashMap<String, f64>,
+    pub(crate) periodic_timers: HashMap<String, PeriodicTimer>,
+    pub(crate) pending_calls: BTreeMap<u64, PendingCall>,
+    pub(crate) next_call_id: u64,
     pub sent_message_count: u64,
     pub received_message_count: u64,
+    /// Maximum size of process inner data observed so far (see [`Process::size`](crate::Process::size)).
+    pub max_size: u64,
 }
 
 impl Hash for ProcessEntryState {
     fn hash<H: Hasher>(&self, hasher: &mut H) {
         self.proc_state.hash_with_dyn(hasher);
         self.local_outbox.hash(hasher);
+        self.pending_calls.hash(hasher);
+        let mut periodic_timers: Vec<_> = self
+            .periodic_timers
+            .iter()
+            .map(|(name, timer)| (name, timer.period.to_bits(), timer.jitter.to_bits(), timer.rearms))
+            .collect();
+        periodic_timers.sort();
+        periodic_timers.hash(hasher);
     }
 }
 
 impl PartialEq for ProcessEntryState {
     fn eq(&self, other: &Self) -> bool {
         let equal_process_states = self.proc_state.eq_with_dyn(&*other.proc_state);
-        equal_process_states && self.local_outbox == other.local_outbox
+        equal_process_states
+            && self.local_outbox == other.local_outbox
+            && self.pending_calls == other.pending_calls
+            && self.periodic_timers == other.periodic_timers
     }
 }
 
-impl Eq for ProcessEntryState {
-    fn assert_receiver_is_total_eq(&self) {}
-}
+impl Eq for ProcessEntryState {}
 
 impl ProcessEntry {
     fn get_state(&self) -> Result<ProcessEntryState, String> {
@@ -47,8 +65,13 @@ impl ProcessEntry {
             event_log: self.event_log.clone(),
             local_outbox: self.local_outbox.clone(),
             pending_timers: self.pending_timers.clone(),
+            timer_deadlines: self.timer_deadlines.clone(),
+            periodic_timers: self.periodic_timers.clone(),
+            pending_calls: self.pending_calls.clone(),
+            next_call_id: self.next_call_id,
             sent_message_count: self.sent_message_count,
             received_message_count: self.received_message_count,
+            max_size: self.max_size,
         })
     }
 
@@ -57,8 +80,13 @@ impl ProcessEntry {
         self.event_log = state.event_log;
         self.local_outbox = state.local_outbox;
         self.pending_timers = state.pending_timers;
+        self.timer_deadlines = state.timer_deadlines;
+        self.periodic_timers = state.periodic_timers;
+        self.pending_calls = state.pending_calls;
+        self.next_call_id = state.next_call_id;
         self.sent_message_count = state.sent_message_count;
         self.received_message_count = state.received_message_count;
+        self.max_size = state.max_size;
         Ok(())
     }
 }
@@ -74,34 +102,47 @@ pub struct McNode {
     name: String,
     pub(crate) processes: HashMap<String, ProcessEntry>,
     trace_handler: Rc<RefCell<TraceHandler>>,
+    topology: Rc<RefCell<Topology>>,
     clock_skew: f64,
     is_crashed: bool,
+    periodic_timer_limit: u32,
 }
 
+/// Default number of times a periodic timer is re-armed in model checking.
+pub(crate) const DEFAULT_PERIODIC_TIMER_LIMIT: u32 = 3;
+
 impl McNode {
     pub(crate) fn new(
         name: String,
         processes: HashMap<String, ProcessEntry>,
         trace_handler: Rc<RefCell<TraceHandler>>,
+        topology: Rc<RefCell<Topology>>,
         clock_skew: f64,
     ) -> Self {
         Self {
             name,
             processes,
             trace_handler,
+            topology,
             clock_skew,
             is_crashed: false,
+            periodic_timer_limit: DEFAULT_PERIODIC_TIMER_LIMIT,
         }
     }
 
+    pub(crate) fn set_periodic_timer_limit(&mut self, limit: u32) {
+        self.periodic_timer_limit = limit;
+    }
+
     pub fn on_message_received(
         &mut self,
         proc: String,
         msg: Message,
         from: String,
+        call: Option<CallTag>,
         time: f64,
         random_seed: u64,
-    ) -> Vec<McEvent> {
+    ) -> Result<Vec<McEvent>, String> {
         assert!(!self.is_crashed, "should not receive message on crashed node");
         let proc_entry = self.processes.get_mut(&proc).unwrap();
         proc_entry.event_log.push(EventLogEntry::new(
@@ -110,35 +151,102 @@ impl McNode {
                 msg: msg.clone(),
                 src: from.clone(),
                 dst: proc.clone(),
+                call,
             },
         ));
         proc_entry.received_message_count += 1;
 
-        let mut proc_ctx = Context::basic(proc.to_string(), time, self.clock_skew, random_seed);
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            time,
+            self.clock_skew,
+            random_seed,
+            &self.name,
+            &self.topology,
+        );
 
-        proc_entry
-            .proc_impl
-            .on_message(msg, from, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        let res = match call {
+            None => call_handler(|| proc_entry.proc_impl.on_message(msg, from, &mut proc_ctx)),
+            Some(CallTag::Request(call_id)) => {
+                proc_ctx.set_current_call(&from, call_id);
+                call_handler(|| proc_entry.proc_impl.on_message(msg, from, &mut proc_ctx))
+            }
+            Some(CallTag::Response(call_id)) => {
+                // late or duplicated responses are ignored
+                if !matches!(proc_entry.pending_calls.get(&call_id), Some(call) if call.dst == from) {
+                    return Ok(Vec::new());
+                }
+                proc_entry.pending_calls.remove(&call_id);
+                proc_ctx.cancel_timer(&rpc::timeout_timer(call_id));
+                call_handler(|| proc_entry.proc_impl.on_response(call_id, msg, from, &mut proc_ctx))
+            }
+        };
+        proc_entry.update_max_size();
+        self.check_handler_result(res, &proc)?;
 
-        self.handle_process_actions(proc, 0.0, proc_ctx.actions())
+        Ok(self.handle_process_actions(proc, 0.0, time, proc_ctx.actions()))
     }
 
-    pub fn on_timer_fired(&mut self, proc: String, timer: String, time: f64, random_seed: u64) -> Vec<McEvent> {
+    pub fn on_timer_fired(
+        &mut self,
+        proc: String,
+        timer: String,
+        time: f64,
+        random_seed: u64,
+    ) -> Result<Vec<McEvent>, String> {
         assert!(!self.is_crashed, "should not fire timer on crashed node");
         let proc_entry = self.processes.get_mut(&proc).unwrap();
         proc_entry.pending_timers.remove(&timer);
+        proc_entry.timer_deadlines.remove(&timer);
 
-        let mut proc_ctx = Context::basic(proc.to_string(), time, self.clock_skew, random_seed);
+        // periodic timer is re-armed without jitter a bounded number of times to keep the state space finite
+        let mut new_events = Vec::new();
+        if let Some(periodic) = proc_entry.periodic_timers.get_mut(&timer) {
+            if periodic.rearms < self.periodic_timer_limit {
+                periodic.rearms += 1;
+                new_events.push(McEvent::TimerFired {
+                    proc: proc.clone(),
+                    timer: timer.clone(),
+                    timer_delay: McTime::from(periodic.period),
+                });
+                proc_entry.pending_timers.insert(timer.clone(), 0);
+                proc_entry.timer_deadlines.insert(timer.clone(), time + periodic.period);
+                self.trace_handler.borrow_mut().push(LogEntry::McTimerSet {
+                    proc: proc.clone(),
+                    timer: timer.clone(),
+                });
+            } else {
+                proc_entry.periodic_timers.remove(&timer);
+            }
+        }
+
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            time,
+            self.clock_skew,
+            random_seed,
+            &self.name,
+            &self.topology,
+        );
 
-        proc_entry
-            .proc_impl
-            .on_timer(timer, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        let res = match rpc::parse_timeout_timer(&timer) {
+            Some(call_id) => match proc_entry.pending_calls.remove(&call_id) {
+                Some(call) => call_handler(|| {
+                    proc_entry
+                        .proc_impl
+                        .on_call_timeout(call_id, call.request, call.dst, &mut proc_ctx)
+                }),
+                None => return Ok(new_events),
+            },
+            None => call_handler(|| proc_entry.proc_impl.on_timer(timer, &mut proc_ctx)),
+        };
+        proc_entry.update_max_size();
+        self.check_handler_result(res, &proc)?;
 
-        self.handle_process_actions(proc, 0.0, proc_ctx.actions())
+        new_events.extend(self.handle_process_actions(proc, 0.0, time, proc_ctx.actions()));
+        Ok(new_events)
     }
 
     pub fn on_local_message_received(
@@ -147,68 +255,92 @@ impl McNode {
         msg: Message,
         time: f64,
         random_seed: u64,
-    ) -> Vec<McEvent> {
+    ) -> Result<Vec<McEvent>, String> {
         assert!(!self.is_crashed, "should not receive local message on crashed node");
         let proc_entry = self.processes.get_mut(&proc).unwrap();
-        let mut proc_ctx = Context::basic(proc.to_string(), time, self.clock_skew, random_seed);
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            time,
+            self.clock_skew,
+            random_seed,
+            &self.name,
+            &self.topology,
+        );
 
-        proc_entry
-            .proc_impl
-            .on_local_message(msg, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        let res = call_handler(|| proc_entry.proc_impl.on_local_message(msg, &mut proc_ctx));
+        proc_entry.update_max_size();
+        self.check_handler_result(res, &proc)?;
 
-        self.handle_process_actions(proc, time, proc_ctx.actions())
+        Ok(self.handle_process_actions(proc, time, time, proc_ctx.actions()))
     }
 
-    pub fn get_state(&self) -> McNodeState {
-        let proc_states = self
-            .processes
-            .iter()
-            .map(|(proc, entry)| {
-                (
-                    proc.clone(),
-                    entry
-                        .get_state()
-                        .map_err(|e| self.handle_process_error(e, proc.clone()))
-                        .unwrap(),
-                )
-            })
-            .collect();
-        McNodeState {
+    pub fn get_state(&self) -> Result<McNodeState, String> {
+        let mut proc_states = BTreeMap::new();
+        for (proc, entry) in &self.processes {
+            let res = call_handler(|| entry.get_state());
+            proc_states.insert(proc.clone(), self.check_handler_result(res, proc)?);
+        }
+        Ok(McNodeState {
             proc_states,
             is_crashed: self.is_crashed,
-        }
+        })
     }
 
-    pub fn set_state(&mut self, state: McNodeState) {
+    pub fn set_state(&mut self, state: McNodeState) -> Result<(), String> {
         for (proc, state) in state.proc_states {
-            self.processes
-                .get_mut(&proc)
-                .unwrap()
-                .set_state(state)
-                .map_err(|e| self.handle_process_error(e, proc.clone()))
-                .unwrap();
+            let entry = self.processes.get_mut(&proc).unwrap();
+            let res = call_handler(|| entry.set_state(state));
+            self.check_handler_result(res, &proc)?;
         }
         self.is_crashed = state.is_crashed;
+        Ok(())
     }
 
     pub(crate) fn crash(&mut self) {
         self.is_crashed = true;
     }
 
-    fn handle_process_actions(&mut self, proc: String, time: f64, actions: Vec<ProcessEvent>) -> Vec<McEvent> {
+    fn create_context(
+        proc: &str,
+        proc_entry: &ProcessEntry,
+        time: f64,
+        clock_skew: f64,
+        random_seed: u64,
+        node_name: &str,
+        topology: &Rc<RefCell<Topology>>,
+    ) -> Context {
+        let mut ctx = Context::basic(proc.to_string(), time, clock_skew, random_seed);
+        ctx.set_topology(node_name, topology.clone());
+        ctx.set_next_call_id(proc_entry.next_call_id);
+        ctx.set_timers(
+            proc_entry
+                .timer_deadlines
+                .iter()
+                .map(|(name, deadline)| (name.clone(), deadline - time)),
+        );
+        ctx
+    }
+
+    fn handle_process_actions(
+        &mut self,
+        proc: String,
+        time: f64,
+        now: f64,
+        actions: Vec<ProcessEvent>,
+    ) -> Vec<McEvent> {
         let mut new_events = Vec::new();
         for action in actions {
             let proc_entry = self.processes.get_mut(&proc).unwrap();
             proc_entry.event_log.push(EventLogEntry::new(time, action.clone()));
             match action {
-                ProcessEvent::MessageSent { msg, src, dst } => {
+                ProcessEvent::MessageSent { msg, src, dst, call } => {
                     new_events.push(McEvent::MessageReceived {
                         msg: msg.clone(),
                         src: src.clone(),
                         dst: dst.clone(),
                         options: DeliveryOptions::NoFailures(0.0.into()),
+                        call,
                     });
                     proc_entry.sent_message_count += 1;
 
@@ -224,38 +356,66 @@ impl McNode {
                     };
                     self.trace_handler.borrow_mut().push(log_entry);
                 }
-                ProcessEvent::TimerSet { name, delay, behavior } => {
-                    if behavior == TimerBehavior::OverrideExisting || !proc_entry.pending_timers.contains_key(&name) {
-                        let event = McEvent::TimerFired {
-                            timer: name.clone(),
-                            proc: proc.clone(),
-                            timer_delay: McTime::from(delay),
-                        };
-                        new_events.push(event);
-                        // event_id is 0 since it is not used in model checking
-                        proc_entry.pending_timers.insert(name.clone(), 0);
-
-                        let log_entry = LogEntry::McTimerSet {
-                            proc: proc.clone(),
-                            timer: name,
-                        };
-                        self.trace_handler.borrow_mut().push(log_entry);
+                ProcessEvent::TimerSet { name, delay, behavior }
+                    if behavior != TimerBehavior::SetOnce || !proc_entry.pending_timers.contains_key(&name) =>
+                {
+                    match behavior {
+                        TimerBehavior::Periodic { period, jitter } => {
+                            let periodic = PeriodicTimer {
+                                period,
+                                jitter,
+                                rearms: 0,
+                            };
+                            proc_entry.periodic_timers.insert(name.clone(), periodic);
+                        }
+                        _ => {
+                            proc_entry.periodic_timers.remove(&name);
+                        }
                     }
+                    proc_entry.timer_deadlines.insert(name.clone(), now + delay);
+                    let event = McEvent::TimerFired {
+                        timer: name.clone(),
+                        proc: proc.clone(),
+                        timer_delay: McTime::from(delay),
+                    };
+                    new_events.push(event);
+                    // event_id is 0 since it is not used in model checking
+                    proc_entry.pending_timers.insert(name.clone(), 0);
+
+                    let log_entry = LogEntry::McTimerSet {
+                        proc: proc.clone(),
+                        timer: name,
+                    };
+                    self.trace_handler.borrow_mut().push(log_entry);
                 }
-                ProcessEvent::TimerCancelled { name } => {
-                    if proc_entry.pending_timers.remove(&name).is_some() {
-                        let event = McEvent::TimerCancelled {
-                            timer: name.clone(),
-                            proc: proc.clone(),
-                        };
-                        new_events.push(event);
-
-                        let log_entry = LogEntry::McTimerCancelled {
-                            proc: proc.clone(),
-                            timer: name,
-                        };
-                        self.trace_handler.borrow_mut().push(log_entry);
-                    }
+                ProcessEvent::TimerCancelled { name } if proc_entry.pending_timers.remove(&name).is_some() => {
+                    proc_entry.periodic_timers.remove(&name);
+                    proc_entry.timer_deadlines.remove(&name);
+                    let event = McEvent::TimerCancelled {
+                        timer: name.clone(),
+                        proc: proc.clone(),
+                    };
+                    new_events.push(event);
+
+                    let log_entry = LogEntry::McTimerCancelled {
+                        proc: proc.clone(),
+                        timer: name,
+                    };
+                    self.trace_handler.borrow_mut().push(log_entry);
+                }
+                ProcessEvent::UserEvent { kind, data } => {
+                    let log_entry = LogEntry::McUserEvent {
+                        proc: proc.clone(),
+                        kind,
+                        data,
+                    };
+                    self.trace_handler.borrow_mut().push(log_entry);
+                }
+                ProcessEvent::CallStarted { call_id, dst, msg } => {
+                    proc_entry
+                        .pending_calls
+                        .insert(call_id, PendingCall { dst, request: msg });
+                    proc_entry.next_call_id = call_id + 1;
                 }
                 _ => {}
             }
@@ -263,6 +423,18 @@ impl McNode {
         new_events
     }
 
+    /// Panics on the error returned by the process, converts a caught panic into an error message.
+    fn check_handler_result<R>(&self, res: Result<R, HandlerFailure>, proc: &str) -> Result<R, String> {
+        match res {
+            Ok(value) => Ok(value),
+            Err(HandlerFailure::Error(err)) => panic!("{}", self.handle_process_error(err, proc.to_string())),
+            Err(HandlerFailure::Panic(message)) => Err(format!(
+                "process '{}' on node '{}' panicked: {}",
+                proc, self.name, message
+            )),
+        }
+    }
+
     fn handle_process_error(&self, err: String, proc: String) -> &str {
         for event in self.trace_handler.borrow().trace() {
             event.print();
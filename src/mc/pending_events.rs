@@ -280,6 +280,7 @@ mod tests {
             src: "src".to_string(),
             dst: "dst".to_string(),
             options: DeliveryOptions::NoFailures(McTime::from(0.0)),
+            call: None,
         });
         assert_eq!(
             pending_events.available_events(&EventOrderingMode::Normal),
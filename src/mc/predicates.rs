@@ -82,6 +82,26 @@ pub mod invariants {
         })
     }
 
+    /// Checks that the maximum size of inner data of each process does not exceed the given number of bytes.
+    ///
+    /// The size is tracked using [`Process::size`](crate::Process::size) and [`Process::max_size`](crate::Process::max_size)
+    /// (see [`crate::size`]).
+    pub fn process_size_limit(limit: u64) -> InvariantFn {
+        boxed!(move |state: &McState| {
+            for (node_name, node) in state.node_states.iter() {
+                for (proc_name, proc) in node.proc_states.iter() {
+                    if proc.max_size > limit {
+                        return Err(format!(
+                            "size of process '{proc_name}' on node '{node_name}' exceeds the limit: {} > {limit} bytes",
+                            proc.max_size
+                        ));
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
     /// Verifies that the set of local messages delivered by a process matches exactly the expected messages.
     /// Message duplications or unexpected messages are not allowed.
     pub fn received_messages<S>(node: S, proc: S, messages_expected: HashSet<String>) -> InvariantFn
@@ -2,8 +2,13 @@
 
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use serde::de::DeserializeOwned;
 
 use crate::logger::LogEntry;
+use crate::process::{downcast_state, parse_state_json, ProcessAccessError};
+use crate::ProcessState;
 
 use crate::mc::{McNetwork, McNodeState, PendingEvents};
 
@@ -49,6 +54,43 @@ impl McState {
             .unwrap_or(0);
         &self.trace[start_pos..]
     }
+
+    /// Returns the state of the process downcasted to the specified type.
+    ///
+    /// ```ignore
+    /// let replica = state.proc_state::<ReplicaState>("replica1")?;
+    /// ```
+    pub fn proc_state<T: ProcessState>(&self, proc: &str) -> Result<Rc<T>, ProcessAccessError> {
+        downcast_state(proc, self.dyn_proc_state(proc)?)
+    }
+
+    /// Parses the string state of the process, e.g. a Python process, as JSON of the specified type.
+    pub fn proc_state_json<T: DeserializeOwned>(&self, proc: &str) -> Result<T, ProcessAccessError> {
+        parse_state_json(proc, self.dyn_proc_state(proc)?)
+    }
+
+    /// Returns the states of all processes having the state of the specified type with the process names.
+    pub fn proc_states_of_type<T: ProcessState>(&self) -> impl Iterator<Item = (&str, Rc<T>)> {
+        self.node_states
+            .values()
+            .flat_map(|node_state| node_state.proc_states.iter())
+            .filter_map(|(proc, proc_state)| {
+                proc_state
+                    .proc_state
+                    .clone()
+                    .downcast_rc::<T>()
+                    .ok()
+                    .map(|state| (proc.as_str(), state))
+            })
+    }
+
+    fn dyn_proc_state(&self, proc: &str) -> Result<Rc<dyn ProcessState>, ProcessAccessError> {
+        self.node_states
+            .values()
+            .find_map(|node_state| node_state.proc_states.get(proc))
+            .map(|proc_state| proc_state.proc_state.clone())
+            .ok_or_else(|| ProcessAccessError::UnknownProcess { proc: proc.to_string() })
+    }
 }
 
 impl PartialEq for McState {
@@ -22,7 +22,7 @@ pub struct Bfs {
 impl Bfs {
     fn bfs(&mut self, system: &mut McSystem) -> Result<(), McError> {
         // Start search from initial state
-        self.states_queue.push_back(system.get_state());
+        self.states_queue.push_back(system.get_state()?);
 
         while !self.states_queue.is_empty() {
             let state = self.states_queue.pop_front().unwrap();
@@ -32,7 +32,7 @@ impl Bfs {
                 continue;
             }
 
-            system.set_state(state);
+            system.set_state(state)?;
             let available_events = system.available_events();
             for event_id in available_events {
                 self.process_event(system, event_id)?;
@@ -45,7 +45,7 @@ impl Strategy for Dfs {
     }
 
     fn run(&mut self, system: &mut McSystem) -> McResult {
-        let state = system.get_state();
+        let state = system.get_state()?;
 
         let res = self.dfs(system, state);
         match res {
@@ -166,7 +166,9 @@ pub trait Strategy {
     fn process_event(&mut self, system: &mut McSystem, event_id: McEventId) -> Result<(), McError> {
         let event = system.events.get(event_id).unwrap();
         match event {
-            MessageReceived { msg, src, dst, options } => {
+            MessageReceived {
+                msg, src, dst, options, ..
+            } => {
                 match *options {
                     DeliveryOptions::NoFailures(..) => self.search_step(system, EventOrId::Id(event_id))?,
                     DeliveryOptions::PossibleFailures {
@@ -227,7 +229,7 @@ pub trait Strategy {
     /// Applies the specified event to the system, calls `search_step_impl` with the produced state
     /// and restores the system state afterwards.
     fn search_step(&mut self, system: &mut McSystem, event: EventOrId) -> Result<(), McError> {
-        let state = system.get_state();
+        let state = system.get_state()?;
 
         let mut event = match event {
             EventOrId::Event(event) => event,
@@ -256,15 +258,20 @@ pub trait Strategy {
 
         self.debug_log(&event, system.depth());
 
-        system.apply_event(event);
+        if let Err(err) = system.apply_event(event) {
+            // the trace includes the event which caused the panic
+            let trace = system.trace_handler.borrow().trace();
+            system.set_state(state)?;
+            return Err(McError::new(err, trace));
+        }
 
-        let new_state = system.get_state();
+        let new_state = system.get_state()?;
         if !self.have_visited(&new_state) {
-            self.mark_visited(system.get_state());
+            self.mark_visited(new_state.clone());
             self.search_step_impl(system, new_state)?;
         }
 
-        system.set_state(state);
+        system.set_state(state)?;
 
         Ok(())
     }
@@ -291,7 +298,11 @@ pub trait Strategy {
     /// Creates MessageReceived event with corrupted msg.
     fn create_corrupted_receive(&self, event: McEvent, corrupted_msg: Message) -> McEvent {
         if let MessageReceived {
-            src, dst, mut options, ..
+            src,
+            dst,
+            mut options,
+            call,
+            ..
         } = event
         {
             if let DeliveryOptions::PossibleFailures { can_be_corrupted, .. } = &mut options {
@@ -302,6 +313,7 @@ pub trait Strategy {
                 src,
                 dst,
                 options,
+                call,
             }
         } else {
             panic!("Unexpected event type")
@@ -8,10 +8,13 @@ use std::rc::Rc;
 
 use ordered_float::OrderedFloat;
 
+use crate::hooks;
 use crate::logger::LogEntry;
 use crate::Message;
 
-use crate::mc::{EventOrderingMode, McEvent, McEventId, McNetwork, McNode, McState, PendingEvents, TraceHandler};
+use crate::mc::{
+    EventOrderingMode, McError, McEvent, McEventId, McNetwork, McNode, McState, PendingEvents, TraceHandler,
+};
 
 /// Used for specifying delays.
 pub type McTime = OrderedFloat<f64>;
@@ -27,6 +30,8 @@ pub struct McSystem {
     depth: u64,
     event_ordering_mode: EventOrderingMode,
     pub(crate) trace_handler: Rc<RefCell<TraceHandler>>,
+    /// Error of a process when handling a local message, reported as the result of model checking.
+    local_message_error: Option<String>,
 }
 
 impl McSystem {
@@ -44,18 +49,28 @@ impl McSystem {
             depth: 0,
             event_ordering_mode: EventOrderingMode::Normal,
             trace_handler,
+            local_message_error: None,
         }
     }
 
     /// Sends a local message to the process.
+    ///
+    /// If the process panics when handling the message, the model checking run fails with the panic message.
     pub fn send_local_message<S>(&mut self, node: S, proc: S, msg: Message)
     where
         S: Into<String>,
     {
-        let node = node.into();
-        let proc = proc.into();
+        if self.local_message_error.is_some() {
+            return;
+        }
+        if let Err(err) = self.send_local_message_impl(node.into(), proc.into(), msg) {
+            self.local_message_error = Some(err);
+        }
+    }
+
+    fn send_local_message_impl(&mut self, node: String, proc: String, msg: Message) -> Result<(), String> {
         let event_time = Self::get_approximate_event_time(self.depth);
-        let state_hash = self.get_state_hash();
+        let state_hash = self.get_state_hash()?;
 
         self.trace_handler.borrow_mut().push(LogEntry::McLocalMessageReceived {
             msg: msg.clone(),
@@ -65,8 +80,14 @@ impl McSystem {
             .nodes
             .get_mut(&node)
             .unwrap()
-            .on_local_message_received(proc, msg, event_time, state_hash);
+            .on_local_message_received(proc, msg, event_time, state_hash)?;
         self.add_events(new_events);
+        Ok(())
+    }
+
+    /// Takes the error of a process which failed to handle a local message.
+    pub(crate) fn take_local_message_error(&mut self) -> Option<String> {
+        self.local_message_error.take()
     }
 
     /// Sets the used [`EventOrderingMode`].
@@ -74,6 +95,16 @@ impl McSystem {
         self.event_ordering_mode = mode;
     }
 
+    /// Sets the number of times a periodic timer is re-armed after firing (3 by default).
+    ///
+    /// Periodic timers (see [`Context::set_periodic_timer`](crate::Context::set_periodic_timer)) are bounded
+    /// in model checking to keep the state space finite. The bound applies since the timer was last set by the process.
+    pub fn set_periodic_timer_limit(&mut self, limit: u32) {
+        for node in self.nodes.values_mut() {
+            node.set_periodic_timer_limit(limit);
+        }
+    }
+
     /// Crashes the specified node.
     pub fn crash_node<S>(&mut self, node: S)
     where
@@ -102,53 +133,58 @@ impl McSystem {
         self.nodes.keys().cloned().collect()
     }
 
-    pub(crate) fn apply_event(&mut self, event: McEvent) {
+    /// Applies the event to the system, returns an error if a process panicked when handling the event.
+    pub(crate) fn apply_event(&mut self, event: McEvent) -> Result<(), String> {
+        hooks::on_step();
         self.depth += 1;
         self.trace_handler.borrow_mut().push(event.to_log_entry());
         let event_time = Self::get_approximate_event_time(self.depth);
-        let state_hash = self.get_state_hash();
+        let state_hash = self.get_state_hash()?;
 
         let new_events = match event {
-            McEvent::MessageReceived { msg, src, dst, .. } => {
+            McEvent::MessageReceived {
+                msg, src, dst, call, ..
+            } => {
                 let name = self.net.get_proc_node(&dst).clone();
                 self.nodes
                     .get_mut(&name)
                     .unwrap()
-                    .on_message_received(dst, msg, src, event_time, state_hash)
+                    .on_message_received(dst, msg, src, call, event_time, state_hash)?
             }
             McEvent::TimerFired { proc, timer, .. } => {
                 let name = self.net.get_proc_node(&proc).clone();
                 self.nodes
                     .get_mut(&name)
                     .unwrap()
-                    .on_timer_fired(proc, timer, event_time, state_hash)
+                    .on_timer_fired(proc, timer, event_time, state_hash)?
             }
             _ => vec![],
         };
         self.add_events(new_events);
+        Ok(())
     }
 
-    pub(crate) fn get_state(&self) -> McState {
-        let mut state = McState::new(
-            self.events.clone(),
-            self.depth,
-            self.trace_handler.borrow().trace(),
-            self.net.clone(),
-        );
-        for (name, node) in &self.nodes {
-            state.node_states.insert(name.clone(), node.get_state());
-        }
-        state
+    /// Returns the system state, fails with the current trace if a process panics when returning its state.
+    pub(crate) fn get_state(&self) -> Result<McState, McError> {
+        self.collect_state().map_err(|err| self.error(err))
     }
 
-    pub(crate) fn set_state(&mut self, state: McState) {
+    /// Restores the system state, fails with the current trace if a process panics when restoring its state.
+    pub(crate) fn set_state(&mut self, state: McState) -> Result<(), McError> {
         for (name, node_state) in state.node_states {
-            self.nodes.get_mut(&name).unwrap().set_state(node_state);
+            let res = self.nodes.get_mut(&name).unwrap().set_state(node_state);
+            res.map_err(|err| self.error(err))?;
         }
         self.events = state.events;
         self.depth = state.depth;
         self.net = state.network;
         self.trace_handler.borrow_mut().set_trace(state.trace);
+        Ok(())
+    }
+
+    /// Creates model checking error with the current trace.
+    pub(crate) fn error(&self, message: String) -> McError {
+        McError::new(message, self.trace_handler.borrow().trace())
     }
 
     pub(crate) fn available_events(&self) -> BTreeSet<McEventId> {
@@ -161,8 +197,11 @@ impl McSystem {
 
     fn add_events(&mut self, events: Vec<McEvent>) {
         for mut event in events {
-            if let McEvent::MessageReceived { msg, src, dst, .. } = event {
-                event = self.net.send_message(msg, src, dst);
+            if let McEvent::MessageReceived {
+                msg, src, dst, call, ..
+            } = event
+            {
+                event = self.net.send_message(msg, src, dst, call);
             }
             match &event {
                 McEvent::TimerCancelled { proc, timer } => {
@@ -185,9 +224,22 @@ impl McSystem {
         depth as f64 / 10.0
     }
 
-    fn get_state_hash(&self) -> u64 {
+    fn collect_state(&self) -> Result<McState, String> {
+        let mut state = McState::new(
+            self.events.clone(),
+            self.depth,
+            self.trace_handler.borrow().trace(),
+            self.net.clone(),
+        );
+        for (name, node) in &self.nodes {
+            state.node_states.insert(name.clone(), node.get_state()?);
+        }
+        Ok(state)
+    }
+
+    fn get_state_hash(&self) -> Result<u64, String> {
         let mut hasher = DefaultHasher::default();
-        self.get_state().hash(&mut hasher);
-        hasher.finish()
+        self.collect_state()?.hash(&mut hasher);
+        Ok(hasher.finish())
     }
 }
@@ -2,10 +2,10 @@
 
 use std::fmt::{Error, Formatter};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Represents a message.
-#[derive(Serialize, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub struct Message {
     /// Message type.
     pub tip: String,
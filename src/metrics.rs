@@ -0,0 +1,386 @@
+//! Metrics registry.
+//!
+//! The system maintains a registry of counters and gauges identified by a name and a set of labels (see
+//! [`System::metrics`](crate::System::metrics)). Each metric keeps its current value and the history of its values
+//! over the simulation time. The registry is populated by the framework with the following metrics:
+//!
+//! - `network_messages_sent`, `network_bytes_sent`, `network_messages_dropped`, `network_messages_duplicated`,
+//!   `network_messages_corrupted` (counters) and `network_messages_in_flight` (gauge) for each network link labeled
+//!   with `src_node` and `dst_node`, messages between processes on the same node are not included;
+//! - `process_messages_sent`, `process_messages_received`, `timers_set`, `timers_fired`, `timers_cancelled`
+//!   (counters) and `timers_pending` (gauge) for each process labeled with `node` and `proc`.
+//!
+//! Processes can update their own metrics labeled with `node` and `proc` via
+//! [`Context::increment_counter`](crate::Context::increment_counter) and
+//! [`Context::set_gauge`](crate::Context::set_gauge). The names of these metrics are prefixed with `user_`, so they
+//! do not clash with the framework metrics. The current values can be exported as a [`MetricsSnapshot`]
+//! in CSV or Prometheus text format, while the value histories can be exported via [`Metrics::series_csv`].
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
+
+/// Metric labels.
+pub type Labels = BTreeMap<String, String>;
+
+/// Type of metric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    /// Monotonically increasing value.
+    Counter,
+    /// Arbitrary value which can go up and down.
+    Gauge,
+}
+
+impl MetricKind {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+/// Update of metric value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricUpdate {
+    /// Increments the counter by the value.
+    Increment(f64),
+    /// Sets the gauge to the value.
+    Set(f64),
+}
+
+impl MetricUpdate {
+    /// Returns the type of the updated metric.
+    pub(crate) fn kind(&self) -> MetricKind {
+        match self {
+            MetricUpdate::Increment(_) => MetricKind::Counter,
+            MetricUpdate::Set(_) => MetricKind::Gauge,
+        }
+    }
+}
+
+/// Returns the registry name of the metric updated by a process.
+pub(crate) fn process_metric_name(name: &str) -> String {
+    format!("user_{name}")
+}
+
+/// Metric value and its history.
+#[derive(Clone, Debug)]
+pub struct Metric {
+    kind: MetricKind,
+    value: f64,
+    series: Vec<(f64, f64)>,
+}
+
+impl Metric {
+    /// Returns the metric type.
+    pub fn kind(&self) -> MetricKind {
+        self.kind
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the history of values as (time, value) pairs.
+    ///
+    /// The history is empty if it is disabled (see [`Metrics::set_series_enabled`]).
+    pub fn series(&self) -> &[(f64, f64)] {
+        &self.series
+    }
+}
+
+/// Registry of metrics.
+#[derive(Default)]
+pub struct Metrics {
+    metrics: Vec<(String, Labels, Metric)>,
+    /// Indices of metrics by the hash of their name and labels, allows to find a metric without allocations.
+    index: HashMap<u64, Vec<usize>>,
+    series_enabled: bool,
+}
+
+/// Identifier of registered metric, allows to update the metric without looking it up by name and labels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct MetricId(usize);
+
+/// Returns the hash of metric name and labels which does not depend on the order of labels.
+fn key_hash<'a>(name: &str, labels: impl Iterator<Item = (&'a str, &'a str)>) -> u64 {
+    let mut labels_hash = 0u64;
+    for label in labels {
+        let mut hasher = DefaultHasher::new();
+        label.hash(&mut hasher);
+        labels_hash = labels_hash.wrapping_add(hasher.finish());
+    }
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    labels_hash.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn labels_match(labels: &Labels, other: &[(&str, &str)]) -> bool {
+    labels.len() == other.len()
+        && other
+            .iter()
+            .all(|(k, v)| labels.get(*k).is_some_and(|value| value == v))
+}
+
+impl Metrics {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables recording of metric value histories (disabled by default).
+    pub fn set_series_enabled(&mut self, enabled: bool) {
+        self.series_enabled = enabled;
+    }
+
+    fn find(&self, name: &str, labels: &[(&str, &str)]) -> Option<usize> {
+        let hash = key_hash(name, labels.iter().copied());
+        self.index.get(&hash)?.iter().copied().find(|idx| {
+            let (metric_name, metric_labels, _) = &self.metrics[*idx];
+            metric_name == name && labels_match(metric_labels, labels)
+        })
+    }
+
+    /// Returns the identifier of the metric, registering it if needed.
+    pub(crate) fn register(&mut self, name: &str, labels: &[(&str, &str)], kind: MetricKind) -> MetricId {
+        let idx = match self.find(name, labels) {
+            Some(idx) => idx,
+            None => {
+                let metric_labels: Labels = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+                assert_eq!(
+                    metric_labels.len(),
+                    labels.len(),
+                    "Metric {name} has duplicate label names"
+                );
+                let hash = key_hash(name, labels.iter().copied());
+                self.index.entry(hash).or_default().push(self.metrics.len());
+                self.metrics.push((
+                    name.to_string(),
+                    metric_labels,
+                    Metric {
+                        kind,
+                        value: 0.,
+                        series: Vec::new(),
+                    },
+                ));
+                self.metrics.len() - 1
+            }
+        };
+        let metric = &self.metrics[idx].2;
+        assert_eq!(
+            metric.kind, kind,
+            "Metric {name} is already registered as {:?}",
+            metric.kind
+        );
+        MetricId(idx)
+    }
+
+    /// Applies the function to the value of the registered metric at the specified time.
+    pub(crate) fn record(&mut self, id: MetricId, time: f64, f: impl Fn(f64) -> f64) {
+        let metric = &mut self.metrics[id.0].2;
+        metric.value = f(metric.value);
+        if self.series_enabled {
+            metric.series.push((time, metric.value));
+        }
+    }
+
+    /// Increments the counter at the specified time, registering it if needed.
+    pub fn increment(&mut self, name: &str, labels: &[(&str, &str)], delta: f64, time: f64) {
+        assert!(delta >= 0., "Counter {name} can not be decreased");
+        let id = self.register(name, labels, MetricKind::Counter);
+        self.record(id, time, |value| value + delta);
+    }
+
+    /// Sets the gauge value at the specified time, registering it if needed.
+    pub fn set(&mut self, name: &str, labels: &[(&str, &str)], value: f64, time: f64) {
+        let id = self.register(name, labels, MetricKind::Gauge);
+        self.record(id, time, |_| value);
+    }
+
+    /// Adds the (possibly negative) value to the gauge at the specified time, registering it if needed.
+    pub fn add(&mut self, name: &str, labels: &[(&str, &str)], delta: f64, time: f64) {
+        let id = self.register(name, labels, MetricKind::Gauge);
+        self.record(id, time, |value| value + delta);
+    }
+
+    /// Applies the update to the metric at the specified time.
+    pub fn update(&mut self, name: &str, labels: &[(&str, &str)], update: MetricUpdate, time: f64) {
+        match update {
+            MetricUpdate::Increment(delta) => self.increment(name, labels, delta, time),
+            MetricUpdate::Set(value) => self.set(name, labels, value, time),
+        }
+    }
+
+    /// Returns the metric with the specified name and labels.
+    pub fn get(&self, name: &str, labels: &[(&str, &str)]) -> Option<&Metric> {
+        self.find(name, labels).map(|idx| &self.metrics[idx].2)
+    }
+
+    /// Returns the current value of the metric with the specified name and labels.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        self.get(name, labels).map(|m| m.value)
+    }
+
+    /// Returns the sum of current values of metrics with the specified name over all labels.
+    pub fn total(&self, name: &str) -> f64 {
+        self.iter()
+            .filter(|(n, _, _)| *n == name)
+            .map(|(_, _, m)| m.value)
+            .sum()
+    }
+
+    /// Returns an iterator over metrics as (name, labels, metric) sorted by name and labels.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Labels, &Metric)> {
+        let mut metrics: Vec<_> = self
+            .metrics
+            .iter()
+            .map(|(name, labels, metric)| (name.as_str(), labels, metric))
+            .collect();
+        metrics.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        metrics.into_iter()
+    }
+
+    /// Returns the current values of all metrics.
+    pub fn snapshot(&self, time: f64) -> MetricsSnapshot {
+        let samples = self
+            .iter()
+            .map(|(name, labels, metric)| MetricSample {
+                name: name.to_string(),
+                labels: labels.clone(),
+                kind: metric.kind,
+                value: metric.value,
+            })
+            .collect();
+        MetricsSnapshot { time, samples }
+    }
+
+    /// Returns the histories of all metrics as CSV with a header, one row per value change sorted by time.
+    ///
+    /// The names and labels are formatted in the same way as in [`MetricsSnapshot::to_csv`].
+    pub fn series_csv(&self) -> String {
+        let mut rows = Vec::new();
+        for (name, labels, metric) in self.iter() {
+            let name = csv_field(name);
+            let labels = csv_field(&format_labels(labels));
+            for (time, value) in metric.series() {
+                rows.push((*time, format!("{time},{name},{labels},{value}\n")));
+            }
+        }
+        // stable sort keeps the order of changes at the same time
+        rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut csv = String::from("time,metric,labels,value\n");
+        for (_, row) in rows {
+            csv.push_str(&row);
+        }
+        csv
+    }
+}
+
+/// Current value of a metric.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricSample {
+    /// Metric name.
+    pub name: String,
+    /// Metric labels.
+    pub labels: Labels,
+    /// Metric type.
+    pub kind: MetricKind,
+    /// Metric value.
+    pub value: f64,
+}
+
+/// Current values of all metrics at some time.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    /// Simulation time of the snapshot.
+    pub time: f64,
+    /// Metric values sorted by name and labels.
+    pub samples: Vec<MetricSample>,
+}
+
+impl MetricsSnapshot {
+    /// Returns the snapshot as CSV with a header, one row per metric.
+    ///
+    /// Labels are formatted as `key="value"` pairs separated by `;`, the values are escaped in the same way
+    /// as in Prometheus format. The fields containing commas, quotes or line breaks are quoted.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,metric,type,labels,value\n");
+        for s in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                self.time,
+                csv_field(&s.name),
+                s.kind.name(),
+                csv_field(&format_labels(&s.labels)),
+                s.value
+            ));
+        }
+        csv
+    }
+
+    /// Returns the snapshot in Prometheus text exposition format.
+    ///
+    /// Characters not allowed in Prometheus metric names are replaced with `_`.
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        let mut last_name = None;
+        for s in &self.samples {
+            let name = prometheus_name(&s.name);
+            if last_name.as_ref() != Some(&name) {
+                text.push_str(&format!("# TYPE {name} {}\n", s.kind.name()));
+                last_name = Some(name.clone());
+            }
+            let labels: Vec<String> = s
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", prometheus_name(k), prometheus_escape(v)))
+                .collect();
+            if labels.is_empty() {
+                text.push_str(&format!("{name} {}\n", s.value));
+            } else {
+                text.push_str(&format!("{name}{{{}}} {}\n", labels.join(","), s.value));
+            }
+        }
+        text
+    }
+}
+
+fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{k}=\"{}\"", prometheus_escape(v)))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn prometheus_name(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if c.is_ascii_alphabetic() || c == '_' || c == ':' || (i > 0 && c.is_ascii_digit()) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn prometheus_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
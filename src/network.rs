@@ -1,20 +1,27 @@
 //! Network implementation.
 
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::rc::Rc;
 
 use lazy_static::lazy_static;
+use ordered_float::OrderedFloat;
 use regex::Regex;
 
-use simcore::event::EventId;
 use simcore::Id;
 use simcore::SimulationContext;
 
+use crate::causality::VectorClock;
 use crate::events::MessageReceived;
 use crate::logger::{LogEntry, Logger};
+use crate::metrics::{MetricId, MetricKind, Metrics};
+use crate::rpc::CallTag;
 use crate::Message;
 
+/// Message in flight as (delivery time, message id, in-flight messages metric of the link).
+type InFlightMessage = Reverse<(OrderedFloat<f64>, u64, MetricId)>;
+
 /// Represents a network that transmits messages between processes located on different nodes.
 pub struct Network {
     min_delay: f64,
@@ -32,10 +39,13 @@ pub struct Network {
     traffic: u64,
     ctx: SimulationContext,
     logger: Rc<RefCell<Logger>>,
+    metrics: Rc<RefCell<Metrics>>,
+    in_flight: BinaryHeap<InFlightMessage>,
+    cancelled: HashMap<u64, u32>,
 }
 
 impl Network {
-    pub(crate) fn new(ctx: SimulationContext, logger: Rc<RefCell<Logger>>) -> Self {
+    pub(crate) fn new(ctx: SimulationContext, logger: Rc<RefCell<Logger>>, metrics: Rc<RefCell<Metrics>>) -> Self {
         Self {
             min_delay: 1.,
             max_delay: 1.,
@@ -52,6 +62,9 @@ impl Network {
             traffic: 0,
             ctx,
             logger,
+            metrics,
+            in_flight: BinaryHeap::new(),
+            cancelled: HashMap::new(),
         }
     }
 
@@ -267,15 +280,16 @@ impl Network {
             || self.disabled_links.contains(&(src.clone(), dst.clone()))
     }
 
-    fn corrupt_if_needed(&self, msg: Message) -> Message {
+    /// Corrupts the message with the configured probability, returns the message and whether it was corrupted.
+    fn corrupt_if_needed(&self, msg: Message) -> (Message, bool) {
         if self.ctx.rand() < self.corrupt_rate {
             lazy_static! {
                 static ref RE: Regex = Regex::new(r#""[^"]+""#).unwrap();
             }
             let corrupted_data = RE.replace_all(&msg.data, "\"\"").to_string();
-            Message::new(msg.tip, corrupted_data)
+            (Message::new(msg.tip, corrupted_data), true)
         } else {
-            msg
+            (msg, false)
         }
     }
 
@@ -288,7 +302,18 @@ impl Network {
     }
 
     /// Sends a message between two processes.
-    pub(crate) fn send_message(&mut self, msg: Message, src: &str, dst: &str) {
+    ///
+    /// The logical clocks of sender process are delivered along with the message.
+    pub(crate) fn send_message(
+        &mut self,
+        msg: Message,
+        src: &str,
+        dst: &str,
+        call: Option<CallTag>,
+        lamport_time: u64,
+        vector_clock: VectorClock,
+    ) {
+        self.expire_in_flight();
         let msg_size = msg.size();
         let src_node = self.proc_locations.get(src).unwrap();
         let dst_node = self.proc_locations.get(dst).unwrap();
@@ -296,58 +321,74 @@ impl Network {
         let dst_node_id = *self.node_ids.get(dst_node).unwrap();
 
         let msg_id = self.message_count;
+        let mut e = MessageReceived {
+            id: msg_id,
+            msg,
+            src: src.to_string(),
+            src_node: src_node.to_string(),
+            dst: dst.to_string(),
+            dst_node: dst_node.to_string(),
+            lamport_time,
+            vector_clock,
+            call,
+        };
 
-        self.log_message_sent(
-            msg_id,
-            src_node.to_string(),
-            src.to_string(),
-            dst_node.to_string(),
-            dst.to_string(),
-            msg.clone(),
-        );
+        self.log_message_sent(&e);
 
         // local communication inside a node is reliable and fast
         if src_node == dst_node {
-            let e = MessageReceived {
-                id: self.message_count,
-                msg,
-                src: src.to_string(),
-                src_node: src_node.to_string(),
-                dst: dst.to_string(),
-                dst_node: dst_node.to_string(),
-            };
             self.ctx.emit_as(e, src_node_id, dst_node_id, 0.);
         // communication between different nodes can be faulty
         } else {
+            let time = self.ctx.time();
+            let link = [("src_node", src_node.as_str()), ("dst_node", dst_node.as_str())];
+            {
+                let mut metrics = self.metrics.borrow_mut();
+                metrics.increment("network_messages_sent", &link, 1., time);
+                metrics.increment("network_bytes_sent", &link, msg_size as f64, time);
+            }
             if !self.message_is_dropped(src_node, dst_node) {
-                let msg = self.corrupt_if_needed(msg);
-                let e = MessageReceived {
-                    id: self.message_count,
-                    msg,
-                    src: src.to_string(),
-                    src_node: src_node.to_string(),
-                    dst: dst.to_string(),
-                    dst_node: dst_node.to_string(),
-                };
+                let (msg, corrupted) = self.corrupt_if_needed(e.msg);
+                e.msg = msg;
                 let msg_count = self.get_message_count();
+                let mut delays = Vec::new();
                 if msg_count == 1 {
                     let delay = self.min_delay + self.ctx.rand() * (self.max_delay - self.min_delay);
+                    delays.push(delay);
                     self.ctx.emit_as(e, src_node_id, dst_node_id, delay);
                 } else {
                     for _ in 0..msg_count {
                         let delay = self.min_delay + self.ctx.rand() * (self.max_delay - self.min_delay);
+                        delays.push(delay);
                         self.ctx.emit_as(e.clone(), src_node_id, dst_node_id, delay);
                     }
                 }
+
+                let mut metrics = self.metrics.borrow_mut();
+                if corrupted {
+                    metrics.increment("network_messages_corrupted", &link, 1., time);
+                }
+                if msg_count > 1 {
+                    metrics.increment("network_messages_duplicated", &link, (msg_count - 1) as f64, time);
+                }
+                let in_flight = metrics.register("network_messages_in_flight", &link, MetricKind::Gauge);
+                metrics.record(in_flight, time, |value| value + msg_count as f64);
+                for delay in delays {
+                    self.in_flight
+                        .push(Reverse((OrderedFloat(time + delay), msg_id, in_flight)));
+                }
             } else {
+                self.metrics
+                    .borrow_mut()
+                    .increment("network_messages_dropped", &link, 1., time);
                 self.logger.borrow_mut().log(LogEntry::MessageDropped {
                     time: self.ctx.time(),
                     msg_id: msg_id.to_string(),
-                    src_proc: src.to_string(),
-                    src_node: src_node.to_string(),
-                    dst_proc: dst.to_string(),
-                    dst_node: dst_node.to_string(),
-                    msg,
+                    src_proc: e.src,
+                    src_node: e.src_node,
+                    dst_proc: e.dst,
+                    dst_node: e.dst_node,
+                    msg: e.msg,
                 });
             }
             self.network_message_count += 1;
@@ -356,23 +397,53 @@ impl Network {
         self.message_count += 1;
     }
 
-    fn log_message_sent(
-        &self,
-        msg_id: EventId,
-        src_node: String,
-        src_proc: String,
-        dst_node: String,
-        dst_proc: String,
-        msg: Message,
-    ) {
+    /// Updates the number of messages in flight by removing the messages whose delivery time has come.
+    ///
+    /// The messages are removed at their delivery time even if they are not delivered because
+    /// the destination node is crashed.
+    pub(crate) fn expire_in_flight(&mut self) {
+        let now = self.ctx.time();
+        while let Some(&Reverse((time, id, in_flight))) = self.in_flight.peek() {
+            if time.0 > now {
+                break;
+            }
+            self.in_flight.pop();
+            if let Some(count) = self.cancelled.get_mut(&id) {
+                *count -= 1;
+                if *count == 0 {
+                    self.cancelled.remove(&id);
+                }
+                continue;
+            }
+            self.metrics.borrow_mut().record(in_flight, time.0, |value| value - 1.);
+        }
+    }
+
+    /// Accounts for the message which delivery was cancelled, e.g. due to the node crash.
+    pub(crate) fn cancel_in_flight(&mut self, e: &MessageReceived) {
+        if e.src_node == e.dst_node {
+            return;
+        }
+        self.expire_in_flight();
+        *self.cancelled.entry(e.id).or_default() += 1;
+        let link = [("src_node", e.src_node.as_str()), ("dst_node", e.dst_node.as_str())];
+        self.metrics
+            .borrow_mut()
+            .add("network_messages_in_flight", &link, -1., self.ctx.time());
+    }
+
+    fn log_message_sent(&self, e: &MessageReceived) {
         self.logger.borrow_mut().log(LogEntry::MessageSent {
             time: self.ctx.time(),
-            msg_id: msg_id.to_string(),
-            src_node,
-            src_proc,
-            dst_node,
-            dst_proc,
-            msg,
+            msg_id: e.id.to_string(),
+            src_node: e.src_node.clone(),
+            src_proc: e.src.clone(),
+            dst_node: e.dst_node.clone(),
+            dst_proc: e.dst.clone(),
+            msg: e.msg.clone(),
+            lamport_time: e.lamport_time,
+            vector_clock: e.vector_clock.clone(),
+            call: e.call,
         });
     }
 }
@@ -1,15 +1,23 @@
 //! Node implementation.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 
 use colored::*;
 
 use simcore::{cast, Event, EventHandler, Id, SimulationContext};
 
+use crate::causality::VectorClock;
+use crate::context::Topology;
+use crate::error::{
+    call_handler, HandledEvent, HandlerFailure, ProcessError, ProcessErrorPolicy, ProcessErrors, StepError,
+};
 use crate::events::{MessageReceived, TimerFired};
+use crate::hooks;
 use crate::logger::{LogEntry, Logger};
+use crate::metrics::{process_metric_name, MetricKind, MetricUpdate, Metrics};
+use crate::rpc::{self, CallTag, PendingCall};
 use crate::{Context, Message, Network, Process, ProcessState};
 
 /// Event log entry as a pair of time and event.
@@ -34,6 +42,22 @@ pub enum TimerBehavior {
     SetOnce,
     /// Override the existing timer delay.
     OverrideExisting,
+    /// Override the existing timer and re-arm the timer after each firing (see [`Context::set_periodic_timer`]).
+    Periodic {
+        /// Delay between timer firings.
+        period: f64,
+        /// Upper bound of a random delay added to each period.
+        jitter: f64,
+    },
+}
+
+/// Parameters of an active periodic timer.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PeriodicTimer {
+    pub period: f64,
+    pub jitter: f64,
+    /// Number of times the timer was re-armed, used to bound periodic timers in model checking.
+    pub rearms: u32,
 }
 
 /// Represents an event happened in a process.
@@ -44,11 +68,13 @@ pub enum ProcessEvent {
         msg: Message,
         src: String,
         dst: String,
+        call: Option<CallTag>,
     },
     MessageReceived {
         msg: Message,
         src: String,
         dst: String,
+        call: Option<CallTag>,
     },
     LocalMessageSent {
         msg: Message,
@@ -67,6 +93,19 @@ pub enum ProcessEvent {
     TimerCancelled {
         name: String,
     },
+    UserEvent {
+        kind: String,
+        data: String,
+    },
+    MetricUpdated {
+        name: String,
+        update: MetricUpdate,
+    },
+    CallStarted {
+        call_id: u64,
+        dst: String,
+        msg: Message,
+    },
 }
 
 #[derive(Clone)]
@@ -76,9 +115,17 @@ pub struct ProcessEntry {
     pub(crate) event_log: Vec<EventLogEntry>,
     pub(crate) local_outbox: Vec<Message>,
     pub(crate) pending_timers: HashMap<String, u64>,
+    pub(crate) timer_deadlines: HashMap<String, f64>,
+    pub(crate) periodic_timers: HashMap<String, PeriodicTimer>,
+    pub(crate) pending_calls: BTreeMap<u64, PendingCall>,
+    pub(crate) next_call_id: u64,
     pub(crate) sent_message_count: u64,
     pub(crate) received_message_count: u64,
     pub(crate) last_state: String,
+    pub(crate) lamport_time: u64,
+    pub(crate) vector_clock: VectorClock,
+    pub(crate) is_crashed: bool,
+    pub(crate) max_size: u64,
 }
 
 impl ProcessEntry {
@@ -89,11 +136,26 @@ impl ProcessEntry {
             event_log: Vec::new(),
             local_outbox: Vec::new(),
             pending_timers: HashMap::new(),
+            timer_deadlines: HashMap::new(),
+            periodic_timers: HashMap::new(),
+            pending_calls: BTreeMap::new(),
+            next_call_id: 0,
             sent_message_count: 0,
             received_message_count: 0,
             last_state: String::from(""),
+            lamport_time: 0,
+            vector_clock: VectorClock::new(),
+            is_crashed: false,
+            max_size: 0,
         }
     }
+
+    /// Updates the maximum size of process inner data after handling an event.
+    ///
+    /// Accounts for both the current size and the maximum size reported by processes which track it themselves.
+    pub(crate) fn update_max_size(&mut self) {
+        self.max_size = self.max_size.max(self.proc_impl.size()).max(self.proc_impl.max_size());
+    }
 }
 
 /// Represents a node which is connected to the network and hosts one or more processes.
@@ -111,6 +173,9 @@ pub struct Node {
     /// Reference to the simulation context the node belongs to.
     pub ctx: Rc<RefCell<SimulationContext>>,
     logger: Rc<RefCell<Logger>>,
+    metrics: Rc<RefCell<Metrics>>,
+    errors: Rc<RefCell<ProcessErrors>>,
+    topology: Rc<RefCell<Topology>>,
     local_message_count: u64,
 }
 
@@ -120,6 +185,9 @@ impl Node {
         net: Rc<RefCell<Network>>,
         ctx: SimulationContext,
         logger: Rc<RefCell<Logger>>,
+        metrics: Rc<RefCell<Metrics>>,
+        errors: Rc<RefCell<ProcessErrors>>,
+        topology: Rc<RefCell<Topology>>,
     ) -> Self {
         Self {
             id: ctx.id(),
@@ -130,6 +198,9 @@ impl Node {
             is_crashed: false,
             ctx: Rc::new(RefCell::new(ctx)),
             logger,
+            metrics,
+            errors,
+            topology,
             local_message_count: 0,
         }
     }
@@ -157,6 +228,14 @@ impl Node {
     /// Marks the node as crashed.
     pub fn crash(&mut self) {
         self.is_crashed = true;
+        // pending timers are discarded along with other events destined to the node
+        let time = self.ctx.borrow().time();
+        for (proc, proc_entry) in self.processes.iter() {
+            if !proc_entry.pending_timers.is_empty() {
+                let labels = [("node", self.name.as_str()), ("proc", proc.as_str())];
+                self.metrics.borrow_mut().set("timers_pending", &labels, 0., time);
+            }
+        }
     }
 
     /// Recovers the node after crash.
@@ -169,18 +248,35 @@ impl Node {
 
     /// Spawns new process on the node.
     pub fn add_process(&mut self, name: &str, proc: Box<dyn Process>) {
+        self.try_add_process(name, proc)
+            .unwrap_or_else(|err| self.raise_process_error(*err))
+    }
+
+    /// Spawns new process on the node, returns the error if the process failed to start.
+    ///
+    /// In contrast to [`Self::add_process`], the process error does not cause a panic.
+    pub fn try_add_process(&mut self, name: &str, proc: Box<dyn Process>) -> Result<(), Box<StepError>> {
         self.processes.insert(name.to_string(), ProcessEntry::new(proc));
 
         // Call proc.on_start() and handle process actions
         let proc_entry = self.processes.get_mut(name).unwrap();
-        let mut proc_ctx = Context::from_simulation(name.to_string(), self.ctx.clone(), self.clock_skew);
-        proc_entry
-            .proc_impl
-            .on_start(&mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, name.to_string()))
-            .unwrap();
-        let time = self.ctx.borrow().time();
-        self.handle_process_actions(name.to_string(), time, proc_ctx.actions());
+        let mut proc_ctx =
+            Self::create_context(name, proc_entry, &self.ctx, self.clock_skew, &self.name, &self.topology);
+        match call_handler(|| proc_entry.proc_impl.on_start(&mut proc_ctx)) {
+            Ok(()) => {
+                proc_entry.update_max_size();
+                let actions = proc_ctx.actions();
+                match self.check_metric_updates(name, &actions) {
+                    Ok(()) => {
+                        let time = self.ctx.borrow().time();
+                        self.handle_process_actions(name.to_string(), time, actions);
+                    }
+                    Err(err) => self.fail_process(name, HandledEvent::Start, err),
+                }
+            }
+            Err(err) => self.fail_process(name, HandledEvent::Start, err),
+        }
+        self.take_process_error()
     }
 
     /// Returns a local process by its name.
@@ -195,13 +291,18 @@ impl Node {
 
     /// Sets the state of the process.
     pub fn set_process_state(&mut self, proc: &str, state: Rc<dyn ProcessState>) {
-        self.processes
-            .get_mut(proc)
-            .unwrap()
-            .proc_impl
-            .set_state(state)
-            .map_err(|e| self.handle_process_error(e, proc.to_string()))
-            .unwrap();
+        self.try_set_process_state(proc, state)
+            .unwrap_or_else(|err| self.raise_process_error(*err))
+    }
+
+    /// Sets the state of the process, returns the error if the process failed to restore the state.
+    ///
+    /// In contrast to [`Self::set_process_state`], the process error does not cause a panic.
+    pub fn try_set_process_state(&mut self, proc: &str, state: Rc<dyn ProcessState>) -> Result<(), Box<StepError>> {
+        if let Err(err) = call_handler(|| self.processes.get_mut(proc).unwrap().proc_impl.set_state(state)) {
+            self.fail_process(proc, HandledEvent::SetState, err);
+        }
+        self.take_process_error()
     }
 
     /// Sends a local message to the process.
@@ -234,8 +335,12 @@ impl Node {
     }
 
     /// Returns the maximum size of process inner data observed so far.
+    ///
+    /// This is the maximum of the size tracked by the system (see [`Process::size`])
+    /// and the size reported by the process (see [`Process::max_size`]).
     pub fn max_size(&mut self, proc: &str) -> u64 {
-        self.processes.get_mut(proc).unwrap().proc_impl.max_size()
+        let proc_entry = self.processes.get_mut(proc).unwrap();
+        proc_entry.max_size.max(proc_entry.proc_impl.max_size())
     }
 
     /// Returns the number of messages sent by the process.
@@ -249,6 +354,9 @@ impl Node {
     }
 
     fn on_local_message_received(&mut self, proc: String, msg: Message) {
+        if self.processes[&proc].is_crashed {
+            return;
+        }
         let time = self.ctx.borrow().time();
         self.logger.borrow_mut().log(LogEntry::LocalMessageReceived {
             time,
@@ -264,19 +372,61 @@ impl Node {
             time,
             ProcessEvent::LocalMessageReceived { msg: msg.clone() },
         ));
-        let mut proc_ctx = Context::from_simulation(proc.clone(), self.ctx.clone(), self.clock_skew);
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            &self.ctx,
+            self.clock_skew,
+            &self.name,
+            &self.topology,
+        );
 
-        proc_entry
-            .proc_impl
-            .on_local_message(msg, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        if let Err(err) = call_handler(|| proc_entry.proc_impl.on_local_message(msg.clone(), &mut proc_ctx)) {
+            return self.fail_process(&proc, HandledEvent::LocalMessage { msg }, err);
+        }
+        proc_entry.update_max_size();
+        let actions = proc_ctx.actions();
+        if let Err(err) = self.check_metric_updates(&proc, &actions) {
+            return self.fail_process(&proc, HandledEvent::LocalMessage { msg }, err);
+        }
 
-        self.handle_process_actions(proc, time, proc_ctx.actions());
+        self.handle_process_actions(proc, time, actions);
     }
 
-    fn on_message_received(&mut self, msg_id: u64, proc: String, msg: Message, from: String, from_node: String) {
+    fn on_message_received(&mut self, e: MessageReceived) {
         let time = self.ctx.borrow().time();
+        let MessageReceived {
+            id: msg_id,
+            msg,
+            src: from,
+            src_node: from_node,
+            dst: proc,
+            dst_node,
+            lamport_time,
+            vector_clock,
+            call,
+        } = e;
+
+        // messages to the crashed process are discarded
+        if self.processes[&proc].is_crashed {
+            self.logger.borrow_mut().log(LogEntry::MessageDropped {
+                time,
+                msg_id: msg_id.to_string(),
+                msg,
+                src_proc: from,
+                src_node: from_node,
+                dst_proc: proc,
+                dst_node,
+            });
+            return;
+        }
+
+        // update logical clocks of the receiver
+        let proc_entry = self.processes.get_mut(&proc).unwrap();
+        proc_entry.lamport_time = proc_entry.lamport_time.max(lamport_time) + 1;
+        proc_entry.vector_clock.merge(&vector_clock);
+        proc_entry.vector_clock.increment(&proc);
+
         self.logger.borrow_mut().log(LogEntry::MessageReceived {
             time,
             msg_id: msg_id.to_string(),
@@ -285,6 +435,8 @@ impl Node {
             dst_proc: proc.clone(),
             dst_node: self.name.clone(),
             msg: msg.clone(),
+            lamport_time: proc_entry.lamport_time,
+            vector_clock: proc_entry.vector_clock.clone(),
         });
 
         let proc_entry = self.processes.get_mut(&proc).unwrap();
@@ -294,27 +446,74 @@ impl Node {
                 msg: msg.clone(),
                 src: from.clone(),
                 dst: proc.clone(),
+                call,
             },
         ));
         proc_entry.received_message_count += 1;
-        let mut proc_ctx = Context::from_simulation(proc.clone(), self.ctx.clone(), self.clock_skew);
+        let labels = [("node", self.name.as_str()), ("proc", proc.as_str())];
+        self.metrics
+            .borrow_mut()
+            .increment("process_messages_received", &labels, 1., time);
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            &self.ctx,
+            self.clock_skew,
+            &self.name,
+            &self.topology,
+        );
 
-        proc_entry
-            .proc_impl
-            .on_message(msg, from, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        let res = match call {
+            None => call_handler(|| {
+                proc_entry
+                    .proc_impl
+                    .on_message(msg.clone(), from.clone(), &mut proc_ctx)
+            }),
+            Some(CallTag::Request(call_id)) => {
+                proc_ctx.set_current_call(&from, call_id);
+                call_handler(|| {
+                    proc_entry
+                        .proc_impl
+                        .on_message(msg.clone(), from.clone(), &mut proc_ctx)
+                })
+            }
+            Some(CallTag::Response(call_id)) => {
+                // late or duplicated responses are ignored
+                if !matches!(proc_entry.pending_calls.get(&call_id), Some(call) if call.dst == from) {
+                    return;
+                }
+                proc_entry.pending_calls.remove(&call_id);
+                proc_ctx.cancel_timer(&rpc::timeout_timer(call_id));
+                call_handler(|| {
+                    proc_entry
+                        .proc_impl
+                        .on_response(call_id, msg.clone(), from.clone(), &mut proc_ctx)
+                })
+            }
+        };
+        if let Err(err) = res {
+            return self.fail_process(&proc, HandledEvent::Message { msg, from }, err);
+        }
+        proc_entry.update_max_size();
+        let actions = proc_ctx.actions();
+        if let Err(err) = self.check_metric_updates(&proc, &actions) {
+            return self.fail_process(&proc, HandledEvent::Message { msg, from }, err);
+        }
 
-        if self.logger.borrow().has_log_file() {
+        if self.logger.borrow().logs_process_states() {
             self.log_process_state(&proc);
         }
-        self.handle_process_actions(proc, time, proc_ctx.actions());
+        self.handle_process_actions(proc, time, actions);
     }
 
     fn on_timer_fired(&mut self, proc: String, timer: String) {
         let time = self.ctx.borrow().time();
 
         let proc_entry = self.processes.get_mut(&proc).unwrap();
+        if proc_entry.is_crashed {
+            return;
+        }
+        proc_entry.timer_deadlines.remove(&timer);
         if let Some(timer_id) = proc_entry.pending_timers.remove(&timer) {
             self.logger.borrow_mut().log(LogEntry::TimerFired {
                 time,
@@ -323,19 +522,88 @@ impl Node {
                 node: self.name.clone(),
                 proc: proc.clone(),
             });
+            let labels = [("node", self.name.as_str()), ("proc", proc.as_str())];
+            let mut metrics = self.metrics.borrow_mut();
+            metrics.increment("timers_fired", &labels, 1., time);
+            metrics.set("timers_pending", &labels, proc_entry.pending_timers.len() as f64, time);
         }
-        let mut proc_ctx = Context::from_simulation(proc.clone(), self.ctx.clone(), self.clock_skew);
 
-        proc_entry
-            .proc_impl
-            .on_timer(timer, &mut proc_ctx)
-            .map_err(|e| self.handle_process_error(e, proc.clone()))
-            .unwrap();
+        // periodic timer is re-armed before calling the process, so it can be cancelled in the handler
+        if let Some(periodic) = proc_entry.periodic_timers.get(&timer) {
+            let (period, jitter) = (periodic.period, periodic.jitter);
+            let delay = if jitter > 0. {
+                period + jitter * self.ctx.borrow().rand()
+            } else {
+                period
+            };
+            let rearm = ProcessEvent::TimerSet {
+                name: timer.clone(),
+                delay,
+                behavior: TimerBehavior::Periodic { period, jitter },
+            };
+            self.handle_process_actions(proc.clone(), time, vec![rearm]);
+        }
+
+        let proc_entry = self.processes.get_mut(&proc).unwrap();
+        let mut proc_ctx = Self::create_context(
+            &proc,
+            proc_entry,
+            &self.ctx,
+            self.clock_skew,
+            &self.name,
+            &self.topology,
+        );
 
-        if self.logger.borrow().has_log_file() {
+        let res = match rpc::parse_timeout_timer(&timer) {
+            Some(call_id) => match proc_entry.pending_calls.remove(&call_id) {
+                Some(call) => call_handler(|| {
+                    proc_entry
+                        .proc_impl
+                        .on_call_timeout(call_id, call.request, call.dst, &mut proc_ctx)
+                }),
+                None => return,
+            },
+            None => call_handler(|| proc_entry.proc_impl.on_timer(timer.clone(), &mut proc_ctx)),
+        };
+        if let Err(err) = res {
+            return self.fail_process(&proc, HandledEvent::Timer { name: timer }, err);
+        }
+        proc_entry.update_max_size();
+        let actions = proc_ctx.actions();
+        if let Err(err) = self.check_metric_updates(&proc, &actions) {
+            return self.fail_process(&proc, HandledEvent::Timer { name: timer }, err);
+        }
+
+        if self.logger.borrow().logs_process_states() {
             self.log_process_state(&proc);
         }
-        self.handle_process_actions(proc, time, proc_ctx.actions());
+        self.handle_process_actions(proc, time, actions);
+    }
+
+    /// Checks that the metric updates made by the process do not change the types of its metrics.
+    ///
+    /// The conflicting update is reported as the failure of the process handler.
+    fn check_metric_updates(&self, proc: &str, actions: &[ProcessEvent]) -> Result<(), HandlerFailure> {
+        let metrics = self.metrics.borrow();
+        let labels = [("node", self.name.as_str()), ("proc", proc)];
+        let mut kinds: HashMap<&str, MetricKind> = HashMap::new();
+        for action in actions {
+            if let ProcessEvent::MetricUpdated { name, update } = action {
+                let registered = kinds.get(name.as_str()).copied().or_else(|| {
+                    metrics
+                        .get(&process_metric_name(name), &labels)
+                        .map(|metric| metric.kind())
+                });
+                if let Some(kind) = registered.filter(|kind| *kind != update.kind()) {
+                    return Err(HandlerFailure::Error(format!(
+                        "metric {name} is already updated as {}",
+                        kind.name()
+                    )));
+                }
+                kinds.insert(name, update.kind());
+            }
+        }
+        Ok(())
     }
 
     /// Processes a sequence of actions for a given process.
@@ -343,10 +611,23 @@ impl Node {
         for action in actions {
             let proc_entry = self.processes.get_mut(&proc).unwrap();
             proc_entry.event_log.push(EventLogEntry::new(time, action.clone()));
+            let labels = [("node", self.name.as_str()), ("proc", proc.as_str())];
             match action {
-                ProcessEvent::MessageSent { msg, src: _, dst } => {
-                    self.net.borrow_mut().send_message(msg, &proc, &dst);
+                ProcessEvent::MessageSent { msg, src: _, dst, call } => {
+                    proc_entry.lamport_time += 1;
+                    proc_entry.vector_clock.increment(&proc);
+                    self.net.borrow_mut().send_message(
+                        msg,
+                        &proc,
+                        &dst,
+                        call,
+                        proc_entry.lamport_time,
+                        proc_entry.vector_clock.clone(),
+                    );
                     proc_entry.sent_message_count += 1;
+                    self.metrics
+                        .borrow_mut()
+                        .increment("process_messages_sent", &labels, 1., time);
                 }
                 ProcessEvent::LocalMessageSent { msg } => {
                     proc_entry.local_outbox.push(msg.clone());
@@ -362,12 +643,25 @@ impl Node {
                 }
                 ProcessEvent::TimerSet { name, delay, behavior } => {
                     if let Some(event_id) = proc_entry.pending_timers.get(&name) {
-                        if behavior == TimerBehavior::OverrideExisting {
-                            self.ctx.borrow_mut().cancel_event(*event_id);
-                        } else {
+                        if behavior == TimerBehavior::SetOnce {
                             continue;
                         }
+                        self.ctx.borrow_mut().cancel_event(*event_id);
                     }
+                    match behavior {
+                        TimerBehavior::Periodic { period, jitter } => {
+                            let periodic = PeriodicTimer {
+                                period,
+                                jitter,
+                                rearms: 0,
+                            };
+                            proc_entry.periodic_timers.insert(name.clone(), periodic);
+                        }
+                        _ => {
+                            proc_entry.periodic_timers.remove(&name);
+                        }
+                    }
+                    proc_entry.timer_deadlines.insert(name.clone(), time + delay);
                     let event = TimerFired {
                         timer: name.clone(),
                         proc: proc.clone(),
@@ -383,8 +677,14 @@ impl Node {
                         proc: proc.clone(),
                         delay,
                     });
+
+                    let mut metrics = self.metrics.borrow_mut();
+                    metrics.increment("timers_set", &labels, 1., time);
+                    metrics.set("timers_pending", &labels, proc_entry.pending_timers.len() as f64, time);
                 }
                 ProcessEvent::TimerCancelled { name } => {
+                    proc_entry.periodic_timers.remove(&name);
+                    proc_entry.timer_deadlines.remove(&name);
                     if let Some(event_id) = proc_entry.pending_timers.remove(&name) {
                         self.logger.borrow_mut().log(LogEntry::TimerCancelled {
                             time,
@@ -395,8 +695,32 @@ impl Node {
                         });
 
                         self.ctx.borrow_mut().cancel_event(event_id);
+
+                        let mut metrics = self.metrics.borrow_mut();
+                        metrics.increment("timers_cancelled", &labels, 1., time);
+                        metrics.set("timers_pending", &labels, proc_entry.pending_timers.len() as f64, time);
                     }
                 }
+                ProcessEvent::UserEvent { kind, data } => {
+                    self.logger.borrow_mut().log(LogEntry::UserEvent {
+                        time,
+                        node: self.name.clone(),
+                        proc: proc.clone(),
+                        kind,
+                        data,
+                    });
+                }
+                ProcessEvent::MetricUpdated { name, update } => {
+                    self.metrics
+                        .borrow_mut()
+                        .update(&process_metric_name(&name), &labels, update, time);
+                }
+                ProcessEvent::CallStarted { call_id, dst, msg } => {
+                    proc_entry
+                        .pending_calls
+                        .insert(call_id, PendingCall { dst, request: msg });
+                    proc_entry.next_call_id = call_id + 1;
+                }
                 _ => {}
             }
         }
@@ -406,20 +730,38 @@ impl Node {
         self.processes.clone()
     }
 
+    fn create_context(
+        proc: &str,
+        proc_entry: &ProcessEntry,
+        sim_ctx: &Rc<RefCell<SimulationContext>>,
+        clock_skew: f64,
+        node_name: &str,
+        topology: &Rc<RefCell<Topology>>,
+    ) -> Context {
+        let mut ctx = Context::from_simulation(proc.to_string(), sim_ctx.clone(), clock_skew);
+        ctx.set_topology(node_name, topology.clone());
+        ctx.set_logical_clocks(proc_entry.lamport_time, proc_entry.vector_clock.clone());
+        ctx.set_next_call_id(proc_entry.next_call_id);
+        let time = sim_ctx.borrow().time();
+        ctx.set_timers(
+            proc_entry
+                .timer_deadlines
+                .iter()
+                .map(|(name, deadline)| (name.clone(), deadline - time)),
+        );
+        ctx
+    }
+
     fn get_local_message_id(&self, proc: &str, local_message_count: u64) -> String {
         format!("{}-{}-{}", self.name, proc, local_message_count)
     }
 
     fn log_process_state(&mut self, proc: &str) {
         let proc_entry = self.processes.get(proc).unwrap();
-        let state = format!(
-            "{:?}",
-            proc_entry
-                .proc_impl
-                .state()
-                .map_err(|e| self.handle_process_error(e, proc.to_string()))
-                .unwrap()
-        );
+        let state = match call_handler(|| proc_entry.proc_impl.state()) {
+            Ok(state) => format!("{state:?}"),
+            Err(err) => return self.fail_process(proc, HandledEvent::GetState, err),
+        };
         if state != proc_entry.last_state {
             self.processes.get_mut(proc).unwrap().last_state.clone_from(&state);
             self.logger.borrow_mut().log(LogEntry::ProcessStateUpdated {
@@ -431,6 +773,96 @@ impl Node {
         }
     }
 
+    /// Returns true if the process is crashed after an error.
+    pub fn process_is_crashed(&self, proc: &str) -> bool {
+        self.processes[proc].is_crashed
+    }
+
+    /// Handles the error returned by the process or the panic according to the process error policy.
+    fn fail_process(&mut self, proc: &str, event: HandledEvent, failure: HandlerFailure) {
+        let time = self.ctx.borrow().time();
+        let (err, panicked) = match failure {
+            HandlerFailure::Error(err) => (err, false),
+            HandlerFailure::Panic(message) => (message, true),
+        };
+        self.logger.borrow_mut().log(LogEntry::ProcessFailed {
+            time,
+            node: self.name.clone(),
+            proc: proc.to_string(),
+            error: if panicked {
+                format!("panicked: {err}")
+            } else {
+                err.clone()
+            },
+        });
+        let error = ProcessError {
+            proc: proc.to_string(),
+            node: self.name.clone(),
+            event,
+            error: err,
+            panicked,
+            time,
+            trace: Vec::new(),
+        };
+        let mut errors = self.errors.borrow_mut();
+        // the trace is copied only for the error which stops the simulation,
+        // the trace of other errors is obtained from the logger on demand
+        errors
+            .errors
+            .push((error.clone(), self.logger.borrow().trace_position()));
+        match errors.policy {
+            // the error is taken by the system after the simulation step
+            ProcessErrorPolicy::Fail => {
+                errors.pending = Some(ProcessError {
+                    trace: self.logger.borrow().trace().to_vec(),
+                    ..error
+                })
+            }
+            ProcessErrorPolicy::CrashProcess => {
+                drop(errors);
+                self.crash_process(proc, time);
+            }
+        }
+    }
+
+    /// Takes the process error which stopped the simulation outside of the simulation step.
+    pub(crate) fn take_process_error(&self) -> Result<(), Box<StepError>> {
+        match self.errors.borrow_mut().pending.take() {
+            Some(error) => Err(Box::new(StepError::Process(error))),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics with the process error returned outside of the simulation step.
+    fn raise_process_error(&self, err: StepError) -> ! {
+        // the sink error is kept by the logger and returned by System::flush_log
+        let _ = self.logger.borrow_mut().flush();
+        match err {
+            StepError::Process(error) => panic!(
+                "{}: {}",
+                self.handle_process_error(error.error.clone(), error.proc.clone()),
+                error
+            ),
+            err => panic!("{err}"),
+        }
+    }
+
+    /// Stops delivering events to the process and cancels its pending timers.
+    fn crash_process(&mut self, proc: &str, time: f64) {
+        let proc_entry = self.processes.get_mut(proc).unwrap();
+        proc_entry.is_crashed = true;
+        proc_entry.periodic_timers.clear();
+        proc_entry.pending_calls.clear();
+        proc_entry.timer_deadlines.clear();
+        if !proc_entry.pending_timers.is_empty() {
+            for (_, event_id) in proc_entry.pending_timers.drain() {
+                self.ctx.borrow_mut().cancel_event(event_id);
+            }
+            let labels = [("node", self.name.as_str()), ("proc", proc)];
+            self.metrics.borrow_mut().set("timers_pending", &labels, 0., time);
+        }
+    }
+
     /// Logs a process error and returns a descriptive message.
     pub fn handle_process_error(&self, err: String, proc: String) -> &str {
         eprintln!(
@@ -447,6 +879,7 @@ impl Node {
 
 impl EventHandler for Node {
     fn on(&mut self, event: Event) {
+        hooks::on_step();
         cast!(match event.data {
             MessageReceived {
                 id,
@@ -454,9 +887,22 @@ impl EventHandler for Node {
                 src,
                 src_node,
                 dst,
-                dst_node: _,
+                dst_node,
+                lamport_time,
+                vector_clock,
+                call,
             } => {
-                self.on_message_received(id, dst, msg, src, src_node);
+                self.on_message_received(MessageReceived {
+                    id,
+                    msg,
+                    src,
+                    src_node,
+                    dst,
+                    dst_node,
+                    lamport_time,
+                    vector_clock,
+                    call,
+                });
             }
             TimerFired { proc, timer } => {
                 self.on_timer_fired(proc, timer);
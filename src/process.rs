@@ -1,16 +1,18 @@
 //! Process trait and related types.
 
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 use downcast_rs::{impl_downcast, Downcast};
 use dyn_clone::{clone_trait_object, DynClone};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 use crate::{Context, Message};
 
 /// A trait for process implementations.
-pub trait Process: DynClone {
+pub trait Process: DynClone + Downcast {
     /// Called when the process is started on a node.
     fn on_start(&mut self, _ctx: &mut Context) -> Result<(), String> {
         Ok(())
@@ -25,11 +27,40 @@ pub trait Process: DynClone {
     /// Called when a timer fires.
     fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String>;
 
+    /// Called when a response to the call made with [`Context::call`] is received.
+    fn on_response(&mut self, _call_id: u64, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when a response to the call made with [`Context::call`] is not received within the timeout.
+    ///
+    /// The request message and the called process are passed to allow retrying the call.
+    fn on_call_timeout(
+        &mut self,
+        _call_id: u64,
+        _request: Message,
+        _dst: String,
+        _ctx: &mut Context,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
     /// Returns the maximum size of process inner data observed so far.
+    ///
+    /// Can be implemented by processes which track their size themselves, e.g. Python processes.
+    /// Otherwise, the maximum of values returned by [`Self::size`] is tracked by the system.
+    /// Called after each event handled by the process along with [`Self::size`], so it should be cheap.
     fn max_size(&mut self) -> u64 {
         0
     }
 
+    /// Returns the estimated current size of process inner data in bytes.
+    ///
+    /// Called after each event handled by the process to track the maximum size (see [`crate::size`]).
+    fn size(&self) -> u64 {
+        0
+    }
+
     /// Returns the process state.
     fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
         Ok(Rc::new(EmptyProcessState {}))
@@ -42,6 +73,7 @@ pub trait Process: DynClone {
 }
 
 clone_trait_object!(Process);
+impl_downcast!(Process);
 
 /// A trait for implementations of process state.
 pub trait ProcessState: Downcast + Debug {
@@ -73,3 +105,259 @@ impl<T: Hash + Eq + Debug + 'static> ProcessState for T {
         }
     }
 }
+
+/// Error returned by typed accessors of processes and their states, such as [`System::proc_state`](crate::System::proc_state).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProcessAccessError {
+    /// There is no process with such name.
+    UnknownProcess {
+        /// Process name.
+        proc: String,
+    },
+    /// The process has a different type.
+    WrongProcessType {
+        /// Process name.
+        proc: String,
+        /// Requested type.
+        expected: &'static str,
+    },
+    /// The process state has a different type.
+    WrongStateType {
+        /// Process name.
+        proc: String,
+        /// Requested type.
+        expected: &'static str,
+    },
+    /// The process failed to return its state.
+    StateError {
+        /// Process name.
+        proc: String,
+        /// Error returned by the process.
+        error: String,
+    },
+    /// The process state is not a string containing JSON of the requested type.
+    InvalidJson {
+        /// Process name.
+        proc: String,
+        /// Requested type.
+        expected: &'static str,
+        /// Parsing error.
+        error: String,
+    },
+}
+
+impl Display for ProcessAccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownProcess { proc } => write!(f, "unknown process '{proc}'"),
+            Self::WrongProcessType { proc, expected } => write!(f, "process '{proc}' is not of type {expected}"),
+            Self::WrongStateType { proc, expected } => {
+                write!(f, "state of process '{proc}' is not of type {expected}")
+            }
+            Self::StateError { proc, error } => write!(f, "failed to get state of process '{proc}': {error}"),
+            Self::InvalidJson { proc, expected, error } => {
+                write!(
+                    f,
+                    "state of process '{proc}' is not a valid JSON of type {expected}: {error}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProcessAccessError {}
+
+/// Downcasts the process state to the requested type.
+pub(crate) fn downcast_state<T: ProcessState>(
+    proc: &str,
+    state: Rc<dyn ProcessState>,
+) -> Result<Rc<T>, ProcessAccessError> {
+    state
+        .downcast_rc::<T>()
+        .map_err(|_| ProcessAccessError::WrongStateType {
+            proc: proc.to_string(),
+            expected: std::any::type_name::<T>(),
+        })
+}
+
+/// Parses the string process state, e.g. the state of a Python process, as JSON of the requested type.
+pub(crate) fn parse_state_json<T: DeserializeOwned>(
+    proc: &str,
+    state: Rc<dyn ProcessState>,
+) -> Result<T, ProcessAccessError> {
+    let state = downcast_state::<StringProcessState>(proc, state)?;
+    serde_json::from_str(&state).map_err(|err| ProcessAccessError::InvalidJson {
+        proc: proc.to_string(),
+        expected: std::any::type_name::<T>(),
+        error: err.to_string(),
+    })
+}
+
+/// Process wrapper which implements [`Process::state`] and [`Process::set_state`] by cloning the process.
+///
+/// The snapshots of process state are hashed and compared through a _view_ of the process, which should include
+/// all fields affecting the process behavior. By default, the view is the process serialized to JSON with the object
+/// keys sorted, so the maps with the same contents have the same view regardless of their iteration order. Note that
+/// sets are serialized as arrays in iteration order, so a `HashSet` field should be replaced with `BTreeSet`
+/// or excluded from the view.
+/// This allows using a `Clone` process in model checking without implementing the state methods by hand:
+///
+/// ```ignore
+/// sys.add_process("proc", boxed!(Snapshot::new(MyProcess::new())), "node");
+/// ```
+pub struct Snapshot<P, V = String> {
+    proc: P,
+    view: View<P, V>,
+}
+
+/// Function producing the view of the process.
+enum View<P, V> {
+    Custom(fn(&P) -> V),
+    Json(fn(&P) -> Result<V, String>),
+}
+
+impl<P, V> Clone for View<P, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, V> Copy for View<P, V> {}
+
+impl<P: Serialize> Snapshot<P> {
+    /// Wraps the process using its canonical JSON serialization as the view.
+    pub fn new(proc: P) -> Self {
+        Self {
+            proc,
+            view: View::Json(canonical_json),
+        }
+    }
+}
+
+/// Serializes the value to JSON with the object keys sorted.
+pub(crate) fn canonical_json<P: Serialize>(proc: &P) -> Result<String, String> {
+    let mut value = serde_json::to_value(proc).map_err(|err| format!("failed to serialize process: {err}"))?;
+    value.sort_all_objects();
+    Ok(value.to_string())
+}
+
+impl<P, V> Snapshot<P, V> {
+    /// Wraps the process using the specified function to obtain the view.
+    ///
+    /// Fields not included in the view are still restored from the snapshot,
+    /// but states differing only in these fields are considered equal.
+    pub fn with_view(proc: P, view: fn(&P) -> V) -> Self {
+        Self {
+            proc,
+            view: View::Custom(view),
+        }
+    }
+
+    /// Returns a reference to the wrapped process.
+    pub fn inner(&self) -> &P {
+        &self.proc
+    }
+
+    /// Returns a mutable reference to the wrapped process.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.proc
+    }
+}
+
+impl<P: Clone, V> Clone for Snapshot<P, V> {
+    fn clone(&self) -> Self {
+        Self {
+            proc: self.proc.clone(),
+            view: self.view,
+        }
+    }
+}
+
+/// Snapshot of process state produced by [`Snapshot`].
+pub struct ProcessSnapshot<P, V> {
+    proc: P,
+    view: V,
+}
+
+impl<P, V: Hash> Hash for ProcessSnapshot<P, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.view.hash(state);
+    }
+}
+
+impl<P, V: PartialEq> PartialEq for ProcessSnapshot<P, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.view == other.view
+    }
+}
+
+impl<P, V: Eq> Eq for ProcessSnapshot<P, V> {}
+
+impl<P, V: Debug> Debug for ProcessSnapshot<P, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.view.fmt(f)
+    }
+}
+
+impl<P, V> Process for Snapshot<P, V>
+where
+    P: Process + Clone + 'static,
+    V: Hash + Eq + Debug + 'static,
+{
+    fn on_start(&mut self, ctx: &mut Context) -> Result<(), String> {
+        self.proc.on_start(ctx)
+    }
+
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        self.proc.on_message(msg, from, ctx)
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        self.proc.on_local_message(msg, ctx)
+    }
+
+    fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
+        self.proc.on_timer(timer, ctx)
+    }
+
+    fn on_response(&mut self, call_id: u64, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        self.proc.on_response(call_id, msg, from, ctx)
+    }
+
+    fn on_call_timeout(
+        &mut self,
+        call_id: u64,
+        request: Message,
+        dst: String,
+        ctx: &mut Context,
+    ) -> Result<(), String> {
+        self.proc.on_call_timeout(call_id, request, dst, ctx)
+    }
+
+    fn max_size(&mut self) -> u64 {
+        self.proc.max_size()
+    }
+
+    fn size(&self) -> u64 {
+        self.proc.size()
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        let view = match self.view {
+            View::Custom(view) => view(&self.proc),
+            View::Json(view) => view(&self.proc)?,
+        };
+        Ok(Rc::new(ProcessSnapshot {
+            proc: self.proc.clone(),
+            view,
+        }))
+    }
+
+    fn set_state(&mut self, state: Rc<dyn ProcessState>) -> Result<(), String> {
+        let snapshot = state
+            .downcast_rc::<ProcessSnapshot<P, V>>()
+            .map_err(|_| "invalid process snapshot".to_string())?;
+        self.proc.clone_from(&snapshot.proc);
+        Ok(())
+    }
+}
@@ -69,10 +69,19 @@ impl PyProcessFactory {
             max_size: 0,
             max_size_freq: 0,
             max_size_counter: 0,
+            topology: None,
         }
     }
 }
 
+/// Python objects describing the system topology, passed to the Python context.
+struct PyTopology {
+    version: u64,
+    processes: Py<PyAny>,
+    node_processes: Py<PyAny>,
+    groups: Py<PyAny>,
+}
+
 /// Process implementation backed by a Python object.
 pub struct PyProcess {
     proc: Py<PyAny>,
@@ -82,15 +91,46 @@ pub struct PyProcess {
     max_size: u64,
     max_size_freq: u32,
     max_size_counter: u32,
+    topology: Option<PyTopology>,
 }
 
 impl PyProcess {
     /// Sets the frequency of updating the maximum size of process inner data.
+    ///
+    /// The size is measured after every `freq`-th handled event, 0 disables the measurement.
     pub fn set_max_size_freq(&mut self, freq: u32) {
         self.max_size_freq = freq;
         self.max_size_counter = 1;
     }
 
+    fn create_py_context(&mut self, ctx: &Context, py: Python) -> Py<PyAny> {
+        // topology objects are converted once and reused until the topology changes
+        let version = ctx.topology_version();
+        if self
+            .topology
+            .as_ref()
+            .is_none_or(|topology| topology.version != version)
+        {
+            self.topology = Some(PyTopology {
+                version,
+                processes: ctx.processes().into_pyobject(py).unwrap().into_any().unbind(),
+                node_processes: ctx.node_processes().into_pyobject(py).unwrap().into_any().unbind(),
+                groups: ctx.groups().into_pyobject(py).unwrap().into_any().unbind(),
+            });
+        }
+        let topology = self.topology.as_ref().unwrap();
+        let args = (
+            ctx.time(),
+            ctx.timers_remaining(),
+            ctx.proc_name(),
+            ctx.node_name(),
+            topology.processes.bind(py),
+            topology.node_processes.bind(py),
+            topology.groups.bind(py),
+        );
+        self.ctx_class.call1(py, args).unwrap()
+    }
+
     fn handle_proc_actions(ctx: &mut Context, py_ctx: &Py<PyAny>, py: Python) {
         let sent: Vec<(String, String, String)> = py_ctx.getattr(py, "_sent_messages").unwrap().extract(py).unwrap();
         for m in sent {
@@ -101,23 +141,34 @@ impl PyProcess {
         for m in sent_local {
             ctx.send_local(Message::new(&m.0, &m.1));
         }
-        let timer_actions: Vec<(String, f64, bool)> =
+        let timer_actions: Vec<(String, String, f64, f64)> =
             py_ctx.getattr(py, "_timer_actions").unwrap().extract(py).unwrap();
-        for t in timer_actions {
-            if t.1 < 0.0 {
-                ctx.cancel_timer(&t.0);
-            } else if t.2 {
-                ctx.set_timer_once(&t.0, t.1);
+        for (action, name, delay, jitter) in timer_actions {
+            match action.as_str() {
+                "set" => ctx.set_timer(&name, delay),
+                "once" => ctx.set_timer_once(&name, delay),
+                "periodic" => ctx.set_periodic_timer(&name, delay, jitter),
+                _ => ctx.cancel_timer(&name),
+            }
+        }
+        let user_events: Vec<(String, String)> = py_ctx.getattr(py, "_user_events").unwrap().extract(py).unwrap();
+        for e in user_events {
+            ctx.log(&e.0, &e.1);
+        }
+        let metrics: Vec<(String, String, f64)> = py_ctx.getattr(py, "_metrics").unwrap().extract(py).unwrap();
+        for (name, kind, value) in metrics {
+            if kind == "counter" {
+                ctx.increment_counter(&name, value);
             } else {
-                ctx.set_timer(&t.0, t.1);
+                ctx.set_gauge(&name, value);
             }
         }
     }
 
-    fn update_max_size(&mut self, py: Python, force_update: bool) {
+    fn update_max_size(&mut self, py: Python) {
         if self.max_size_freq > 0 {
             self.max_size_counter -= 1;
-            if self.max_size_counter == 0 || force_update {
+            if self.max_size_counter == 0 {
                 let size: u64 = self.get_size_fun.call1(py, (&self.proc,)).unwrap().extract(py).unwrap();
                 self.max_size = self.max_size.max(size);
                 self.max_size_counter = self.max_size_freq;
@@ -137,12 +188,12 @@ impl PyProcess {
 impl Process for PyProcess {
     fn on_start(&mut self, ctx: &mut Context) -> Result<(), String> {
         Python::attach(|py| {
-            let py_ctx = self.ctx_class.call1(py, (ctx.time(),)).unwrap();
+            let py_ctx = self.create_py_context(ctx, py);
             self.proc
                 .call_method1(py, "on_start", (&py_ctx,))
                 .map_err(|e| error_to_string(e, py))?;
             PyProcess::handle_proc_actions(ctx, &py_ctx, py);
-            self.update_max_size(py, false);
+            self.update_max_size(py);
             Ok(())
         })
     }
@@ -153,12 +204,12 @@ impl Process for PyProcess {
                 .msg_class
                 .call_method1(py, "from_json", (msg.tip, msg.data))
                 .unwrap();
-            let py_ctx = self.ctx_class.call1(py, (ctx.time(),)).unwrap();
+            let py_ctx = self.create_py_context(ctx, py);
             self.proc
                 .call_method1(py, "on_message", (py_msg, from, &py_ctx))
                 .map_err(|e| error_to_string(e, py))?;
             PyProcess::handle_proc_actions(ctx, &py_ctx, py);
-            self.update_max_size(py, false);
+            self.update_max_size(py);
             Ok(())
         })
     }
@@ -169,30 +220,29 @@ impl Process for PyProcess {
                 .msg_class
                 .call_method1(py, "from_json", (msg.tip, msg.data))
                 .unwrap();
-            let py_ctx = self.ctx_class.call1(py, (ctx.time(),)).unwrap();
+            let py_ctx = self.create_py_context(ctx, py);
             self.proc
                 .call_method1(py, "on_local_message", (py_msg, &py_ctx))
                 .map_err(|e| error_to_string(e, py))?;
             PyProcess::handle_proc_actions(ctx, &py_ctx, py);
-            self.update_max_size(py, false);
+            self.update_max_size(py);
             Ok(())
         })
     }
 
     fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
         Python::attach(|py| {
-            let py_ctx = self.ctx_class.call1(py, (ctx.time(),)).unwrap();
+            let py_ctx = self.create_py_context(ctx, py);
             self.proc
                 .call_method1(py, "on_timer", (timer, &py_ctx))
                 .map_err(|e| error_to_string(e, py))?;
             PyProcess::handle_proc_actions(ctx, &py_ctx, py);
-            self.update_max_size(py, false);
+            self.update_max_size(py);
             Ok(())
         })
     }
 
     fn max_size(&mut self) -> u64 {
-        Python::attach(|py| self.update_max_size(py, true));
         self.max_size
     }
 
@@ -228,6 +278,8 @@ impl Clone for PyProcess {
             max_size: self.max_size,
             max_size_freq: self.max_size_freq,
             max_size_counter: self.max_size_counter,
+            // topology objects are recreated on the next handler call
+            topology: None,
         }
     }
 }
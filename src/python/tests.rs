@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::rc::Rc;
 
@@ -5,7 +6,8 @@ use serde::Serialize;
 
 use crate::{Message, Process, ProcessState, System};
 
-use crate::python::PyProcessFactory;
+use crate::process::ProcessAccessError;
+use crate::python::{PyProcess, PyProcessFactory};
 
 fn build_system() -> (System, Rc<dyn ProcessState>) {
     let mut sys = System::new(0);
@@ -36,3 +38,61 @@ fn test_set_state() {
     sys.send_local_message("proc", Message::json("CHECK_STATE", &EmptyMessage {}));
     sys.step_until_no_events();
 }
+
+#[test]
+fn test_context_topology() {
+    env::set_var("PYTHONPATH", "python");
+    let mut sys = System::new(0);
+    sys.add_node("node1");
+    sys.add_node("node2");
+    let proc_f = PyProcessFactory::new("tests/python/process.py", "TopologyProcess");
+    sys.add_process("proc1", Box::new(proc_f.build((), 1)), "node1");
+    sys.add_process("proc2", Box::new(proc_f.build((), 1)), "node1");
+    sys.add_process("proc3", Box::new(proc_f.build((), 1)), "node2");
+    sys.add_group("group", &["proc1", "proc3"]);
+
+    sys.send_local_message("proc2", Message::json("GET", &EmptyMessage {}));
+    let info: serde_json::Value = serde_json::from_str(&sys.read_local_messages("proc2")[0].data).unwrap();
+    assert_eq!(
+        info,
+        serde_json::json!({
+            "proc": "proc2",
+            "node": "node1",
+            "processes": ["proc1", "proc2", "proc3"],
+            "peers": ["proc1", "proc3"],
+            "group": ["proc1", "proc3"],
+            "unknown_group": [],
+            "node_processes": ["proc1", "proc2"],
+        })
+    );
+
+    // topology changes are visible in subsequent handler calls
+    sys.add_process("proc4", Box::new(proc_f.build((), 1)), "node1");
+    sys.add_group("group", &["proc4"]);
+    sys.send_local_message("proc2", Message::json("GET", &EmptyMessage {}));
+    let info: serde_json::Value = serde_json::from_str(&sys.read_local_messages("proc2")[0].data).unwrap();
+    assert_eq!(
+        info["processes"],
+        serde_json::json!(["proc1", "proc2", "proc3", "proc4"])
+    );
+    assert_eq!(info["group"], serde_json::json!(["proc4"]));
+    assert_eq!(info["node_processes"], serde_json::json!(["proc1", "proc2", "proc4"]));
+}
+
+#[test]
+fn test_state_json() {
+    env::set_var("PYTHONPATH", "python");
+    let (sys, _) = build_system();
+    assert!(sys.process::<PyProcess>("proc").is_ok());
+
+    // the default state of Python process is a JSON object with encoded values of process fields
+    let state = sys.proc_state_json::<BTreeMap<String, String>>("proc").unwrap();
+    assert_eq!(
+        state.keys().collect::<Vec<_>>(),
+        ["data", "inner_member", "messages", "tmp_value"]
+    );
+    assert!(matches!(
+        sys.proc_state::<u64>("proc"),
+        Err(ProcessAccessError::WrongStateType { .. })
+    ));
+}
@@ -0,0 +1,39 @@
+//! Request/response calls between processes (see [`Context::call`](crate::Context::call)).
+//!
+//! The call id is transmitted along with the message as a separate [`CallTag`],
+//! so the message type specified by the sender is delivered unchanged.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+const TIMEOUT_TIMER_PREFIX: &str = "call-timeout#";
+
+/// Role of the message in a request/response call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CallTag {
+    /// Request with the specified call id sent with [`Context::call`](crate::Context::call).
+    Request(u64),
+    /// Response to the call with the specified id sent with [`Context::reply`](crate::Context::reply)
+    /// or [`Context::reply_to`](crate::Context::reply_to).
+    Response(u64),
+}
+
+/// Outgoing call awaiting a response.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct PendingCall {
+    /// Name of the called process.
+    pub dst: String,
+    /// Request message.
+    pub request: Message,
+}
+
+/// Returns the name of the timer used to detect the call timeout.
+pub(crate) fn timeout_timer(call_id: u64) -> String {
+    format!("{TIMEOUT_TIMER_PREFIX}{call_id}")
+}
+
+/// Returns the call id if the timer is a call timeout timer.
+pub(crate) fn parse_timeout_timer(timer: &str) -> Option<u64> {
+    timer.strip_prefix(TIMEOUT_TIMER_PREFIX)?.parse().ok()
+}
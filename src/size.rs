@@ -0,0 +1,151 @@
+//! Estimation of memory size of process data.
+//!
+//! The size of process inner data is tracked by calling [`Process::size`](crate::Process::size) after each event.
+//! It can be implemented using the [`SizeOf`] trait, which is implemented for primitive types and std collections:
+//!
+//! ```ignore
+//! impl Process for MyProcess {
+//!     ...
+//!
+//!     fn size(&self) -> u64 {
+//!         self.storage.size_of() + self.pending.size_of()
+//!     }
+//! }
+//! ```
+//!
+//! Alternatively, the size of JSON serialization returned by [`serialized_size`] can be used.
+//! The maximum observed size can be obtained via [`System::max_size`](crate::System::max_size) or checked
+//! in model checking with [`invariants::process_size_limit`](crate::mc::predicates::invariants::process_size_limit).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::mem;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::Message;
+
+/// Estimates the memory size of a value in bytes including the data owned by it on the heap.
+///
+/// The estimation does not account for unused capacity of collections and allocator overhead.
+pub trait SizeOf {
+    /// Returns the estimated size of the value in bytes.
+    fn size_of(&self) -> u64;
+}
+
+/// Returns the length of the value serialized to JSON in bytes.
+pub fn serialized_size<T: Serialize + ?Sized>(value: &T) -> u64 {
+    serde_json::to_vec(value).map(|json| json.len() as u64).unwrap_or(0)
+}
+
+macro_rules! impl_size_of_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl SizeOf for $t {
+                fn size_of(&self) -> u64 {
+                    mem::size_of::<$t>() as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_size_of_primitive!((), bool, char, f32, f64);
+impl_size_of_primitive!(u8, u16, u32, u64, u128, usize);
+impl_size_of_primitive!(i8, i16, i32, i64, i128, isize);
+
+impl SizeOf for String {
+    fn size_of(&self) -> u64 {
+        (mem::size_of::<String>() + self.len()) as u64
+    }
+}
+
+impl SizeOf for Message {
+    fn size_of(&self) -> u64 {
+        self.tip.size_of() + self.data.size_of()
+    }
+}
+
+impl<T: SizeOf> SizeOf for Option<T> {
+    fn size_of(&self) -> u64 {
+        let inline = mem::size_of::<Self>() as u64;
+        match self {
+            Some(value) => inline - mem::size_of::<T>() as u64 + value.size_of(),
+            None => inline,
+        }
+    }
+}
+
+impl<T: SizeOf + ?Sized> SizeOf for Box<T> {
+    fn size_of(&self) -> u64 {
+        mem::size_of::<Self>() as u64 + (**self).size_of()
+    }
+}
+
+/// The shared value is counted in each reference.
+impl<T: SizeOf + ?Sized> SizeOf for Rc<T> {
+    fn size_of(&self) -> u64 {
+        mem::size_of::<Self>() as u64 + (**self).size_of()
+    }
+}
+
+impl<T: SizeOf> SizeOf for [T] {
+    fn size_of(&self) -> u64 {
+        self.iter().map(SizeOf::size_of).sum()
+    }
+}
+
+impl<T: SizeOf, const N: usize> SizeOf for [T; N] {
+    fn size_of(&self) -> u64 {
+        self.as_slice().size_of()
+    }
+}
+
+macro_rules! impl_size_of_collection {
+    ($($t:ident),*) => {
+        $(
+            impl<T: SizeOf> SizeOf for $t<T> {
+                fn size_of(&self) -> u64 {
+                    mem::size_of::<Self>() as u64 + self.iter().map(SizeOf::size_of).sum::<u64>()
+                }
+            }
+        )*
+    };
+}
+
+impl_size_of_collection!(Vec, VecDeque, BTreeSet);
+
+impl<T: SizeOf, S> SizeOf for HashSet<T, S> {
+    fn size_of(&self) -> u64 {
+        mem::size_of::<Self>() as u64 + self.iter().map(SizeOf::size_of).sum::<u64>()
+    }
+}
+
+impl<K: SizeOf, V: SizeOf, S> SizeOf for HashMap<K, V, S> {
+    fn size_of(&self) -> u64 {
+        mem::size_of::<Self>() as u64 + self.iter().map(|(k, v)| k.size_of() + v.size_of()).sum::<u64>()
+    }
+}
+
+impl<K: SizeOf, V: SizeOf> SizeOf for BTreeMap<K, V> {
+    fn size_of(&self) -> u64 {
+        mem::size_of::<Self>() as u64 + self.iter().map(|(k, v)| k.size_of() + v.size_of()).sum::<u64>()
+    }
+}
+
+macro_rules! impl_size_of_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: SizeOf),+> SizeOf for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn size_of(&self) -> u64 {
+                let ($($name,)+) = self;
+                0 $(+ $name.size_of())+
+            }
+        }
+    };
+}
+
+impl_size_of_tuple!(A);
+impl_size_of_tuple!(A, B);
+impl_size_of_tuple!(A, B, C);
+impl_size_of_tuple!(A, B, C, D);
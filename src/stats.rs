@@ -0,0 +1,353 @@
+//! Latency and throughput statistics of client operations.
+//!
+//! Client operations are identified by matching local messages received by client processes (requests) with local
+//! messages sent by these processes (responses) using a user-provided correlation key, e.g. a request id included
+//! in both messages. The operations can be collected from a trace (see [`OperationStats::from_trace`]) or during
+//! the simulation (see [`StatsRecorder`]). The collected operations are summarized in a [`StatsReport`] with latency
+//! percentiles for all operations and per operation type, throughput over time windows and network usage per
+//! completed operation, which can be exported as JSON or CSV.
+//!
+//! ```ignore
+//! let stats = OperationStats::from_trace(sys.logger().trace(), request_id, response_id);
+//! let report = stats.report(&sys.network(), 10.0);
+//! println!("{}", report.to_csv());
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::logger::{LogEntry, LogSink};
+use crate::{Message, Network};
+
+/// Client operation matched from request and response local messages.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OperationRecord {
+    /// Operation type, i.e. the type of request message.
+    pub op: String,
+    /// Name of client process.
+    pub client: String,
+    /// Correlation key of request and response.
+    pub key: String,
+    /// Time of request.
+    pub start: f64,
+    /// Time of response, `None` if the response is not received.
+    pub end: Option<f64>,
+}
+
+impl OperationRecord {
+    /// Returns the operation latency or `None` if the operation is not completed.
+    pub fn latency(&self) -> Option<f64> {
+        self.end.map(|end| end - self.start)
+    }
+}
+
+type KeyFn = Box<dyn Fn(&Message) -> Option<String>>;
+
+/// Operations collected from local messages of client processes.
+#[derive(Clone, Debug, Default)]
+pub struct OperationStats {
+    records: Vec<OperationRecord>,
+    pending: HashMap<(String, String), usize>,
+}
+
+impl OperationStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects operations from a trace.
+    ///
+    /// A local message received by a process is treated as a request if the `request_key` function returns its
+    /// correlation key. A local message sent by the same process is treated as the response if the `response_key`
+    /// function returns the same key. Only the first response is matched with the request.
+    pub fn from_trace<FReq, FResp>(trace: &[LogEntry], request_key: FReq, response_key: FResp) -> Self
+    where
+        FReq: Fn(&Message) -> Option<String>,
+        FResp: Fn(&Message) -> Option<String>,
+    {
+        let mut stats = Self::new();
+        for entry in trace {
+            stats.record(entry, &request_key, &response_key);
+        }
+        stats
+    }
+
+    fn record<FReq, FResp>(&mut self, entry: &LogEntry, request_key: FReq, response_key: FResp)
+    where
+        FReq: Fn(&Message) -> Option<String>,
+        FResp: Fn(&Message) -> Option<String>,
+    {
+        match entry {
+            LogEntry::LocalMessageReceived { time, proc, msg, .. } => {
+                if let Some(key) = request_key(msg) {
+                    self.pending.insert((proc.clone(), key.clone()), self.records.len());
+                    self.records.push(OperationRecord {
+                        op: msg.tip.clone(),
+                        client: proc.clone(),
+                        key,
+                        start: *time,
+                        end: None,
+                    });
+                }
+            }
+            LogEntry::LocalMessageSent { time, proc, msg, .. } => {
+                if let Some(key) = response_key(msg) {
+                    if let Some(idx) = self.pending.remove(&(proc.clone(), key)) {
+                        self.records[idx].end = Some(*time);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the collected operations in the order of requests.
+    pub fn records(&self) -> &[OperationRecord] {
+        &self.records
+    }
+
+    /// Returns the latencies of completed operations of the specified type (or all operations)
+    /// in the order of requests.
+    pub fn latencies(&self, op: Option<&str>) -> Vec<f64> {
+        self.records
+            .iter()
+            .filter(|r| op.is_none_or(|op| r.op == op))
+            .filter_map(|r| r.latency())
+            .collect()
+    }
+
+    /// Builds a report using the specified width of throughput windows.
+    ///
+    /// Network usage per operation is computed from the total number of messages and traffic sent over
+    /// the network (see [`Network::network_message_count`] and [`Network::traffic`]) divided by the number
+    /// of completed operations.
+    pub fn report(&self, network: &Network, window: f64) -> StatsReport {
+        assert!(window > 0., "Window width must be positive");
+        let mut by_op: BTreeMap<&str, Vec<&OperationRecord>> = BTreeMap::new();
+        for record in &self.records {
+            by_op.entry(&record.op).or_default().push(record);
+        }
+        let latency = by_op
+            .into_iter()
+            .map(|(op, records)| LatencyStats::new(op, records.into_iter()))
+            .collect();
+
+        let mut ends: Vec<f64> = self.records.iter().filter_map(|r| r.end).collect();
+        ends.sort_by(f64::total_cmp);
+        let mut throughput = Vec::new();
+        if let Some(last) = ends.last() {
+            let start = self.records.iter().map(|r| r.start).fold(f64::INFINITY, f64::min);
+            let count = ((last - start) / window).floor() as usize + 1;
+            for i in 0..count {
+                let window_start = start + i as f64 * window;
+                let window_end = window_start + window;
+                let completed = ends.iter().filter(|t| **t >= window_start && **t < window_end).count();
+                throughput.push(ThroughputWindow {
+                    start: window_start,
+                    end: window_end,
+                    completed,
+                    throughput: completed as f64 / window,
+                });
+            }
+        }
+
+        let completed = ends.len();
+        let messages = network.network_message_count();
+        let traffic = network.traffic();
+        let per_op = |total: u64| {
+            if completed > 0 {
+                total as f64 / completed as f64
+            } else {
+                0.
+            }
+        };
+        StatsReport {
+            total: LatencyStats::new("", self.records.iter()),
+            latency,
+            throughput,
+            network_messages: messages,
+            traffic,
+            messages_per_op: per_op(messages),
+            bytes_per_op: per_op(traffic),
+        }
+    }
+}
+
+/// Collects operations from logged events as a [`LogSink`].
+///
+/// Allows to collect the statistics without keeping the whole trace in memory.
+pub struct StatsRecorder {
+    stats: Rc<RefCell<OperationStats>>,
+    request_key: KeyFn,
+    response_key: KeyFn,
+}
+
+impl StatsRecorder {
+    /// Creates a recorder with the specified correlation key functions (see [`OperationStats::from_trace`]).
+    pub fn new<FReq, FResp>(request_key: FReq, response_key: FResp) -> Self
+    where
+        FReq: Fn(&Message) -> Option<String> + 'static,
+        FResp: Fn(&Message) -> Option<String> + 'static,
+    {
+        Self {
+            stats: Rc::new(RefCell::new(OperationStats::new())),
+            request_key: Box::new(request_key),
+            response_key: Box::new(response_key),
+        }
+    }
+
+    /// Returns a shared reference to the collected statistics.
+    pub fn stats(&self) -> Rc<RefCell<OperationStats>> {
+        self.stats.clone()
+    }
+}
+
+impl LogSink for StatsRecorder {
+    fn write(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        self.stats
+            .borrow_mut()
+            .record(entry, &self.request_key, &self.response_key);
+        Ok(())
+    }
+}
+
+/// Latency distribution of operations of some type.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LatencyStats {
+    /// Operation type, empty for the statistics of all operations.
+    pub op: String,
+    /// Number of requests.
+    pub count: usize,
+    /// Number of completed operations.
+    pub completed: usize,
+    /// Mean latency.
+    pub mean: f64,
+    /// Median latency.
+    pub p50: f64,
+    /// 95th percentile of latency.
+    pub p95: f64,
+    /// 99th percentile of latency.
+    pub p99: f64,
+    /// Maximum latency.
+    pub max: f64,
+}
+
+impl LatencyStats {
+    fn new<'a>(op: &str, records: impl Iterator<Item = &'a OperationRecord>) -> Self {
+        let mut count = 0;
+        let mut latencies = Vec::new();
+        for record in records {
+            count += 1;
+            latencies.extend(record.latency());
+        }
+        latencies.sort_by(f64::total_cmp);
+        let mean = if latencies.is_empty() {
+            0.
+        } else {
+            latencies.iter().sum::<f64>() / latencies.len() as f64
+        };
+        Self {
+            op: op.to_string(),
+            count,
+            completed: latencies.len(),
+            mean,
+            p50: percentile(&latencies, 50.),
+            p95: percentile(&latencies, 95.),
+            p99: percentile(&latencies, 99.),
+            max: latencies.last().copied().unwrap_or(0.),
+        }
+    }
+}
+
+/// Returns the percentile of sorted values using the nearest-rank method.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.;
+    }
+    let rank = (p / 100. * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Number of operations completed in a time window.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ThroughputWindow {
+    /// Window start time (inclusive).
+    pub start: f64,
+    /// Window end time (exclusive).
+    pub end: f64,
+    /// Number of operations completed in the window.
+    pub completed: usize,
+    /// Completed operations per time unit.
+    pub throughput: f64,
+}
+
+/// Summary of operation statistics.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StatsReport {
+    /// Latency statistics for all operations.
+    pub total: LatencyStats,
+    /// Latency statistics per operation type sorted by type.
+    pub latency: Vec<LatencyStats>,
+    /// Throughput over consecutive time windows starting from the first request.
+    pub throughput: Vec<ThroughputWindow>,
+    /// Total number of messages sent over the network.
+    pub network_messages: u64,
+    /// Total size of messages sent over the network.
+    pub traffic: u64,
+    /// Network messages per completed operation.
+    pub messages_per_op: f64,
+    /// Network traffic per completed operation.
+    pub bytes_per_op: f64,
+}
+
+impl StatsReport {
+    /// Returns the report serialized as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Returns the latency statistics as CSV with a header, one row per operation type.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("op,count,completed,mean,p50,p95,p99,max\n");
+        for s in &self.latency {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                s.op, s.count, s.completed, s.mean, s.p50, s.p95, s.p99, s.max
+            ));
+        }
+        csv
+    }
+
+    /// Returns the latency statistics for all operations and the network usage as CSV with a header and one row.
+    pub fn summary_csv(&self) -> String {
+        let s = &self.total;
+        format!(
+            "count,completed,mean,p50,p95,p99,max,network_messages,traffic,messages_per_op,bytes_per_op\n\
+             {},{},{},{},{},{},{},{},{},{},{}\n",
+            s.count,
+            s.completed,
+            s.mean,
+            s.p50,
+            s.p95,
+            s.p99,
+            s.max,
+            self.network_messages,
+            self.traffic,
+            self.messages_per_op,
+            self.bytes_per_op
+        )
+    }
+
+    /// Returns the throughput windows as CSV with a header, one row per window.
+    pub fn throughput_csv(&self) -> String {
+        let mut csv = String::from("start,end,completed,throughput\n");
+        for w in &self.throughput {
+            csv.push_str(&format!("{},{},{},{}\n", w.start, w.end, w.completed, w.throughput));
+        }
+        csv
+    }
+}
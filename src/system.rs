@@ -2,18 +2,66 @@
 
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::HashMap;
+use std::io;
 use std::path::Path;
 use std::rc::Rc;
 
 use indexmap::IndexMap;
 use rand::distributions::uniform::{SampleRange, SampleUniform};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sugars::{rc, refcell};
 
 use simcore::handler::EventCancellationPolicy;
-use simcore::{cast, Simulation};
+use simcore::{cast, Event, EventHandler, EventId, Simulation, SimulationContext};
 
+use crate::context::Topology;
+use crate::error::{InvariantViolation, ProcessError, ProcessErrorPolicy, ProcessErrors, StepError};
 use crate::events::MessageReceived;
-use crate::logger::{LogEntry, Logger};
-use crate::{EventLogEntry, Message, Network, Node, Process};
+use crate::logger::{LogEntry, Logger, LoggerConfig};
+use crate::metrics::Metrics;
+use crate::process::{downcast_state, parse_state_json, ProcessAccessError};
+use crate::{EventLogEntry, Message, Network, Node, Process, ProcessState};
+
+/// Invariant checked during the simulation, receives the system and returns an error if the invariant is violated.
+pub type SystemInvariantFn = Box<dyn FnMut(&System) -> Result<(), String>>;
+
+/// Invariants checked during the simulation.
+struct Invariants {
+    list: Vec<(String, SystemInvariantFn)>,
+    check_interval: u64,
+    steps_since_check: u64,
+    checkpoint_time: f64,
+    checkpoint_trace_position: u64,
+}
+
+impl Default for Invariants {
+    fn default() -> Self {
+        Self {
+            list: Vec::new(),
+            check_interval: 1,
+            steps_since_check: 0,
+            checkpoint_time: 0.,
+            checkpoint_trace_position: 0,
+        }
+    }
+}
+
+/// Marks the end of the simulation steps performed by [`System::try_step_for_duration`].
+#[derive(Clone, Serialize)]
+struct DeadlineReached {}
+
+/// Receives the deadline events.
+#[derive(Default)]
+struct Deadline {
+    reached: Option<EventId>,
+}
+
+impl EventHandler for Deadline {
+    fn on(&mut self, event: Event) {
+        self.reached = Some(event.id);
+    }
+}
 
 /// Models distributed system consisting of multiple nodes connected via network.
 pub struct System {
@@ -22,34 +70,61 @@ pub struct System {
     nodes: HashMap<String, Rc<RefCell<Node>>>,
     proc_nodes: IndexMap<String, Rc<RefCell<Node>>>,
     logger: Rc<RefCell<Logger>>,
+    metrics: Rc<RefCell<Metrics>>,
+    errors: Rc<RefCell<ProcessErrors>>,
+    topology: Rc<RefCell<Topology>>,
+    invariants: Invariants,
+    deadline: Rc<RefCell<Deadline>>,
+    deadline_ctx: SimulationContext,
+    workload_count: usize,
 }
 
 impl System {
     /// Creates a system with specified random seed.
     pub fn new(seed: u64) -> Self {
-        let logger = Rc::new(RefCell::new(Logger::new()));
-        let mut sim = Simulation::new(seed);
-        let net = Rc::new(RefCell::new(Network::new(sim.create_context("net"), logger.clone())));
-        Self {
-            sim,
-            net,
-            nodes: HashMap::new(),
-            proc_nodes: IndexMap::new(),
-            logger,
-        }
+        Self::with_logger_config(seed, LoggerConfig::default())
     }
 
     /// Creates a system with logging events to file.
+    ///
+    /// The file output is buffered, the pending events are written when the system is dropped,
+    /// when the simulation is stopped by an error or on [`Self::flush_log`].
+    ///
+    /// Panics if the log file can not be created.
     pub fn with_log_file(seed: u64, log_path: &Path) -> Self {
-        let logger = Rc::new(RefCell::new(Logger::with_log_file(log_path)));
+        Self::with_logger_config(seed, LoggerConfig::default().log_file(log_path))
+    }
+
+    /// Creates a system with the specified logger configuration.
+    ///
+    /// Panics if the log file can not be created.
+    pub fn with_logger_config(seed: u64, config: LoggerConfig) -> Self {
+        let logger = Rc::new(RefCell::new(Logger::with_config(config)));
         let mut sim = Simulation::new(seed);
-        let net = Rc::new(RefCell::new(Network::new(sim.create_context("net"), logger.clone())));
+        let metrics = Rc::new(RefCell::new(Metrics::new()));
+        let net = Rc::new(RefCell::new(Network::new(
+            sim.create_context("net"),
+            logger.clone(),
+            metrics.clone(),
+        )));
+        // the deadline events are handled by the network component, which does not receive other events,
+        // to keep the identifiers of node components unchanged
+        let deadline = rc!(refcell!(Deadline::default()));
+        let deadline_ctx = sim.create_context("net");
+        sim.add_handler("net", deadline.clone());
         Self {
             sim,
             net,
             nodes: HashMap::new(),
             proc_nodes: IndexMap::new(),
             logger,
+            metrics,
+            errors: Rc::new(RefCell::new(ProcessErrors::default())),
+            topology: Rc::new(RefCell::new(Topology::default())),
+            invariants: Invariants::default(),
+            deadline,
+            deadline_ctx,
+            workload_count: 0,
         }
     }
 
@@ -58,6 +133,23 @@ impl System {
         self.logger.borrow_mut()
     }
 
+    /// Writes the buffered events to the log file and other sinks.
+    ///
+    /// Returns the first error returned by the log file or another sink during the simulation,
+    /// the failed sink does not receive subsequent events.
+    pub fn flush_log(&self) -> io::Result<()> {
+        self.logger.borrow_mut().flush()
+    }
+
+    /// Returns a mutable reference to the metrics registry.
+    ///
+    /// The values of metrics updated over time (e.g. the number of messages in flight) are brought up to date
+    /// with the current simulation time.
+    pub fn metrics(&self) -> RefMut<'_, Metrics> {
+        self.net.borrow_mut().expire_in_flight();
+        self.metrics.borrow_mut()
+    }
+
     // Network ---------------------------------------------------------------------------------------------------------
 
     /// Returns a mutable reference to network.
@@ -81,6 +173,9 @@ impl System {
             self.net.clone(),
             self.sim.create_context(name),
             self.logger.clone(),
+            self.metrics.clone(),
+            self.errors.clone(),
+            self.topology.clone(),
         )));
         let node_id = self.sim.add_handler(name, node.clone());
         assert!(
@@ -111,6 +206,7 @@ impl System {
     pub fn crash_node(&mut self, node_name: &str) {
         let node = self.nodes.get(node_name).unwrap();
         node.borrow_mut().crash();
+        self.net.borrow_mut().expire_in_flight();
 
         self.logger.borrow_mut().log(LogEntry::NodeCrashed {
             time: self.sim.time(),
@@ -121,6 +217,9 @@ impl System {
         let node_id = self.sim.lookup_id(node_name);
         let cancelled = self.sim.cancel_and_get_events(|e| e.src == node_id);
         for event in cancelled {
+            if let Some(e) = event.data.downcast_ref::<MessageReceived>() {
+                self.net.borrow_mut().cancel_in_flight(e);
+            }
             cast!(match event.data {
                 MessageReceived {
                     id,
@@ -129,6 +228,7 @@ impl System {
                     src_node,
                     dst,
                     dst_node,
+                    ..
                 } => {
                     self.logger.borrow_mut().log(LogEntry::MessageDropped {
                         time: self.sim.time(),
@@ -143,6 +243,13 @@ impl System {
             })
         }
 
+        // messages to the crashed node are discarded on delivery, so they are no longer in flight
+        for event in self.sim.cancel_and_get_events(|e| e.dst == node_id) {
+            if let Some(e) = event.data.downcast_ref::<MessageReceived>() {
+                self.net.borrow_mut().cancel_in_flight(e);
+            }
+        }
+
         // remove the handler to discard all pending and future events sent to this node
         self.sim.remove_handler(node_name, EventCancellationPolicy::Incoming);
     }
@@ -162,6 +269,7 @@ impl System {
 
         // remove previous process-node mappings to enable recreating these processes
         self.proc_nodes.retain(|_, node| node.borrow().name != node_name);
+        self.topology.borrow_mut().remove_node_processes(node_name);
 
         self.logger.borrow_mut().log(LogEntry::NodeRecovered {
             time: self.sim.time(),
@@ -190,6 +298,14 @@ impl System {
     ///
     /// Note that process names should be globally unique.
     pub fn add_process(&mut self, name: &str, proc: Box<dyn Process>, node: &str) {
+        self.try_add_process(name, proc, node)
+            .unwrap_or_else(|err| self.fail(*err))
+    }
+
+    /// Adds a process executing on the node, returns the error if the process failed to start.
+    ///
+    /// In contrast to [`Self::add_process`], the error returned by [`Process::on_start`] does not cause a panic.
+    pub fn try_add_process(&mut self, name: &str, proc: Box<dyn Process>, node: &str) -> Result<(), Box<StepError>> {
         self.net
             .borrow_mut()
             .set_proc_location(name.to_string(), node.to_string());
@@ -199,12 +315,36 @@ impl System {
                 .is_none(),
             "Process with name {name} already exists, process names must be unique"
         );
-        self.nodes[node].borrow_mut().add_process(name, proc);
+        self.topology.borrow_mut().add_process(name, node);
+        self.nodes[node].borrow_mut().try_add_process(name, proc)?;
         self.logger.borrow_mut().log(LogEntry::ProcessStarted {
             time: self.sim.time(),
             node: node.to_string(),
             proc: name.to_string(),
         });
+        Ok(())
+    }
+
+    /// Returns the node hosting the process.
+    pub(crate) fn proc_node(&self, proc: &str) -> Rc<RefCell<Node>> {
+        self.proc_nodes
+            .get(proc)
+            .unwrap_or_else(|| panic!("Process {proc} does not exist"))
+            .clone()
+    }
+
+    /// Adds a named group of processes, available to processes via [`Context::group`](crate::Context::group).
+    ///
+    /// Existing group with the same name is replaced.
+    pub fn add_group(&mut self, name: &str, procs: &[&str]) {
+        self.topology
+            .borrow_mut()
+            .add_group(name, procs.iter().map(|proc| proc.to_string()).collect());
+    }
+
+    /// Returns the system topology shared with the processes.
+    pub(crate) fn topology(&self) -> Rc<RefCell<Topology>> {
+        self.topology.clone()
     }
 
     /// Returns the names of all processes in the system in the order they were added.
@@ -212,8 +352,77 @@ impl System {
         self.proc_nodes.keys().cloned().collect()
     }
 
+    /// Returns a reference to the process of the specified type.
+    ///
+    /// ```ignore
+    /// let replica = sys.process::<Replica>("replica1")?;
+    /// ```
+    pub fn process<T: Process>(&self, proc: &str) -> Result<Ref<'_, T>, ProcessAccessError> {
+        let node = self
+            .proc_nodes
+            .get(proc)
+            .ok_or_else(|| ProcessAccessError::UnknownProcess { proc: proc.to_string() })?
+            .borrow();
+        if node.get_process(proc).is_none() {
+            return Err(ProcessAccessError::UnknownProcess { proc: proc.to_string() });
+        }
+        Ref::filter_map(node, |node| node.get_process(proc)?.downcast_ref::<T>()).map_err(|_| {
+            ProcessAccessError::WrongProcessType {
+                proc: proc.to_string(),
+                expected: std::any::type_name::<T>(),
+            }
+        })
+    }
+
+    /// Returns all processes of the specified type with their names in the order they were added.
+    pub fn processes_of_type<T: Process>(&self) -> impl Iterator<Item = (&str, Ref<'_, T>)> {
+        self.proc_nodes.iter().filter_map(|(proc, node)| {
+            Ref::filter_map(node.borrow(), |node| {
+                node.get_process(proc).and_then(|proc| proc.downcast_ref::<T>())
+            })
+            .ok()
+            .map(|proc_impl| (proc.as_str(), proc_impl))
+        })
+    }
+
+    /// Returns the state of the process downcasted to the specified type.
+    pub fn proc_state<T: ProcessState>(&self, proc: &str) -> Result<Rc<T>, ProcessAccessError> {
+        downcast_state(proc, self.dyn_proc_state(proc)?)
+    }
+
+    /// Parses the string state of the process as JSON of the specified type.
+    ///
+    /// Can be used to read the state of Python processes returning JSON from `get_state`,
+    /// `serde_json::Value` can be used as the type if the state structure is not known.
+    pub fn proc_state_json<T: DeserializeOwned>(&self, proc: &str) -> Result<T, ProcessAccessError> {
+        parse_state_json(proc, self.dyn_proc_state(proc)?)
+    }
+
+    fn dyn_proc_state(&self, proc: &str) -> Result<Rc<dyn ProcessState>, ProcessAccessError> {
+        let node = self
+            .proc_nodes
+            .get(proc)
+            .ok_or_else(|| ProcessAccessError::UnknownProcess { proc: proc.to_string() })?
+            .borrow();
+        node.get_process(proc)
+            .ok_or_else(|| ProcessAccessError::UnknownProcess { proc: proc.to_string() })?
+            .state()
+            .map_err(|error| ProcessAccessError::StateError {
+                proc: proc.to_string(),
+                error,
+            })
+    }
+
     /// Sends a local message to the process.
     pub fn send_local_message(&mut self, proc: &str, msg: Message) {
+        self.try_send_local_message(proc, msg)
+            .unwrap_or_else(|err| self.fail(*err))
+    }
+
+    /// Sends a local message to the process, returns the error if the process failed to handle it.
+    ///
+    /// In contrast to [`Self::send_local_message`], the process error does not cause a panic.
+    pub fn try_send_local_message(&mut self, proc: &str, msg: Message) -> Result<(), Box<StepError>> {
         let mut node = self.proc_nodes[proc].borrow_mut();
         assert!(
             !node.is_crashed(),
@@ -222,6 +431,7 @@ impl System {
             node.name
         );
         node.send_local_message(proc.to_string(), msg);
+        node.take_process_error()
     }
 
     /// Reads and returns the local messages produced by the process.
@@ -244,7 +454,7 @@ impl System {
         self.proc_nodes[proc].borrow().event_log(proc)
     }
 
-    /// Returns the maximum size of process inner data observed so far.
+    /// Returns the maximum size of process inner data observed so far (see [`Node::max_size`]).
     pub fn max_size(&mut self, proc: &str) -> u64 {
         self.proc_nodes[proc].borrow_mut().max_size(proc)
     }
@@ -269,8 +479,121 @@ impl System {
         self.proc_nodes[proc].borrow().is_crashed()
     }
 
+    /// Checks if the process is crashed after an error (see [`ProcessErrorPolicy::CrashProcess`]).
+    pub fn proc_is_crashed(&self, proc: &str) -> bool {
+        self.proc_nodes[proc].borrow().process_is_crashed(proc)
+    }
+
+    /// Sets the policy of handling errors returned by processes.
+    ///
+    /// By default, the simulation is stopped on the first error.
+    pub fn set_process_error_policy(&mut self, policy: ProcessErrorPolicy) {
+        self.errors.borrow_mut().policy = policy;
+    }
+
+    /// Returns all errors returned by processes so far.
+    pub fn process_errors(&self) -> Vec<ProcessError> {
+        let logger = self.logger.borrow();
+        let trace = logger.trace();
+        self.errors
+            .borrow()
+            .errors
+            .iter()
+            .map(|(error, position)| {
+                let until = trace.len() - logger.trace_since(*position).len();
+                ProcessError {
+                    trace: trace[..until].to_vec(),
+                    ..error.clone()
+                }
+            })
+            .collect()
+    }
+
+    // Invariants ------------------------------------------------------------------------------------------------------
+
+    /// Adds an invariant which is checked automatically during the simulation.
+    ///
+    /// The invariant receives the system after the simulation step and can inspect the process states,
+    /// the network and the simulation time. By default, the invariants are checked after each step,
+    /// this can be changed with [`Self::set_invariant_check_interval`].
+    ///
+    /// The first violation stops the simulation. It is returned by `try_step*` methods as
+    /// [`StepError::InvariantViolation`], other simulation methods panic with the violation message.
+    pub fn add_invariant<F>(&mut self, name: &str, invariant: F)
+    where
+        F: FnMut(&System) -> Result<(), String> + 'static,
+    {
+        if self.invariants.list.is_empty() {
+            self.set_invariant_checkpoint();
+        }
+        self.invariants.list.push((name.to_string(), Box::new(invariant)));
+    }
+
+    /// Sets the number of simulation steps between the invariant checks.
+    pub fn set_invariant_check_interval(&mut self, step_count: u64) {
+        assert!(step_count > 0, "Invariant check interval must be positive");
+        self.invariants.check_interval = step_count;
+    }
+
+    /// Checks all invariants, returns the first violation.
+    ///
+    /// The trace of the violation contains the events since the last successful check.
+    pub fn check_invariants(&mut self) -> Result<(), Box<InvariantViolation>> {
+        let mut invariants = std::mem::take(&mut self.invariants.list);
+        let res = invariants
+            .iter_mut()
+            .find_map(|(name, invariant)| invariant(self).err().map(|err| (name.clone(), err)));
+        self.invariants.list = invariants;
+        match res {
+            Some((invariant, error)) => Err(Box::new(InvariantViolation {
+                invariant,
+                error,
+                time: self.time(),
+                checkpoint_time: self.invariants.checkpoint_time,
+                trace: self
+                    .logger
+                    .borrow()
+                    .trace_since(self.invariants.checkpoint_trace_position)
+                    .to_vec(),
+            })),
+            None => {
+                self.set_invariant_checkpoint();
+                Ok(())
+            }
+        }
+    }
+
+    fn set_invariant_checkpoint(&mut self) {
+        self.invariants.steps_since_check = 0;
+        self.invariants.checkpoint_time = self.time();
+        self.invariants.checkpoint_trace_position = self.logger.borrow().trace_position();
+    }
+
+    /// Checks the invariants if the check interval is reached after the simulation step.
+    fn check_invariants_after_step(&mut self) -> Result<(), Box<InvariantViolation>> {
+        if self.invariants.list.is_empty() {
+            return Ok(());
+        }
+        self.invariants.steps_since_check += 1;
+        if self.invariants.steps_since_check < self.invariants.check_interval {
+            return Ok(());
+        }
+        self.check_invariants()
+    }
+
     // Simulation ------------------------------------------------------------------------------------------------------
 
+    /// Creates a simulation context for a new workload driver.
+    pub(crate) fn create_workload_context(&mut self) -> SimulationContext {
+        self.workload_count += 1;
+        self.sim.create_context(format!("workload-{}", self.workload_count))
+    }
+
+    /// Adds a simulation event handler for the component with the specified name.
+    pub(crate) fn add_handler(&mut self, name: &str, handler: Rc<RefCell<dyn EventHandler>>) {
+        self.sim.add_handler(name, handler);
+    }
+
     /// Returns the reference to inner simulation.
     pub fn sim(&self) -> &Simulation {
         &self.sim
@@ -283,22 +606,106 @@ impl System {
 
     /// Performs a single step through the simulation.
     pub fn step(&mut self) -> bool {
-        self.sim.step()
+        self.try_step().unwrap_or_else(|err| self.fail(*err))
     }
 
     /// Performs the specified number of steps through the simulation.
     pub fn steps(&mut self, step_count: u64) -> bool {
-        self.sim.steps(step_count)
+        self.try_steps(step_count).unwrap_or_else(|err| self.fail(*err))
     }
 
     /// Steps through the simulation until there are no pending events left.
     pub fn step_until_no_events(&mut self) {
-        self.sim.step_until_no_events()
+        self.try_step_until_no_events().unwrap_or_else(|err| self.fail(*err))
     }
 
     /// Steps through the simulation with duration limit.
     pub fn step_for_duration(&mut self, duration: f64) -> bool {
-        self.sim.step_for_duration(duration)
+        self.try_step_for_duration(duration)
+            .unwrap_or_else(|err| self.fail(*err))
+    }
+
+    /// Performs a single step through the simulation, returns the error if the step failed.
+    ///
+    /// In contrast to [`Self::step`], the process error or the invariant violation does not cause a panic.
+    /// The actions performed by the failed process handler are discarded, the simulation can be continued
+    /// after the error.
+    pub fn try_step(&mut self) -> Result<bool, Box<StepError>> {
+        let res = self.sim.step();
+        if res {
+            self.after_step()?;
+        }
+        Ok(res)
+    }
+
+    /// Performs the specified number of steps through the simulation, stops on the first error.
+    pub fn try_steps(&mut self, step_count: u64) -> Result<bool, Box<StepError>> {
+        for _ in 0..step_count {
+            if !self.try_step()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Steps through the simulation until there are no pending events left, stops on the first error.
+    pub fn try_step_until_no_events(&mut self) -> Result<(), Box<StepError>> {
+        while self.try_step()? {}
+        Ok(())
+    }
+
+    /// Steps through the simulation with duration limit, stops on the first error.
+    pub fn try_step_for_duration(&mut self, duration: f64) -> Result<bool, Box<StepError>> {
+        let end_time = self.time() + duration;
+        // The events are processed one by one until the deadline event emitted at the end time is delivered.
+        // The events with the end time emitted later are delivered after the deadline event,
+        // so it is emitted again until no other events are processed before it.
+        let mut deadline = self.deadline_ctx.emit_self(DeadlineReached {}, duration);
+        let mut stepped = false;
+        while self.sim.step() {
+            if self.deadline.borrow_mut().reached.take() == Some(deadline) {
+                if !stepped {
+                    break;
+                }
+                deadline = self.deadline_ctx.emit_self_now(DeadlineReached {});
+                stepped = false;
+                continue;
+            }
+            stepped = true;
+            if let Err(err) = self.after_step() {
+                self.deadline_ctx.cancel_event(deadline);
+                return Err(err);
+            }
+        }
+        // advances the time without processing events
+        Ok(self.sim.step_until_time(end_time))
+    }
+
+    /// Handles the outcome of the simulation step: takes the process error and checks the invariants.
+    fn after_step(&mut self) -> Result<(), Box<StepError>> {
+        let error = self.errors.borrow_mut().pending.take();
+        if let Some(err) = error {
+            return Err(Box::new(StepError::Process(err)));
+        }
+        self.check_invariants_after_step()
+            .map_err(|violation| Box::new(StepError::InvariantViolation(*violation)))
+    }
+
+    /// Panics with the error returned by the simulation step.
+    fn fail(&self, err: StepError) -> ! {
+        // the sink error is less relevant than the simulation error
+        let _ = self.flush_log();
+        match err {
+            StepError::Process(err) => {
+                let node = self.nodes[&err.node].borrow();
+                panic!(
+                    "{}: {}",
+                    node.handle_process_error(err.error.clone(), err.proc.clone()),
+                    err
+                );
+            }
+            StepError::InvariantViolation(violation) => panic!("{violation}"),
+        }
     }
 
     /// Steps through the simulation until the process produces local message(s)
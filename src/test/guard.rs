@@ -0,0 +1,90 @@
+//! Limits and output capture for the test running in the current thread.
+//!
+//! The guard is installed by [`TestSuite`](crate::test::TestSuite) before running a test as the execution hooks
+//! of the current thread. Each simulation or model checking step is reported to the guard, which interrupts the test
+//! if its step or time limit is exceeded. The logger passes printed lines to the guard, which keeps them instead of
+//! printing if the output capture is enabled.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use crate::hooks::{self, ExecutionHooks};
+
+struct Guard {
+    step_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    started: Instant,
+    steps: u64,
+    output: Option<String>,
+}
+
+thread_local! {
+    static GUARD: RefCell<Option<Guard>> = const { RefCell::new(None) };
+}
+
+/// Installs the guard for the current thread.
+pub(crate) fn install(step_limit: Option<u64>, time_limit: Option<Duration>, capture: bool) {
+    GUARD.with(|guard| {
+        *guard.borrow_mut() = Some(Guard {
+            step_limit,
+            time_limit,
+            started: Instant::now(),
+            steps: 0,
+            output: capture.then(String::new),
+        })
+    });
+    hooks::set(Some(Box::new(GuardHooks)));
+}
+
+/// Removes the guard, returns the number of steps and the captured output.
+pub(crate) fn uninstall() -> (u64, String) {
+    hooks::set(None);
+    GUARD.with(|guard| {
+        guard
+            .borrow_mut()
+            .take()
+            .map(|g| (g.steps, g.output.unwrap_or_default()))
+            .unwrap_or_default()
+    })
+}
+
+/// Hooks forwarding the execution events to the guard of the current thread.
+struct GuardHooks;
+
+impl ExecutionHooks for GuardHooks {
+    fn on_step(&self) -> Result<(), String> {
+        GUARD.with(|guard| {
+            let mut guard = guard.borrow_mut();
+            let Some(guard) = guard.as_mut() else {
+                return Ok(());
+            };
+            guard.steps += 1;
+            if let Some(limit) = guard.step_limit.filter(|limit| guard.steps > *limit) {
+                return Err(format!("step limit of {limit} steps exceeded"));
+            }
+            if let Some(limit) = guard.time_limit {
+                if guard.started.elapsed() > limit {
+                    return Err(format!("time limit of {:.3}s exceeded", limit.as_secs_f64()));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn is_capturing(&self) -> bool {
+        GUARD.with(|guard| guard.borrow().as_ref().is_some_and(|g| g.output.is_some()))
+    }
+
+    fn capture(&self, line: &str) -> bool {
+        GUARD.with(
+            |guard| match guard.borrow_mut().as_mut().and_then(|g| g.output.as_mut()) {
+                Some(output) => {
+                    output.push_str(line);
+                    output.push('\n');
+                    true
+                }
+                None => false,
+            },
+        )
+    }
+}
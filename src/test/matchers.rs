@@ -0,0 +1,584 @@
+//! Matchers and assertions over process event logs and traces.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+use serde_json::Value;
+
+use crate::logger::LogEntry;
+use crate::{EventLogEntry, Message, ProcessEvent};
+
+/// Maximum number of entries included in the excerpt of a failed assertion.
+const EXCERPT_LIMIT: usize = 10;
+
+/// Number of neighbouring entries shown around the relevant entry in the excerpt.
+const EXCERPT_CONTEXT: usize = 2;
+
+/// Log entry which can be checked with [`EventMatcher`].
+///
+/// Implemented for process event log entries ([`EventLogEntry`]) and trace entries ([`LogEntry`]).
+pub trait MatchableEvent {
+    /// Returns the event kind, e.g. `MessageSent`. The `Mc` prefix of model checking entries is omitted.
+    fn event_kind(&self) -> &str;
+    /// Returns the event time if it is known.
+    fn event_time(&self) -> Option<f64>;
+    /// Returns the message associated with the event.
+    fn event_msg(&self) -> Option<&Message>;
+    /// Returns the sender of the message or the process where the event happened, if known.
+    fn event_src(&self) -> Option<&str>;
+    /// Returns the receiver of the message or the process where the event happened, if known.
+    fn event_dst(&self) -> Option<&str>;
+    /// Returns the name of the timer, the kind of the user event or the name of the metric.
+    fn event_name(&self) -> Option<&str>;
+    /// Returns a single-line description of the event.
+    fn describe(&self) -> String;
+}
+
+impl MatchableEvent for EventLogEntry {
+    fn event_kind(&self) -> &str {
+        match self.event {
+            ProcessEvent::MessageSent { .. } => "MessageSent",
+            ProcessEvent::MessageReceived { .. } => "MessageReceived",
+            ProcessEvent::LocalMessageSent { .. } => "LocalMessageSent",
+            ProcessEvent::LocalMessageReceived { .. } => "LocalMessageReceived",
+            ProcessEvent::TimerSet { .. } => "TimerSet",
+            ProcessEvent::TimerFired { .. } => "TimerFired",
+            ProcessEvent::TimerCancelled { .. } => "TimerCancelled",
+            ProcessEvent::UserEvent { .. } => "UserEvent",
+            ProcessEvent::MetricUpdated { .. } => "MetricUpdated",
+            ProcessEvent::CallStarted { .. } => "CallStarted",
+        }
+    }
+
+    fn event_time(&self) -> Option<f64> {
+        Some(self.time)
+    }
+
+    fn event_msg(&self) -> Option<&Message> {
+        match &self.event {
+            ProcessEvent::MessageSent { msg, .. }
+            | ProcessEvent::MessageReceived { msg, .. }
+            | ProcessEvent::LocalMessageSent { msg }
+            | ProcessEvent::LocalMessageReceived { msg }
+            | ProcessEvent::CallStarted { msg, .. } => Some(msg),
+            _ => None,
+        }
+    }
+
+    fn event_src(&self) -> Option<&str> {
+        match &self.event {
+            ProcessEvent::MessageSent { src, .. } | ProcessEvent::MessageReceived { src, .. } => Some(src),
+            _ => None,
+        }
+    }
+
+    fn event_dst(&self) -> Option<&str> {
+        match &self.event {
+            ProcessEvent::MessageSent { dst, .. }
+            | ProcessEvent::MessageReceived { dst, .. }
+            | ProcessEvent::CallStarted { dst, .. } => Some(dst),
+            _ => None,
+        }
+    }
+
+    fn event_name(&self) -> Option<&str> {
+        match &self.event {
+            ProcessEvent::TimerSet { name, .. }
+            | ProcessEvent::TimerFired { name }
+            | ProcessEvent::TimerCancelled { name }
+            | ProcessEvent::MetricUpdated { name, .. } => Some(name),
+            ProcessEvent::UserEvent { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let details = match &self.event {
+            ProcessEvent::MessageSent { msg, src, dst, .. } => format!("{src} --> {dst} {msg:?}"),
+            ProcessEvent::MessageReceived { msg, src, dst, .. } => format!("{dst} <-- {src} {msg:?}"),
+            ProcessEvent::LocalMessageSent { msg } | ProcessEvent::LocalMessageReceived { msg } => format!("{msg:?}"),
+            ProcessEvent::TimerSet { name, delay, .. } => format!("{name} (delay {delay})"),
+            ProcessEvent::TimerFired { name } | ProcessEvent::TimerCancelled { name } => name.clone(),
+            ProcessEvent::UserEvent { kind, data } => format!("{kind} {data}"),
+            ProcessEvent::MetricUpdated { name, update } => format!("{name} {update:?}"),
+            ProcessEvent::CallStarted { call_id, dst, msg } => format!("#{call_id} --> {dst} {msg:?}"),
+        };
+        format!("{:>9.3} {} {}", self.time, self.event_kind(), details)
+    }
+}
+
+impl MatchableEvent for LogEntry {
+    fn event_kind(&self) -> &str {
+        let kind = self.kind();
+        kind.strip_prefix("Mc").unwrap_or(kind)
+    }
+
+    fn event_time(&self) -> Option<f64> {
+        self.time()
+    }
+
+    fn event_msg(&self) -> Option<&Message> {
+        self.msg()
+    }
+
+    fn event_src(&self) -> Option<&str> {
+        self.procs().first().copied()
+    }
+
+    fn event_dst(&self) -> Option<&str> {
+        self.procs().last().copied()
+    }
+
+    fn event_name(&self) -> Option<&str> {
+        match self {
+            LogEntry::TimerSet { timer_name, .. }
+            | LogEntry::TimerFired { timer_name, .. }
+            | LogEntry::TimerCancelled { timer_name, .. } => Some(timer_name),
+            LogEntry::McTimerSet { timer, .. }
+            | LogEntry::McTimerFired { timer, .. }
+            | LogEntry::McTimerCancelled { timer, .. } => Some(timer),
+            LogEntry::UserEvent { kind, .. } | LogEntry::McUserEvent { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.console_line(false) {
+            Some(line) => line.trim().to_string(),
+            None => format!("{} {}", self.kind(), self.procs().join(" ")),
+        }
+    }
+}
+
+/// Condition on the JSON data of a message.
+#[derive(Clone)]
+enum DataCondition {
+    Field { path: String, value: Value },
+    Predicate(Rc<dyn Fn(&Value) -> bool>),
+}
+
+/// Matches log entries by kind, message, involved processes, name and time.
+///
+/// All specified conditions must hold for the entry to match:
+///
+/// ```ignore
+/// let ping = EventMatcher::message_sent().tip("PING").to("server").field("attempt", json!(1)).before(5.);
+/// ```
+#[derive(Clone, Default)]
+pub struct EventMatcher {
+    kinds: Vec<String>,
+    tip: Option<String>,
+    src: Option<String>,
+    dst: Option<String>,
+    name: Option<String>,
+    data: Vec<DataCondition>,
+    time_from: Option<f64>,
+    time_to: Option<f64>,
+}
+
+impl EventMatcher {
+    /// Creates a matcher for entries of any kind.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Creates a matcher for entries of the specified kind, e.g. `UserEvent`.
+    pub fn kind(kind: &str) -> Self {
+        Self::any().or_kind(kind)
+    }
+
+    /// Creates a matcher for sent messages.
+    pub fn message_sent() -> Self {
+        Self::kind("MessageSent")
+    }
+
+    /// Creates a matcher for received messages.
+    pub fn message_received() -> Self {
+        Self::kind("MessageReceived")
+    }
+
+    /// Creates a matcher for local messages sent by the process.
+    pub fn local_message_sent() -> Self {
+        Self::kind("LocalMessageSent")
+    }
+
+    /// Creates a matcher for local messages received by the process.
+    pub fn local_message_received() -> Self {
+        Self::kind("LocalMessageReceived")
+    }
+
+    /// Creates a matcher for set timers.
+    pub fn timer_set() -> Self {
+        Self::kind("TimerSet")
+    }
+
+    /// Creates a matcher for fired timers.
+    pub fn timer_fired() -> Self {
+        Self::kind("TimerFired")
+    }
+
+    /// Creates a matcher for user events recorded via [`Context::log`](crate::Context::log).
+    pub fn user_event() -> Self {
+        Self::kind("UserEvent")
+    }
+
+    /// Allows the entry to also be of the specified kind.
+    pub fn or_kind(mut self, kind: &str) -> Self {
+        self.kinds.push(kind.to_string());
+        self
+    }
+
+    /// Requires the message type.
+    pub fn tip(mut self, tip: &str) -> Self {
+        self.tip = Some(tip.to_string());
+        self
+    }
+
+    /// Requires the message sender or the process where the event happened.
+    pub fn from(mut self, proc: &str) -> Self {
+        self.src = Some(proc.to_string());
+        self
+    }
+
+    /// Requires the message receiver or the process where the event happened.
+    pub fn to(mut self, proc: &str) -> Self {
+        self.dst = Some(proc.to_string());
+        self
+    }
+
+    /// Requires the timer name, the user event kind or the metric name.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Requires the field of the message data parsed as JSON to be equal to the value.
+    ///
+    /// The field is specified by a JSON pointer (`/key/0`) or a dot-separated path (`key.0`).
+    pub fn field(mut self, path: &str, value: Value) -> Self {
+        let path = if path.starts_with('/') || path.is_empty() {
+            path.to_string()
+        } else {
+            format!("/{}", path.replace('.', "/"))
+        };
+        self.data.push(DataCondition::Field { path, value });
+        self
+    }
+
+    /// Requires the message data parsed as JSON to satisfy the predicate.
+    pub fn data_matches(mut self, predicate: impl Fn(&Value) -> bool + 'static) -> Self {
+        self.data.push(DataCondition::Predicate(Rc::new(predicate)));
+        self
+    }
+
+    /// Requires the event to happen in the time window `[from, to]`.
+    ///
+    /// Entries without time, e.g. model checking entries, do not match time conditions.
+    pub fn between(mut self, from: f64, to: f64) -> Self {
+        self.time_from = Some(from);
+        self.time_to = Some(to);
+        self
+    }
+
+    /// Requires the event to happen not later than the specified time.
+    pub fn before(mut self, time: f64) -> Self {
+        self.time_to = Some(time);
+        self
+    }
+
+    /// Requires the event to happen not earlier than the specified time.
+    pub fn after(mut self, time: f64) -> Self {
+        self.time_from = Some(time);
+        self
+    }
+
+    /// Checks if the entry matches the kind of the matcher ignoring other conditions.
+    fn matches_kind<E: MatchableEvent>(&self, entry: &E) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|kind| kind == entry.event_kind())
+    }
+
+    /// Checks if the entry matches all conditions.
+    pub fn matches<E: MatchableEvent>(&self, entry: &E) -> bool {
+        if !self.matches_kind(entry)
+            || !matches_opt(&self.src, entry.event_src())
+            || !matches_opt(&self.dst, entry.event_dst())
+            || !matches_opt(&self.name, entry.event_name())
+        {
+            return false;
+        }
+        if self.time_from.is_some() || self.time_to.is_some() {
+            let Some(time) = entry.event_time() else {
+                return false;
+            };
+            if self.time_from.is_some_and(|from| time < from) || self.time_to.is_some_and(|to| time > to) {
+                return false;
+            }
+        }
+        if self.tip.is_some() || !self.data.is_empty() {
+            let Some(msg) = entry.event_msg() else {
+                return false;
+            };
+            if !matches_opt(&self.tip, Some(&msg.tip)) {
+                return false;
+            }
+            if !self.data.is_empty() {
+                let Ok(data) = serde_json::from_str::<Value>(&msg.data) else {
+                    return false;
+                };
+                return self.data.iter().all(|condition| match condition {
+                    DataCondition::Field { path, value } => data.pointer(path) == Some(value),
+                    DataCondition::Predicate(predicate) => predicate(&data),
+                });
+            }
+        }
+        true
+    }
+}
+
+fn matches_opt(expected: &Option<String>, actual: Option<&str>) -> bool {
+    expected.as_deref().is_none_or(|expected| actual == Some(expected))
+}
+
+impl Display for EventMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.kinds.is_empty() {
+            write!(f, "any event")?;
+        } else {
+            write!(f, "{}", self.kinds.join(" or "))?;
+        }
+        if let Some(tip) = &self.tip {
+            write!(f, " {tip}")?;
+        }
+        if let Some(name) = &self.name {
+            write!(f, " named '{name}'")?;
+        }
+        if let Some(src) = &self.src {
+            write!(f, " from {src}")?;
+        }
+        if let Some(dst) = &self.dst {
+            write!(f, " to {dst}")?;
+        }
+        for condition in &self.data {
+            match condition {
+                DataCondition::Field { path, value } => write!(f, " with {path} = {value}")?,
+                DataCondition::Predicate(_) => write!(f, " with data matching predicate")?,
+            }
+        }
+        match (self.time_from, self.time_to) {
+            (Some(from), Some(to)) => write!(f, " in [{from:.3}, {to:.3}]"),
+            (Some(from), None) => write!(f, " after {from:.3}"),
+            (None, Some(to)) => write!(f, " before {to:.3}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl Debug for EventMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EventMatcher({self})")
+    }
+}
+
+/// Failure of an assertion over a log with the relevant excerpt of the log.
+#[derive(Clone, PartialEq)]
+pub struct LogAssertionError {
+    /// Description of the failure.
+    pub message: String,
+    /// Relevant log entries with their indices, the entries which caused the failure are marked with `>`.
+    pub excerpt: Vec<String>,
+}
+
+impl Display for LogAssertionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        for line in &self.excerpt {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+// Prints the excerpt on separate lines when the result is unwrapped
+impl Debug for LogAssertionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for LogAssertionError {}
+
+impl From<LogAssertionError> for String {
+    fn from(err: LogAssertionError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Assertions over a sequence of log entries, e.g. a process event log returned by
+/// [`System::event_log`](crate::System::event_log) or a trace returned by [`Logger::trace`](crate::logger::Logger::trace).
+///
+/// ```ignore
+/// let log = sys.event_log("client");
+/// let log = EventLog::new(&log);
+/// log.assert_before(&EventMatcher::message_sent().tip("PUT"), &EventMatcher::local_message_sent().tip("PUT_OK"))?;
+/// log.assert_absent(&EventMatcher::message_sent().to("replica3"))?;
+/// ```
+pub struct EventLog<'a, E> {
+    entries: &'a [E],
+}
+
+impl<'a, E: MatchableEvent> EventLog<'a, E> {
+    /// Creates assertions over the entries.
+    pub fn new(entries: &'a [E]) -> Self {
+        Self { entries }
+    }
+
+    /// Returns the first matching entry.
+    pub fn find(&self, matcher: &EventMatcher) -> Option<&'a E> {
+        self.position(matcher, 0).map(|idx| &self.entries[idx])
+    }
+
+    /// Returns all matching entries.
+    pub fn find_all(&self, matcher: &EventMatcher) -> Vec<&'a E> {
+        self.entries.iter().filter(|entry| matcher.matches(*entry)).collect()
+    }
+
+    /// Returns the number of matching entries.
+    pub fn count(&self, matcher: &EventMatcher) -> usize {
+        self.entries.iter().filter(|entry| matcher.matches(*entry)).count()
+    }
+
+    /// Checks that the log contains a matching entry.
+    pub fn assert_contains(&self, matcher: &EventMatcher) -> Result<&'a E, LogAssertionError> {
+        self.find(matcher)
+            .ok_or_else(|| self.not_found_error(matcher, 0, format!("expected {matcher}")))
+    }
+
+    /// Checks that the log does not contain matching entries.
+    pub fn assert_absent(&self, matcher: &EventMatcher) -> Result<(), LogAssertionError> {
+        match self.position(matcher, 0) {
+            None => Ok(()),
+            Some(idx) => Err(LogAssertionError {
+                message: format!("unexpected {matcher} at #{idx}"),
+                excerpt: self.context_excerpt(&[idx]),
+            }),
+        }
+    }
+
+    /// Checks that the log contains exactly `count` matching entries.
+    pub fn assert_count(&self, matcher: &EventMatcher, count: usize) -> Result<(), LogAssertionError> {
+        let matched: Vec<_> = (0..self.entries.len())
+            .filter(|idx| matcher.matches(&self.entries[*idx]))
+            .collect();
+        if matched.len() == count {
+            return Ok(());
+        }
+        Err(LogAssertionError {
+            message: format!("expected {count} of {matcher}, found {}", matched.len()),
+            excerpt: self.excerpt(&matched, &matched),
+        })
+    }
+
+    /// Checks that the first entry matching `first` precedes the first entry matching `then`.
+    ///
+    /// Unlike [`Self::assert_order`], the check fails if some entry matching `then` precedes the first entry matching
+    /// `first`, even if it is also matched by a later entry.
+    pub fn assert_before(&self, first: &EventMatcher, then: &EventMatcher) -> Result<(), LogAssertionError> {
+        let Some(first_idx) = self.position(first, 0) else {
+            return Err(self.not_found_error(first, 0, format!("expected {first}")));
+        };
+        match self.position(then, 0) {
+            Some(then_idx) if then_idx > first_idx => Ok(()),
+            Some(then_idx) => Err(LogAssertionError {
+                message: format!("expected {then} after {first} at #{first_idx}, found first at #{then_idx}"),
+                excerpt: self.context_excerpt(&[then_idx, first_idx]),
+            }),
+            None => Err(self.not_found_error(
+                then,
+                first_idx + 1,
+                format!("expected {then} after {first} at #{first_idx}"),
+            )),
+        }
+    }
+
+    /// Checks that the log contains the entries matching the matchers in the specified order,
+    /// possibly with other entries between them.
+    ///
+    /// Each matcher is applied to the entries following the entry matched by the previous matcher.
+    pub fn assert_order(&self, matchers: &[&EventMatcher]) -> Result<(), LogAssertionError> {
+        let mut matched = Vec::new();
+        for (i, matcher) in matchers.iter().enumerate() {
+            let start = matched.last().map_or(0, |idx| idx + 1);
+            match self.position(matcher, start) {
+                Some(idx) => matched.push(idx),
+                None => {
+                    let message = match matched.last() {
+                        Some(prev) => format!("expected {matcher} after {} at #{prev}", matchers[i - 1]),
+                        None => format!("expected {matcher}"),
+                    };
+                    let mut err = self.not_found_error(matcher, start, message);
+                    // the matching entry precedes the required one
+                    if let Some(idx) = self.position(matcher, 0) {
+                        err.message += &format!(", found only at #{idx}");
+                        let mut relevant = vec![idx];
+                        relevant.extend(matched.last());
+                        err.excerpt = self.context_excerpt(&relevant);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self, matcher: &EventMatcher, start: usize) -> Option<usize> {
+        (start..self.entries.len()).find(|idx| matcher.matches(&self.entries[*idx]))
+    }
+
+    /// Builds an error for the missing entry, showing the entries of the same kind or the log tail.
+    fn not_found_error(&self, matcher: &EventMatcher, start: usize, message: String) -> LogAssertionError {
+        let same_kind: Vec<_> = (start..self.entries.len())
+            .filter(|idx| matcher.matches_kind(&self.entries[*idx]))
+            .collect();
+        let (message, shown) = if matcher.kinds.is_empty() || same_kind.is_empty() {
+            let tail = self.entries.len().saturating_sub(EXCERPT_LIMIT).max(start);
+            (
+                format!("{message}, last entries:"),
+                (tail..self.entries.len()).collect(),
+            )
+        } else {
+            (format!("{message}, entries of the same kind:"), same_kind)
+        };
+        LogAssertionError {
+            message,
+            excerpt: self.excerpt(&shown, &[]),
+        }
+    }
+
+    /// Returns the excerpt with the marked entries and their neighbours.
+    fn context_excerpt(&self, marked: &[usize]) -> Vec<String> {
+        let mut shown: Vec<usize> = marked
+            .iter()
+            .flat_map(|idx| idx.saturating_sub(EXCERPT_CONTEXT)..(idx + EXCERPT_CONTEXT + 1).min(self.entries.len()))
+            .collect();
+        shown.sort_unstable();
+        shown.dedup();
+        self.excerpt(&shown, marked)
+    }
+
+    fn excerpt(&self, shown: &[usize], marked: &[usize]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut prev = None;
+        for &idx in shown.iter().take(EXCERPT_LIMIT) {
+            if prev.is_some_and(|prev| idx > prev + 1) {
+                lines.push("  ...".to_string());
+            }
+            let marker = if marked.contains(&idx) { '>' } else { ' ' };
+            lines.push(format!("{marker} #{idx:<4} {}", self.entries[idx].describe()));
+            prev = Some(idx);
+        }
+        if shown.len() > EXCERPT_LIMIT {
+            lines.push(format!("  ... and {} more", shown.len() - EXCERPT_LIMIT));
+        }
+        lines
+    }
+}
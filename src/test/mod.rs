@@ -0,0 +1,449 @@
+//! Testing facilities.
+
+pub(crate) mod guard;
+mod matchers;
+mod property;
+mod report;
+mod seeds;
+
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::error::panic_message;
+use crate::hooks::Interrupted;
+pub use matchers::{EventLog, EventMatcher, LogAssertionError, MatchableEvent};
+pub use property::{
+    FaultKind, PropertyFailure, PropertyTest, Schedule, ScheduleConfig, ScheduleEntry, ScheduledAction,
+};
+pub use report::{CaseOutcome, TestOutcome, TestReport};
+pub use seeds::{SeedReport, SeedRunner};
+
+/// A test result.
+pub type TestResult = Result<bool, String>;
+
+type SetupFn<T> = fn(&T) -> Result<(), String>;
+
+/// Criterion for passing a parameterized test depending on the results of its cases.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PassCriterion {
+    /// All cases must pass.
+    All,
+    /// The fraction of passed cases must be at least the specified value, e.g. 0.95.
+    MinPassRate(f64),
+    /// The number of passed cases must be at least the specified value.
+    MinPassed(usize),
+}
+
+impl PassCriterion {
+    fn check(&self, passed: usize, total: usize) -> Result<(), String> {
+        let ok = match self {
+            PassCriterion::All => passed == total,
+            PassCriterion::MinPassRate(rate) => passed as f64 >= rate * total as f64,
+            PassCriterion::MinPassed(count) => passed >= *count,
+        };
+        if ok {
+            return Ok(());
+        }
+        match self {
+            PassCriterion::All => Err(format!("failed on {} of {total} cases", total - passed)),
+            PassCriterion::MinPassRate(rate) => Err(format!(
+                "passed on {:.1}% of {total} cases, required {:.1}%",
+                100. * passed as f64 / total as f64,
+                100. * rate
+            )),
+            PassCriterion::MinPassed(count) => Err(format!("passed on {passed} of {total} cases, required {count}")),
+        }
+    }
+}
+
+/// Test function applied to the test config, possibly with some parameters.
+enum Case<T> {
+    Plain(fn(&T) -> TestResult),
+    Parameterized {
+        params: String,
+        func: Box<dyn Fn(&T) -> TestResult>,
+    },
+}
+
+impl<T> Case<T> {
+    fn params(&self) -> Option<&str> {
+        match self {
+            Case::Plain(_) => None,
+            Case::Parameterized { params, .. } => Some(params),
+        }
+    }
+
+    fn run(&self, config: &T) -> TestResult {
+        match self {
+            Case::Plain(func) => func(config),
+            Case::Parameterized { func, .. } => func(config),
+        }
+    }
+}
+
+/// A test registered in a test suite.
+///
+/// A parameterized test consists of multiple cases, one for each parameter value.
+pub struct Test<T> {
+    name: String,
+    cases: Vec<Case<T>>,
+    config: T,
+    tags: Vec<String>,
+    step_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    criterion: PassCriterion,
+}
+
+impl<T> Test<T> {
+    /// Returns the test name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds a tag to the test.
+    pub fn tag(&mut self, tag: &str) -> &mut Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Sets the maximum number of simulation steps made by the test, overriding the suite limit.
+    pub fn step_limit(&mut self, steps: u64) -> &mut Self {
+        self.step_limit = Some(steps);
+        self
+    }
+
+    /// Sets the maximum wall-clock duration of the test, overriding the suite limit.
+    ///
+    /// For parameterized tests the limits are applied to each case.
+    pub fn time_limit(&mut self, limit: Duration) -> &mut Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Sets the criterion for passing a parameterized test ([`PassCriterion::All`] by default).
+    pub fn pass_criterion(&mut self, criterion: PassCriterion) -> &mut Self {
+        if let PassCriterion::MinPassRate(rate) = criterion {
+            assert!((0. ..=1.).contains(&rate), "Pass rate must be in [0, 1]");
+        }
+        self.criterion = criterion;
+        self
+    }
+}
+
+/// A set of tests supposed to be run together.
+///
+/// Tests can be selected by name patterns and tags, limited in the number of simulation steps and wall-clock time,
+/// and surrounded by setup and teardown hooks. The results are returned as a [`TestReport`], which can be written in
+/// JSON or JUnit XML format.
+///
+/// The limits are checked on each event handled by the nodes of systems created in the test thread. A test exceeding
+/// its limits is interrupted and reported as failed, as well as a test which panics.
+pub struct TestSuite<T> {
+    name: String,
+    tests: Vec<Test<T>>,
+    filter: Option<Regex>,
+    tags: Vec<String>,
+    step_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    setup: Option<SetupFn<T>>,
+    teardown: Option<fn(&T)>,
+    capture_output: bool,
+}
+
+impl<T> TestSuite<T> {
+    /// Creates an empty test suite.
+    pub fn new() -> Self {
+        Self {
+            name: "tests".to_string(),
+            tests: Vec::new(),
+            filter: None,
+            tags: Vec::new(),
+            step_limit: None,
+            time_limit: None,
+            setup: None,
+            teardown: None,
+            capture_output: false,
+        }
+    }
+
+    /// Adds a test to the suite.
+    ///
+    /// Returns a reference to the added test, which can be used to set its tags and limits.
+    pub fn add(&mut self, name: &str, f: fn(&T) -> TestResult, config: T) -> &mut Test<T> {
+        self.push(name, vec![Case::Plain(f)], config)
+    }
+
+    /// Adds a parameterized test to the suite, which runs the test function with each parameter value.
+    ///
+    /// The parameters can be any values, e.g. seeds or tuples of network settings and cluster sizes. The results are
+    /// reported for each parameter value formatted with [`Debug`]. By default, the test passes if all cases pass,
+    /// use [`Test::pass_criterion`] to check probabilistic properties.
+    pub fn add_parameterized<P, I>(
+        &mut self,
+        name: &str,
+        f: fn(&T, &P) -> TestResult,
+        config: T,
+        params: I,
+    ) -> &mut Test<T>
+    where
+        T: 'static,
+        P: Debug + 'static,
+        I: IntoIterator<Item = P>,
+    {
+        let cases: Vec<Case<T>> = params
+            .into_iter()
+            .map(|p| Case::Parameterized {
+                params: format!("{p:?}"),
+                func: Box::new(move |config| f(config, &p)),
+            })
+            .collect();
+        assert!(
+            !cases.is_empty(),
+            "Parameterized test must have at least one parameter value"
+        );
+        self.push(name, cases, config)
+    }
+
+    fn push(&mut self, name: &str, cases: Vec<Case<T>>, config: T) -> &mut Test<T> {
+        self.tests.push(Test {
+            name: name.to_string(),
+            cases,
+            config,
+            tags: Vec::new(),
+            step_limit: None,
+            time_limit: None,
+            criterion: PassCriterion::All,
+        });
+        self.tests.last_mut().unwrap()
+    }
+
+    /// Sets the suite name used in reports.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Selects the tests with names matching the glob pattern, where `*` matches any sequence of characters
+    /// and `?` matches any single character.
+    pub fn set_filter(&mut self, pattern: &str) {
+        self.filter = Some(glob_regex(pattern));
+    }
+
+    /// Selects the tests having any of the specified tags.
+    pub fn set_tags<I, S>(&mut self, tags: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(|t| t.into()).collect();
+    }
+
+    /// Sets the default maximum number of simulation steps made by each test.
+    pub fn set_step_limit(&mut self, steps: u64) {
+        self.step_limit = Some(steps);
+    }
+
+    /// Sets the default maximum wall-clock duration of each test.
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.time_limit = Some(limit);
+    }
+
+    /// Sets the function called before each test, the test fails without running if the function returns error.
+    pub fn set_setup(&mut self, f: SetupFn<T>) {
+        self.setup = Some(f);
+    }
+
+    /// Sets the function called after each test, including failed ones.
+    pub fn set_teardown(&mut self, f: fn(&T)) {
+        self.teardown = Some(f);
+    }
+
+    /// Enables capturing of simulation log output, i.e. events printed by the system loggers, during each test.
+    ///
+    /// The captured output is stored in the test outcome and printed only if the test fails.
+    /// Other output of the test, e.g. printed with `println!`, is not captured.
+    pub fn set_capture_output(&mut self, enabled: bool) {
+        self.capture_output = enabled;
+    }
+
+    fn is_selected(&self, test: &Test<T>) -> bool {
+        self.filter.as_ref().is_none_or(|re| re.is_match(&test.name))
+            && (self.tags.is_empty() || test.tags.iter().any(|t| self.tags.contains(t)))
+    }
+
+    fn execute(&self, test: &Test<T>) -> TestOutcome {
+        let mut cases: Vec<CaseOutcome> = test.cases.iter().map(|case| self.execute_case(test, case)).collect();
+        if test.cases[0].params().is_none() {
+            let case = cases.pop().unwrap();
+            return TestOutcome {
+                name: test.name.clone(),
+                tags: test.tags.clone(),
+                passed: case.passed,
+                error: case.error,
+                duration: case.duration,
+                steps: case.steps,
+                output: case.output,
+                cases: Vec::new(),
+                result: case.result,
+            };
+        }
+
+        let passed = cases.iter().filter(|c| c.passed).count();
+        let result = test.criterion.check(passed, cases.len()).map(|_| true).map_err(|e| {
+            let failures: Vec<String> = cases
+                .iter()
+                .filter_map(|c| c.error.as_ref().map(|error| format!("- {}: {error}", c.params)))
+                .collect();
+            format!("{e}\n{}", failures.join("\n"))
+        });
+        match &result {
+            Ok(_) => println!("=== {}: PASSED on {passed} of {} cases ===\n", test.name, cases.len()),
+            Err(e) => println!("=== {}: FAILED {e}\n", test.name),
+        }
+        TestOutcome {
+            name: test.name.clone(),
+            tags: test.tags.clone(),
+            passed: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            duration: cases.iter().map(|c| c.duration).sum(),
+            steps: cases.iter().map(|c| c.steps).sum(),
+            output: cases
+                .iter()
+                .filter(|c| !c.output.is_empty())
+                .map(|c| format!("--- {} ---\n{}", c.params, c.output))
+                .collect(),
+            cases,
+            result,
+        }
+    }
+
+    fn execute_case(&self, test: &Test<T>, case: &Case<T>) -> CaseOutcome {
+        match case.params() {
+            Some(params) => println!("\n--- {} [{params}] ---\n", test.name),
+            None => println!("\n--- {} ---\n", test.name),
+        }
+        guard::install(
+            test.step_limit.or(self.step_limit),
+            test.time_limit.or(self.time_limit),
+            self.capture_output,
+        );
+        let start = Instant::now();
+        let mut result = run_guarded(|| {
+            if let Some(setup) = self.setup {
+                setup(&test.config).map_err(|e| format!("setup failed: {e}"))?;
+            }
+            case.run(&test.config)
+        });
+        if let Some(teardown) = self.teardown {
+            if let Err(e) = run_guarded(|| {
+                teardown(&test.config);
+                Ok(true)
+            }) {
+                result = result.and(Err(format!("teardown failed: {e}")));
+            }
+        }
+        let duration = start.elapsed().as_secs_f64();
+        let (steps, output) = guard::uninstall();
+
+        match &result {
+            Ok(_) => println!("\nPASSED\n"),
+            Err(e) => {
+                if !output.is_empty() {
+                    print!("{output}");
+                }
+                println!("\nFAILED: {e}\n");
+            }
+        }
+        CaseOutcome {
+            params: case.params().unwrap_or_default().to_string(),
+            passed: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            duration,
+            steps,
+            output,
+            result,
+        }
+    }
+
+    /// Executes the selected tests in turn and returns the report.
+    ///
+    /// Collects and prints the result of each test, and prints the summary in the end.
+    pub fn run_report(&mut self) -> TestReport {
+        let start = Instant::now();
+        let tests: Vec<TestOutcome> = self
+            .tests
+            .iter()
+            .filter(|test| self.is_selected(test))
+            .map(|test| self.execute(test))
+            .collect();
+        let report = TestReport {
+            name: self.name.clone(),
+            tests,
+            duration: start.elapsed().as_secs_f64(),
+        };
+
+        println!("-------------------------------------------------------------------------------");
+        println!("\nPassed {} from {} tests\n", report.passed_count(), report.tests.len());
+        if !report.all_passed() {
+            println!("Failed tests:");
+            for test in &report.tests {
+                if let Some(e) = &test.error {
+                    println!("- {}: {e}", test.name)
+                }
+            }
+            println!();
+        }
+        report
+    }
+
+    /// Executes the selected tests by running each test in turn.
+    ///
+    /// Collects and prints the result of each test, and prints the summary in the end.
+    /// Returns whether all tests are passed and results for each test.
+    pub fn run(&mut self) -> (bool, IndexMap<String, TestResult>) {
+        let report = self.run_report();
+        let all_passed = report.all_passed();
+        let results = report.tests.into_iter().map(|t| (t.name, t.result)).collect();
+        (all_passed, results)
+    }
+
+    /// Runs the tests with names matching the specified name or glob pattern and prints their results.
+    pub fn run_test(&mut self, name: &str) {
+        let re = glob_regex(name);
+        for test in &self.tests {
+            if test.name == name || re.is_match(&test.name) {
+                self.execute(test);
+            }
+        }
+    }
+}
+
+impl<T> Default for TestSuite<T> {
+    fn default() -> Self {
+        TestSuite::new()
+    }
+}
+
+/// Runs the function converting panics and exceeded limits into errors.
+pub(crate) fn run_guarded<F: FnOnce() -> TestResult>(f: F) -> TestResult {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            if let Some(Interrupted(message)) = payload.downcast_ref::<Interrupted>() {
+                Err(message.clone())
+            } else if let Some(message) = panic_message(&*payload) {
+                Err(format!("panicked: {message}"))
+            } else {
+                Err("panicked".to_string())
+            }
+        }
+    }
+}
+
+fn glob_regex(pattern: &str) -> Regex {
+    let re = regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", ".");
+    Regex::new(&format!("^{re}$")).unwrap()
+}
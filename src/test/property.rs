@@ -0,0 +1,673 @@
+//! Randomized testing with explicit fault schedules and shrinking of failing schedules.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::error::StepError;
+use crate::logger::LogEntry;
+use crate::test::TestResult;
+use crate::{Message, System};
+
+/// Action applied to the system at some time of a scheduled run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduledAction {
+    /// Sends a local message to the process.
+    LocalMessage {
+        /// Process name.
+        proc: String,
+        /// Sent message.
+        msg: Message,
+    },
+    /// Crashes the node (the node is not recovered).
+    CrashNode {
+        /// Node name.
+        node: String,
+    },
+    /// Disconnects the node from the network for the specified time.
+    DisconnectNode {
+        /// Node name.
+        node: String,
+        /// Time until the node is connected back.
+        duration: f64,
+    },
+    /// Creates a network partition between two groups of nodes for the specified time.
+    Partition {
+        /// First group of nodes.
+        group1: Vec<String>,
+        /// Second group of nodes.
+        group2: Vec<String>,
+        /// Time until the partition is healed.
+        duration: f64,
+    },
+    /// Disables the link between two nodes in one direction for the specified time.
+    DisableLink {
+        /// Source node.
+        from: String,
+        /// Destination node.
+        to: String,
+        /// Time until the link is enabled back.
+        duration: f64,
+    },
+}
+
+impl ScheduledAction {
+    /// Returns the fault duration or `None` for actions without duration.
+    pub fn duration(&self) -> Option<f64> {
+        match self {
+            ScheduledAction::DisconnectNode { duration, .. }
+            | ScheduledAction::Partition { duration, .. }
+            | ScheduledAction::DisableLink { duration, .. } => Some(*duration),
+            _ => None,
+        }
+    }
+
+    fn set_duration(&mut self, value: f64) {
+        match self {
+            ScheduledAction::DisconnectNode { duration, .. }
+            | ScheduledAction::Partition { duration, .. }
+            | ScheduledAction::DisableLink { duration, .. } => *duration = value,
+            _ => {}
+        }
+    }
+}
+
+impl Display for ScheduledAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduledAction::LocalMessage { proc, msg } => write!(f, "local message {msg:?} to {proc}"),
+            ScheduledAction::CrashNode { node } => write!(f, "crash node {node}"),
+            ScheduledAction::DisconnectNode { node, duration } => {
+                write!(f, "disconnect node {node} for {duration:.3}")
+            }
+            ScheduledAction::Partition {
+                group1,
+                group2,
+                duration,
+            } => write!(f, "partition {group1:?} / {group2:?} for {duration:.3}"),
+            ScheduledAction::DisableLink { from, to, duration } => {
+                write!(f, "disable link {from} -> {to} for {duration:.3}")
+            }
+        }
+    }
+}
+
+/// Action scheduled at some time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleEntry {
+    /// Time since the run start.
+    pub time: f64,
+    /// Applied action.
+    pub action: ScheduledAction,
+}
+
+/// Explicit schedule of faults and local messages applied during a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    /// Scheduled actions sorted by time.
+    pub entries: Vec<ScheduleEntry>,
+    /// Run duration.
+    pub duration: f64,
+}
+
+impl Schedule {
+    /// Applies the schedule to the system by stepping through the simulation and applying the actions at their
+    /// times relative to the current time, then steps until the end of the run.
+    ///
+    /// Faults with duration are reverted after this duration. Overlapping faults affecting the same node or link are
+    /// accounted for, so that the node or link is restored only after all of them are over.
+    ///
+    /// The run is stopped on the first process error or invariant violation, which is returned.
+    pub fn run(&self, sys: &mut System) -> Result<(), Box<StepError>> {
+        let mut executor = Executor::new(sys);
+        for entry in &self.entries {
+            executor.advance(sys, entry.time);
+            executor.apply(sys, entry.time, &entry.action);
+        }
+        executor.finish(sys, self.duration)
+    }
+
+    fn without(&self, from: usize, to: usize) -> Self {
+        let mut schedule = self.clone();
+        schedule.entries.drain(from..to);
+        schedule
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Schedule of {} actions for {:.3}:",
+            self.entries.len(),
+            self.duration
+        )?;
+        for entry in &self.entries {
+            write!(f, "\n{:>10.3}  {}", entry.time, entry.action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies scheduled actions to the system and reverts the faults after their durations.
+///
+/// After the first process error or invariant violation, the executor stops stepping and applying actions.
+struct Executor {
+    start: f64,
+    /// Active faults with their end times since the start in the order of application.
+    ends: Vec<(f64, ScheduledAction)>,
+    disconnected: HashMap<String, u32>,
+    disabled: HashMap<(String, String), u32>,
+    error: Option<Box<StepError>>,
+}
+
+impl Executor {
+    fn new(sys: &System) -> Self {
+        Self {
+            start: sys.time(),
+            ends: Vec::new(),
+            disconnected: HashMap::new(),
+            disabled: HashMap::new(),
+            error: None,
+        }
+    }
+
+    /// Steps the simulation until the specified time since the start, reverting the faults which end before it.
+    ///
+    /// Faults ending at some time are reverted before the actions applied at the same time.
+    fn advance(&mut self, sys: &mut System, time: f64) {
+        loop {
+            let mut next: Option<usize> = None;
+            for (idx, (end, _)) in self.ends.iter().enumerate() {
+                if *end <= time && next.is_none_or(|next| *end < self.ends[next].0) {
+                    next = Some(idx);
+                }
+            }
+            let Some(idx) = next else {
+                break;
+            };
+            let (end, action) = self.ends.remove(idx);
+            self.step_to(sys, end);
+            self.revert(sys, &action);
+        }
+        self.step_to(sys, time);
+    }
+
+    /// Reverts the remaining faults and steps until the end of the run, returns the error which stopped the run.
+    fn finish(&mut self, sys: &mut System, duration: f64) -> Result<(), Box<StepError>> {
+        let last_end = self.ends.iter().map(|(end, _)| *end).fold(duration, f64::max);
+        self.advance(sys, last_end);
+        self.step_to(sys, duration);
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn step_to(&mut self, sys: &mut System, time: f64) {
+        if self.error.is_none() {
+            self.error = sys
+                .try_step_for_duration((self.start + time - sys.time()).max(0.))
+                .err();
+        }
+    }
+
+    fn apply(&mut self, sys: &mut System, time: f64, action: &ScheduledAction) {
+        if self.error.is_some() {
+            return;
+        }
+        match action {
+            ScheduledAction::LocalMessage { proc, msg } => {
+                if !sys.proc_node_is_crashed(proc) {
+                    self.error = sys.try_send_local_message(proc, msg.clone()).err();
+                }
+            }
+            ScheduledAction::CrashNode { node } => {
+                if !sys.node_is_crashed(node) {
+                    sys.crash_node(node);
+                }
+            }
+            ScheduledAction::DisconnectNode { node, .. } => {
+                *self.disconnected.entry(node.clone()).or_default() += 1;
+                sys.network().disconnect_node(node);
+            }
+            ScheduledAction::Partition { group1, group2, .. } => {
+                for (from, to) in partition_links(group1, group2) {
+                    *self.disabled.entry((from.to_string(), to.to_string())).or_default() += 1;
+                }
+                let group1: Vec<&str> = group1.iter().map(|n| n.as_str()).collect();
+                let group2: Vec<&str> = group2.iter().map(|n| n.as_str()).collect();
+                sys.network().make_partition(&group1, &group2);
+            }
+            ScheduledAction::DisableLink { from, to, .. } => {
+                *self.disabled.entry((from.clone(), to.clone())).or_default() += 1;
+                sys.network().disable_link(from, to);
+            }
+        }
+        if let Some(duration) = action.duration() {
+            self.ends.push((time + duration, action.clone()));
+        }
+    }
+
+    fn revert(&mut self, sys: &mut System, action: &ScheduledAction) {
+        match action {
+            ScheduledAction::DisconnectNode { node, .. } if release(&mut self.disconnected, node.clone()) => {
+                sys.network().connect_node(node);
+            }
+            ScheduledAction::Partition { group1, group2, .. } => {
+                for (from, to) in partition_links(group1, group2) {
+                    if release(&mut self.disabled, (from.to_string(), to.to_string())) {
+                        sys.network().enable_link(from, to);
+                    }
+                }
+            }
+            ScheduledAction::DisableLink { from, to, .. }
+                if release(&mut self.disabled, (from.clone(), to.clone())) =>
+            {
+                sys.network().enable_link(from, to);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn partition_links<'a>(group1: &'a [String], group2: &'a [String]) -> Vec<(&'a str, &'a str)> {
+    let mut links = Vec::new();
+    for n1 in group1 {
+        for n2 in group2 {
+            links.push((n1.as_str(), n2.as_str()));
+            links.push((n2.as_str(), n1.as_str()));
+        }
+    }
+    links
+}
+
+/// Decrements the fault counter, returns true if there are no more active faults.
+fn release<K: Eq + std::hash::Hash>(counts: &mut HashMap<K, u32>, key: K) -> bool {
+    let count = counts.get_mut(&key).unwrap();
+    *count -= 1;
+    if *count == 0 {
+        counts.remove(&key);
+        true
+    } else {
+        false
+    }
+}
+
+/// Kind of fault injected by generated schedules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultKind {
+    /// Node crash without recovery.
+    Crash,
+    /// Temporary node disconnection.
+    Disconnect,
+    /// Temporary partition between random groups of nodes.
+    Partition,
+    /// Temporary one-way link failure.
+    Link,
+}
+
+type MessageGenerator = Box<dyn Fn(&mut dyn RngCore) -> Message>;
+
+/// Configuration of random runs with injected faults and local messages.
+///
+/// By default, runs have duration 100 with 10 operations sent to clients and 5 temporary faults (disconnections,
+/// partitions and link failures) lasting up to 10 time units.
+pub struct ScheduleConfig {
+    nodes: Vec<String>,
+    clients: Vec<String>,
+    message: Option<MessageGenerator>,
+    operations: usize,
+    faults: usize,
+    fault_kinds: Vec<FaultKind>,
+    max_fault_duration: f64,
+    duration: f64,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            clients: Vec::new(),
+            message: None,
+            operations: 10,
+            faults: 5,
+            fault_kinds: vec![FaultKind::Disconnect, FaultKind::Partition, FaultKind::Link],
+            max_fault_duration: 10.,
+            duration: 100.,
+        }
+    }
+}
+
+impl ScheduleConfig {
+    /// Sets the names of nodes affected by faults.
+    pub fn nodes<I, S>(mut self, nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.nodes = nodes.into_iter().map(|n| n.into()).collect();
+        self
+    }
+
+    /// Sets the names of processes receiving local messages and the function generating these messages.
+    pub fn clients<I, S, F>(mut self, clients: I, message: F) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        F: Fn(&mut dyn RngCore) -> Message + 'static,
+    {
+        self.clients = clients.into_iter().map(|c| c.into()).collect();
+        self.message = Some(Box::new(message));
+        self
+    }
+
+    /// Sets the number of local messages (operations) in a schedule.
+    pub fn operations(mut self, count: usize) -> Self {
+        self.operations = count;
+        self
+    }
+
+    /// Sets the number of faults in a schedule and their kinds.
+    pub fn faults(mut self, count: usize, kinds: &[FaultKind]) -> Self {
+        self.faults = count;
+        self.fault_kinds = kinds.to_vec();
+        self
+    }
+
+    /// Sets the maximum duration of temporary faults.
+    pub fn max_fault_duration(mut self, duration: f64) -> Self {
+        assert!(duration > 0., "Fault duration must be positive");
+        self.max_fault_duration = duration;
+        self
+    }
+
+    /// Sets the run duration, all actions are applied and all faults are over within this duration.
+    pub fn duration(mut self, duration: f64) -> Self {
+        assert!(duration > 0., "Run duration must be positive");
+        self.duration = duration;
+        self
+    }
+
+    /// Runs the system injecting random local messages and faults, returns the schedule of the injected actions.
+    ///
+    /// The times of actions are drawn at the start of the run, while the actions themselves are chosen when they
+    /// are applied based on the current system state: local messages are sent only to clients on running nodes,
+    /// crashes and disconnections affect only running nodes. The random choices are made with a separate RNG
+    /// seeded with `seed`, so the system RNG is not affected. The returned schedule reproduces the run when applied
+    /// with [`Schedule::run`] to the system built in the same way.
+    ///
+    /// The run is stopped on the first process error or invariant violation, which is returned along with
+    /// the schedule of the actions injected before it.
+    pub fn run_random(&self, sys: &mut System, seed: u64) -> (Schedule, Result<(), Box<StepError>>) {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let kinds: Vec<FaultKind> = self
+            .fault_kinds
+            .iter()
+            .copied()
+            .filter(|kind| matches!(kind, FaultKind::Crash | FaultKind::Disconnect) || self.nodes.len() > 1)
+            .collect();
+        let mut slots = Vec::new();
+        if self.message.is_some() && !self.clients.is_empty() {
+            slots.extend((0..self.operations).map(|_| (rng.gen_range(0.0..self.duration), false)));
+        }
+        if !self.nodes.is_empty() && !kinds.is_empty() {
+            slots.extend((0..self.faults).map(|_| (rng.gen_range(0.0..self.duration), true)));
+        }
+        slots.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut executor = Executor::new(sys);
+        let mut entries = Vec::new();
+        for (time, is_fault) in slots {
+            executor.advance(sys, time);
+            if executor.error.is_some() {
+                break;
+            }
+            let action = if is_fault {
+                self.random_fault(sys, &kinds, time, &mut rng)
+            } else {
+                self.random_operation(sys, &mut rng)
+            };
+            if let Some(action) = action {
+                executor.apply(sys, time, &action);
+                entries.push(ScheduleEntry { time, action });
+            }
+        }
+        let result = executor.finish(sys, self.duration);
+        let schedule = Schedule {
+            entries,
+            duration: self.duration,
+        };
+        (schedule, result)
+    }
+
+    fn random_operation(&self, sys: &System, rng: &mut Pcg64) -> Option<ScheduledAction> {
+        let clients: Vec<&String> = self
+            .clients
+            .iter()
+            .filter(|client| !sys.proc_node_is_crashed(client))
+            .collect();
+        let proc = (*clients.choose(rng)?).clone();
+        let msg = (self.message.as_ref()?)(rng);
+        Some(ScheduledAction::LocalMessage { proc, msg })
+    }
+
+    fn random_fault(&self, sys: &System, kinds: &[FaultKind], time: f64, rng: &mut Pcg64) -> Option<ScheduledAction> {
+        let duration = rng.gen_range(0.0..self.max_fault_duration.min(self.duration - time));
+        let running: Vec<&String> = self.nodes.iter().filter(|node| !sys.node_is_crashed(node)).collect();
+        let action = match kinds.choose(rng).unwrap() {
+            FaultKind::Crash => ScheduledAction::CrashNode {
+                node: (*running.choose(rng)?).clone(),
+            },
+            FaultKind::Disconnect => ScheduledAction::DisconnectNode {
+                node: (*running.choose(rng)?).clone(),
+                duration,
+            },
+            FaultKind::Partition => {
+                let mut nodes = self.nodes.clone();
+                nodes.shuffle(rng);
+                let group2 = nodes.split_off(rng.gen_range(1..nodes.len()));
+                ScheduledAction::Partition {
+                    group1: nodes,
+                    group2,
+                    duration,
+                }
+            }
+            FaultKind::Link => {
+                let mut pair = self.nodes.choose_multiple(rng, 2);
+                ScheduledAction::DisableLink {
+                    from: pair.next().unwrap().clone(),
+                    to: pair.next().unwrap().clone(),
+                    duration,
+                }
+            }
+        };
+        Some(action)
+    }
+}
+
+/// Failing run found by [`PropertyTest`] with its shrunk schedule.
+#[derive(Clone, Debug)]
+pub struct PropertyFailure {
+    /// Seed of the failing run.
+    pub seed: u64,
+    /// Schedule recorded in the original run.
+    pub original: Schedule,
+    /// Minimal found schedule which still fails the check.
+    pub schedule: Schedule,
+    /// Error returned by the check for the minimal schedule.
+    pub error: String,
+    /// Trace of the run with the minimal schedule.
+    pub trace: Vec<LogEntry>,
+    /// Number of runs performed during shrinking.
+    pub shrink_runs: usize,
+}
+
+impl Display for PropertyFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Check failed for seed {}: {}", self.seed, self.error)?;
+        writeln!(
+            f,
+            "Shrunk schedule from {} to {} actions in {} runs",
+            self.original.entries.len(),
+            self.schedule.entries.len(),
+            self.shrink_runs
+        )?;
+        write!(f, "{}", self.schedule)
+    }
+}
+
+/// Property-testing driver running the system with randomly generated schedules of faults and local messages.
+///
+/// For each seed, the driver builds a system, runs it injecting random faults and local messages
+/// (see [`ScheduleConfig::run_random`]) and checks the system state. The injected actions are recorded as a schedule.
+/// If the check fails, the driver re-runs the same seed with shrunk recorded schedules (fewer faults and operations,
+/// shorter faults) until it finds a minimal schedule which still fails the check. The system RNG is seeded with
+/// the same seed in each re-run, so the runs differ only by the schedule.
+///
+/// ```ignore
+/// let config = ScheduleConfig::default()
+///     .nodes(["node1", "node2", "node3"])
+///     .clients(["client"], |rng| Message::new("PUT", &format!("{}", rng.next_u32() % 10)))
+///     .faults(10, &[FaultKind::Disconnect, FaultKind::Partition]);
+/// if let Err(failure) = PropertyTest::new(config).run(0..100, build_system, check_replicas) {
+///     println!("{failure}");
+///     failure.trace.iter().for_each(|entry| entry.print());
+/// }
+/// ```
+pub struct PropertyTest {
+    config: ScheduleConfig,
+    max_shrink_runs: usize,
+}
+
+impl PropertyTest {
+    /// Creates a driver generating schedules with the specified configuration.
+    pub fn new(config: ScheduleConfig) -> Self {
+        Self {
+            config,
+            max_shrink_runs: 1000,
+        }
+    }
+
+    /// Sets the maximum number of runs performed while shrinking a failing schedule (1000 by default).
+    pub fn max_shrink_runs(mut self, runs: usize) -> Self {
+        self.max_shrink_runs = runs;
+        self
+    }
+
+    /// Runs the check for each seed until the first failure.
+    ///
+    /// The `build` function creates the system using the given seed, the `check` function is called after the run.
+    /// Returns the shrunk failure if the check failed for some seed. A process error or an invariant violation which
+    /// stopped the run is reported as a failure without calling the check. The trace of the failing run is collected
+    /// from the system logger, so it is empty if the trace is disabled in the logger configuration.
+    pub fn run<S, B, C>(&self, seeds: S, build: B, check: C) -> Result<(), Box<PropertyFailure>>
+    where
+        S: IntoIterator<Item = u64>,
+        B: Fn(u64) -> System,
+        C: Fn(&mut System) -> TestResult,
+    {
+        for seed in seeds {
+            let mut sys = build(seed);
+            let (schedule, result) = self.config.run_random(&mut sys, seed);
+            if let Err(error) = result.map_err(|err| err.to_string()).and_then(|()| check(&mut sys)) {
+                return Err(Box::new(self.shrink(seed, schedule, error, &build, &check)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the check with the specified schedule, returns the check result and the run trace.
+    ///
+    /// If the run is stopped by a process error or an invariant violation, the error is returned as the check result.
+    pub fn replay<B, C>(seed: u64, schedule: &Schedule, build: B, check: C) -> (TestResult, Vec<LogEntry>)
+    where
+        B: Fn(u64) -> System,
+        C: Fn(&mut System) -> TestResult,
+    {
+        Self::run_schedule(seed, schedule, &build, &check)
+    }
+
+    fn run_schedule<B, C>(seed: u64, schedule: &Schedule, build: &B, check: &C) -> (TestResult, Vec<LogEntry>)
+    where
+        B: Fn(u64) -> System,
+        C: Fn(&mut System) -> TestResult,
+    {
+        let mut sys = build(seed);
+        let result = schedule
+            .run(&mut sys)
+            .map_err(|err| err.to_string())
+            .and_then(|()| check(&mut sys));
+        let trace = sys.logger().trace().to_vec();
+        (result, trace)
+    }
+
+    fn shrink<B, C>(&self, seed: u64, original: Schedule, error: String, build: &B, check: &C) -> PropertyFailure
+    where
+        B: Fn(u64) -> System,
+        C: Fn(&mut System) -> TestResult,
+    {
+        let mut schedule = original.clone();
+        let mut error = error;
+        let mut runs = 0;
+        let fails = |candidate: &Schedule| Self::run_schedule(seed, candidate, build, check).0.err();
+
+        let mut changed = true;
+        while changed && runs < self.max_shrink_runs {
+            changed = false;
+
+            // remove chunks of actions starting from large chunks
+            let mut chunk = (schedule.entries.len() / 2).max(1);
+            loop {
+                let mut start = 0;
+                while start < schedule.entries.len() && runs < self.max_shrink_runs {
+                    let end = (start + chunk).min(schedule.entries.len());
+                    let candidate = schedule.without(start, end);
+                    runs += 1;
+                    if let Some(e) = fails(&candidate) {
+                        schedule = candidate;
+                        error = e;
+                        changed = true;
+                    } else {
+                        start += chunk;
+                    }
+                }
+                if chunk == 1 {
+                    break;
+                }
+                chunk /= 2;
+            }
+
+            // shorten the remaining faults
+            for i in 0..schedule.entries.len() {
+                while runs < self.max_shrink_runs {
+                    let Some(duration) = schedule.entries[i].action.duration() else {
+                        break;
+                    };
+                    if duration < 1e-3 {
+                        break;
+                    }
+                    let mut candidate = schedule.clone();
+                    candidate.entries[i].action.set_duration(duration / 2.);
+                    runs += 1;
+                    let Some(e) = fails(&candidate) else {
+                        break;
+                    };
+                    schedule = candidate;
+                    error = e;
+                    changed = true;
+                }
+            }
+        }
+
+        let (result, trace) = Self::run_schedule(seed, &schedule, build, check);
+        PropertyFailure {
+            seed,
+            original,
+            schedule,
+            error: result.err().unwrap_or(error),
+            trace,
+            shrink_runs: runs,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Running a test over multiple random seeds in parallel.
+
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::test::{run_guarded, TestResult};
+
+/// Runs a test over multiple random seeds in parallel worker threads.
+///
+/// The test is specified as a function which builds a system with the given seed, runs it and checks the outcome.
+/// Since [`System`](crate::System) can not be shared between threads, each run builds its own system in the worker
+/// thread. The result of each seed depends only on the seed, so the report is the same regardless of the number
+/// of threads.
+///
+/// ```ignore
+/// let report = SeedRunner::new(0..1000).threads(8).run(|seed| test_consensus(seed));
+/// assert!(report.all_passed(), "{report}");
+/// ```
+pub struct SeedRunner {
+    seeds: Vec<u64>,
+    threads: usize,
+}
+
+impl SeedRunner {
+    /// Creates a runner for the specified seeds.
+    ///
+    /// By default, the number of worker threads is equal to the available parallelism.
+    pub fn new<I: IntoIterator<Item = u64>>(seeds: I) -> Self {
+        Self {
+            seeds: seeds.into_iter().collect(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    /// Sets the number of worker threads.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Number of threads must be positive");
+        self.threads = threads;
+        self
+    }
+
+    /// Runs the test for each seed and returns the results in the order of seeds.
+    ///
+    /// If the test panics, the seed is reported as failed with the panic message.
+    pub fn run<F>(&self, test: F) -> SeedReport
+    where
+        F: Fn(u64) -> TestResult + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<TestResult>>> = Mutex::new(vec![None; self.seeds.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(self.seeds.len()) {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    if idx >= self.seeds.len() {
+                        break;
+                    }
+                    let result = run_guarded(|| test(self.seeds[idx]));
+                    results.lock().unwrap()[idx] = Some(result);
+                });
+            }
+        });
+        let results = results.into_inner().unwrap();
+        SeedReport {
+            results: self
+                .seeds
+                .iter()
+                .zip(results)
+                .map(|(seed, result)| (*seed, result.unwrap()))
+                .collect(),
+        }
+    }
+}
+
+/// Results of running a test over multiple seeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedReport {
+    results: Vec<(u64, TestResult)>,
+}
+
+impl SeedReport {
+    /// Returns the result for each seed in the order of seeds.
+    pub fn results(&self) -> &[(u64, TestResult)] {
+        &self.results
+    }
+
+    /// Returns the number of seeds for which the test passed.
+    pub fn passed_count(&self) -> usize {
+        self.results.iter().filter(|(_, result)| result.is_ok()).count()
+    }
+
+    /// Returns the number of seeds for which the test failed.
+    pub fn failed_count(&self) -> usize {
+        self.results.len() - self.passed_count()
+    }
+
+    /// Returns true if the test passed for all seeds.
+    pub fn all_passed(&self) -> bool {
+        self.failed_count() == 0
+    }
+
+    /// Returns the failed seeds with their error messages in the order of seeds.
+    pub fn failures(&self) -> Vec<(u64, &str)> {
+        self.results
+            .iter()
+            .filter_map(|(seed, result)| result.as_ref().err().map(|e| (*seed, e.as_str())))
+            .collect()
+    }
+}
+
+impl Display for SeedReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Passed {} from {} seeds", self.passed_count(), self.results.len())?;
+        let failures = self.failures();
+        if !failures.is_empty() {
+            write!(f, "\nFailed seeds:")?;
+            for (seed, e) in failures {
+                write!(f, "\n- {seed}: {e}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+//! Workload generation for driving client processes with local messages.
+//!
+//! [`Workload`] generates key-value operations for a set of client processes according to [`WorkloadConfig`]:
+//! the mix of reads and writes, the distribution of keys and the arrival process. The operations are encoded as
+//! local messages and sent to clients at the simulation times determined by the arrival process, while the responses
+//! are decoded from local messages sent by clients. The invocations and responses are recorded into a [`History`],
+//! which can be checked by the [`history`](crate::history) checkers using the [`KvStore`](crate::history::KvStore)
+//! model. All random choices are made using the simulation RNG, so the workload is deterministic for a given seed.
+//!
+//! ```ignore
+//! let config = WorkloadConfig::default()
+//!     .clients(["client1", "client2"])
+//!     .read_ratio(0.8)
+//!     .keys(10, KeyDistribution::Zipfian(0.99))
+//!     .arrival(Arrival::Open { rate: 2.0 })
+//!     .operations(100);
+//! let history = Workload::new(config, encode_request, decode_response).run(&mut sys);
+//! check_linearizability(&KvStore::new(), &history)?;
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use simcore::{cast, Event, EventHandler, SimulationContext};
+
+use crate::history::{History, KvOp};
+use crate::logger::{LogEntry, LogSink};
+use crate::{Message, Node, System};
+
+/// History of key-value operations produced by a workload.
+pub type KvHistory = History<KvOp<String, String>, Option<String>>;
+
+/// Distribution of keys accessed by operations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyDistribution {
+    /// All keys are accessed with the same probability.
+    Uniform,
+    /// The probability of accessing the key with rank `i` (starting from 1) is proportional to `1 / i^s`,
+    /// where `s` is the specified exponent.
+    Zipfian(f64),
+}
+
+/// Process of operation arrivals at each client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arrival {
+    /// Operations arrive independently of responses with exponentially distributed intervals (Poisson process).
+    /// The rate is specified as the mean number of operations per time unit.
+    Open {
+        /// Operations per time unit.
+        rate: f64,
+    },
+    /// Next operation is invoked after the response to the previous one and a fixed think time.
+    Closed {
+        /// Delay between the response and the next invocation.
+        think_time: f64,
+    },
+}
+
+/// Workload configuration.
+///
+/// By default, the workload issues 10 operations per client in closed loop without think time,
+/// half of operations are reads and 10 keys are accessed uniformly.
+#[derive(Clone, Debug)]
+pub struct WorkloadConfig {
+    clients: Vec<String>,
+    read_ratio: f64,
+    keys: usize,
+    key_distribution: KeyDistribution,
+    arrival: Arrival,
+    operations: Option<usize>,
+    duration: Option<f64>,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        Self {
+            clients: Vec::new(),
+            read_ratio: 0.5,
+            keys: 10,
+            key_distribution: KeyDistribution::Uniform,
+            arrival: Arrival::Closed { think_time: 0. },
+            operations: Some(10),
+            duration: None,
+        }
+    }
+}
+
+impl WorkloadConfig {
+    /// Sets the names of client processes receiving the operations.
+    pub fn clients<I, S>(mut self, clients: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.clients = clients.into_iter().map(|c| c.into()).collect();
+        self
+    }
+
+    /// Sets the fraction of read operations, other operations are writes.
+    pub fn read_ratio(mut self, ratio: f64) -> Self {
+        assert!((0. ..=1.).contains(&ratio), "Read ratio must be in [0, 1]");
+        self.read_ratio = ratio;
+        self
+    }
+
+    /// Sets the number of keys and their distribution.
+    ///
+    /// Keys are named `key0`, `key1`, etc. in the order of decreasing popularity.
+    pub fn keys(mut self, count: usize, distribution: KeyDistribution) -> Self {
+        assert!(count > 0, "Number of keys must be positive");
+        self.keys = count;
+        self.key_distribution = distribution;
+        self
+    }
+
+    /// Sets the arrival process.
+    pub fn arrival(mut self, arrival: Arrival) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    /// Sets the number of operations issued by each client, `None` means no limit.
+    pub fn operations(mut self, count: impl Into<Option<usize>>) -> Self {
+        self.operations = count.into();
+        self
+    }
+
+    /// Limits the time since the workload start during which new operations are issued.
+    pub fn duration(mut self, duration: f64) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+type RequestEncoder = Box<dyn Fn(&KvOp<String, String>) -> Message>;
+type ResponseDecoder = Box<dyn Fn(&Message) -> Option<Option<String>>>;
+
+/// Generates operations and sends them to client processes as local messages.
+///
+/// Writes use unique values `v1`, `v2`, etc., so that reads can be attributed to writes. The operation output is
+/// the read value for reads (`None` if the key is missing) and is ignored for writes. The operations issued while
+/// the client is unavailable (its node is crashed or the process is not added again after the node recovery)
+/// are dropped, in closed loop such client stops issuing operations.
+pub struct Workload {
+    config: WorkloadConfig,
+    request: RequestEncoder,
+    response: ResponseDecoder,
+}
+
+impl Workload {
+    /// Creates a workload with the specified functions for encoding operations as local messages
+    /// and decoding responses from local messages sent by clients.
+    ///
+    /// Local messages for which the decoding function returns `None` are not treated as responses.
+    pub fn new<FReq, FResp>(config: WorkloadConfig, request: FReq, response: FResp) -> Self
+    where
+        FReq: Fn(&KvOp<String, String>) -> Message + 'static,
+        FResp: Fn(&Message) -> Option<Option<String>> + 'static,
+    {
+        Self {
+            config,
+            request: Box::new(request),
+            response: Box::new(response),
+        }
+    }
+
+    /// Starts the workload in the system at the current simulation time.
+    ///
+    /// Returns a shared reference to the history, which is updated as the simulation proceeds.
+    pub fn start(self, sys: &mut System) -> Rc<RefCell<KvHistory>> {
+        assert!(
+            self.config.operations.is_some() || self.config.duration.is_some(),
+            "Workload must be limited by the number of operations or duration"
+        );
+        let ctx = sys.create_workload_context();
+        let name = ctx.name().to_string();
+        let nodes = self
+            .config
+            .clients
+            .iter()
+            .map(|client| (client.clone(), sys.proc_node(client)))
+            .collect();
+        let end_time = self.config.duration.map(|duration| ctx.time() + duration);
+        let history = Rc::new(RefCell::new(History::new()));
+        let state = Rc::new(RefCell::new(WorkloadState {
+            key_cdf: key_cdf(self.config.keys, self.config.key_distribution),
+            config: self.config,
+            request: self.request,
+            response: self.response,
+            history: history.clone(),
+            issued: HashMap::new(),
+            end_time,
+            next_value: 1,
+            ctx,
+        }));
+
+        sys.logger().add_sink(Box::new(WorkloadSink { state: state.clone() }));
+        {
+            let mut state = state.borrow_mut();
+            for client in state.config.clients.clone() {
+                let delay = match state.config.arrival {
+                    Arrival::Open { rate } => state.exponential(rate),
+                    Arrival::Closed { .. } => 0.,
+                };
+                state.schedule(&client, delay);
+            }
+        }
+        sys.add_handler(&name, Rc::new(RefCell::new(WorkloadDriver { state, nodes })));
+        history
+    }
+
+    /// Starts the workload and steps through the simulation until there are no pending events left.
+    ///
+    /// Returns the recorded history.
+    pub fn run(self, sys: &mut System) -> KvHistory {
+        let history = self.start(sys);
+        sys.step_until_no_events();
+        let history = history.borrow().clone();
+        history
+    }
+}
+
+/// Cumulative probabilities of keys.
+fn key_cdf(keys: usize, distribution: KeyDistribution) -> Vec<f64> {
+    let weights: Vec<f64> = match distribution {
+        KeyDistribution::Uniform => vec![1.; keys],
+        KeyDistribution::Zipfian(s) => (1..=keys).map(|rank| 1. / (rank as f64).powf(s)).collect(),
+    };
+    let total: f64 = weights.iter().sum();
+    let mut sum = 0.;
+    weights
+        .iter()
+        .map(|w| {
+            sum += w;
+            sum / total
+        })
+        .collect()
+}
+
+struct WorkloadState {
+    config: WorkloadConfig,
+    key_cdf: Vec<f64>,
+    request: RequestEncoder,
+    response: ResponseDecoder,
+    history: Rc<RefCell<KvHistory>>,
+    issued: HashMap<String, usize>,
+    end_time: Option<f64>,
+    next_value: u64,
+    ctx: SimulationContext,
+}
+
+impl WorkloadState {
+    fn exponential(&self, rate: f64) -> f64 {
+        -(1. - self.ctx.rand()).ln() / rate
+    }
+
+    /// Schedules the next operation of the client if the workload limits allow it.
+    fn schedule(&mut self, client: &str, delay: f64) {
+        let issued = self.issued.get(client).copied().unwrap_or(0);
+        if self.config.operations.is_some_and(|limit| issued >= limit) {
+            return;
+        }
+        if self.end_time.is_some_and(|end| self.ctx.time() + delay > end) {
+            return;
+        }
+        self.ctx.emit_self(
+            WorkloadOperation {
+                client: client.to_string(),
+            },
+            delay,
+        );
+    }
+
+    fn next_op(&mut self) -> KvOp<String, String> {
+        let u = self.ctx.rand();
+        let rank = self.key_cdf.partition_point(|p| *p <= u).min(self.key_cdf.len() - 1);
+        let key = format!("key{rank}");
+        if self.ctx.rand() < self.config.read_ratio {
+            KvOp::Get(key)
+        } else {
+            let value = format!("v{}", self.next_value);
+            self.next_value += 1;
+            KvOp::Put(key, value)
+        }
+    }
+}
+
+/// Operation of the client is invoked.
+#[derive(Clone, Serialize)]
+struct WorkloadOperation {
+    client: String,
+}
+
+struct WorkloadDriver {
+    state: Rc<RefCell<WorkloadState>>,
+    nodes: HashMap<String, Rc<RefCell<Node>>>,
+}
+
+impl EventHandler for WorkloadDriver {
+    fn on(&mut self, event: Event) {
+        cast!(match event.data {
+            WorkloadOperation { client } => {
+                let node = self.nodes[&client].clone();
+                let available = {
+                    let node = node.borrow();
+                    !node.is_crashed() && node.get_process(&client).is_some()
+                };
+                let msg = {
+                    let mut state = self.state.borrow_mut();
+                    *state.issued.entry(client.clone()).or_default() += 1;
+                    let op = state.next_op();
+                    if let Arrival::Open { rate } = state.config.arrival {
+                        let delay = state.exponential(rate);
+                        state.schedule(&client, delay);
+                    }
+                    if available {
+                        let msg = (state.request)(&op);
+                        let time = state.ctx.time();
+                        state.history.borrow_mut().invoke(&client, op, time);
+                        Some(msg)
+                    } else {
+                        None
+                    }
+                };
+                if let Some(msg) = msg {
+                    node.borrow_mut().send_local_message(client, msg);
+                }
+            }
+        })
+    }
+}
+
+/// Records responses and schedules next operations in closed loop.
+struct WorkloadSink {
+    state: Rc<RefCell<WorkloadState>>,
+}
+
+impl LogSink for WorkloadSink {
+    fn write(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        if let LogEntry::LocalMessageSent { time, proc, msg, .. } = entry {
+            let mut state = self.state.borrow_mut();
+            if !state.config.clients.contains(proc) {
+                return Ok(());
+            }
+            let Some(output) = (state.response)(msg) else {
+                return Ok(());
+            };
+            let op = state.history.borrow_mut().respond(proc, output, *time);
+            if let (Some(_), Arrival::Closed { think_time }) = (op, state.config.arrival) {
+                state.schedule(proc, think_time);
+            }
+        }
+        Ok(())
+    }
+}
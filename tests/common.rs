@@ -0,0 +1,107 @@
+//! Processes and builders shared by the tests.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use anysystem::logger::LoggerConfig;
+use anysystem::{Context, Message, Process, System};
+
+/// Forwards local messages to the server and received messages to local.
+#[derive(Clone)]
+pub struct Client {
+    server: String,
+}
+
+impl Client {
+    pub fn new(server: &str) -> Self {
+        Self {
+            server: server.to_string(),
+        }
+    }
+}
+
+impl Process for Client {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, self.server.clone());
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Sends each received message back to the sender.
+#[derive(Clone)]
+pub struct EchoServer;
+
+impl Process for EchoServer {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, from);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Key-value store replying to GET and PUT requests with GET_OK and PUT_OK.
+#[derive(Clone, Default)]
+pub struct KvServer {
+    data: HashMap<String, String>,
+}
+
+impl Process for KvServer {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        let req: Value = serde_json::from_str(&msg.data).unwrap();
+        let key = req["key"].as_str().unwrap().to_string();
+        match msg.tip.as_str() {
+            "GET" => ctx.send(Message::json("GET_OK", &json!({"value": self.data.get(&key)})), from),
+            "PUT" => {
+                self.data.insert(key, req["value"].as_str().unwrap().to_string());
+                ctx.send(Message::json("PUT_OK", &json!({})), from);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Builds a system where each process runs on its own node `n1`, `n2`, ... in the order of `procs`.
+pub fn build_system_with_logger_config(
+    seed: u64,
+    config: LoggerConfig,
+    procs: Vec<(&str, Box<dyn Process>)>,
+) -> System {
+    let mut sys = System::with_logger_config(seed, config);
+    for (i, (name, proc)) in procs.into_iter().enumerate() {
+        let node = format!("n{}", i + 1);
+        sys.add_node(&node);
+        sys.add_process(name, proc, &node);
+    }
+    sys
+}
+
+/// Builds a system with quiet logger where each process runs on its own node `n1`, `n2`, ... in the order of `procs`.
+pub fn build_system(seed: u64, procs: Vec<(&str, Box<dyn Process>)>) -> System {
+    build_system_with_logger_config(seed, LoggerConfig::default().quiet(), procs)
+}
@@ -1,2 +1,22 @@
+mod common;
+mod test_async;
+mod test_causality;
+mod test_context;
+mod test_history;
+mod test_invariants;
+mod test_log_assertions;
+mod test_logger;
 mod test_mc;
+mod test_metrics;
+mod test_process_errors;
+mod test_property;
 mod test_python_mc;
+mod test_rpc;
+mod test_seeds;
+mod test_size;
+mod test_snapshot;
+mod test_state_access;
+mod test_stats;
+mod test_suite;
+mod test_timers;
+mod test_workload;
@@ -35,3 +35,26 @@ class TestProcess(Process):
     def on_timer(self, timer_name: str, ctx: Context):
         # process fired timers here
         pass
+
+
+class TopologyProcess(Process):
+    def on_start(self, ctx: Context):
+        pass
+
+    def on_local_message(self, msg: Message, ctx: Context):
+        info = {
+            'proc': ctx.proc_name(),
+            'node': ctx.node_name(),
+            'processes': ctx.processes(),
+            'peers': ctx.peers(),
+            'group': ctx.group('group'),
+            'unknown_group': ctx.group('unknown'),
+            'node_processes': ctx.node_processes(),
+        }
+        ctx.send_local(Message('INFO', info))
+
+    def on_message(self, msg: Message, sender: str, ctx: Context):
+        pass
+
+    def on_timer(self, timer_name: str, ctx: Context):
+        pass
@@ -0,0 +1,248 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use sugars::{boxed, rc, refcell};
+
+use anysystem::async_process::{select, AsyncProcess, Either};
+use anysystem::error::StepError;
+use anysystem::mc::predicates::{goals, prunes};
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::{Message, System};
+
+use crate::common::build_system;
+
+/// Sends the ping from the user to the server until receiving a pong.
+fn client() -> AsyncProcess {
+    AsyncProcess::new(|ctx| async move {
+        let ping = ctx.recv_local(|msg| msg.tip == "PING").await;
+        loop {
+            ctx.send(ping.clone(), "server");
+            let pong = ctx.recv(|msg, from| msg.tip == "PONG" && from == "server");
+            if let Either::Left(((pong, _), _)) = select(pong, ctx.sleep(3.)).await {
+                ctx.send_local(pong);
+                return Ok(());
+            }
+        }
+    })
+}
+
+/// Replies to pings ignoring the first `drop_count` of them.
+fn server(drop_count: usize) -> AsyncProcess {
+    AsyncProcess::new(move |ctx| async move {
+        for _ in 0..drop_count {
+            ctx.recv(|msg, _| msg.tip == "PING").await;
+        }
+        loop {
+            let (ping, from) = ctx.recv(|msg, _| msg.tip == "PING").await;
+            if ping.data == "STOP" {
+                return Err("stopped".to_string());
+            }
+            ctx.send(Message::new("PONG", &ping.data), &from);
+        }
+    })
+}
+
+fn ping_system(drop_count: usize) -> System {
+    build_system(
+        1,
+        vec![("client", boxed!(client())), ("server", boxed!(server(drop_count)))],
+    )
+}
+
+#[test]
+fn async_ping_pong() {
+    let mut sys = ping_system(0);
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::new("PONG", "1")]);
+    // the sleep timer is cancelled after receiving the pong
+    assert_eq!(sys.time(), 2.);
+}
+
+#[test]
+fn async_retry_on_timeout() {
+    let mut sys = ping_system(2);
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::new("PONG", "1")]);
+    assert_eq!(sys.time(), 8.);
+    assert_eq!(sys.sent_message_count("client"), 3);
+
+    // the process does not react to events after the completion
+    sys.send_local_message("client", Message::new("PING", "2"));
+    sys.step_until_no_events();
+    assert_eq!(sys.sent_message_count("client"), 3);
+}
+
+#[test]
+fn selective_receive() {
+    let proc = AsyncProcess::new(|ctx| async move {
+        for tip in ["B", "A", "C"] {
+            let msg = ctx.recv_local(|msg| msg.tip == tip).await;
+            ctx.send_local(Message::json("GOT", &(msg.tip, ctx.time())));
+            ctx.sleep(1.).await;
+        }
+        Ok(())
+    });
+    let mut sys = build_system(1, vec![("proc", boxed!(proc))]);
+    sys.send_local_message("proc", Message::new("A", ""));
+    sys.send_local_message("proc", Message::new("B", ""));
+    sys.step_until_no_events();
+    sys.send_local_message("proc", Message::new("C", ""));
+    assert_eq!(
+        sys.read_local_messages("proc"),
+        [
+            Message::json("GOT", &("B", 0.)),
+            Message::json("GOT", &("A", 1.)),
+            Message::json("GOT", &("C", 2.)),
+        ]
+    );
+}
+
+#[test]
+fn async_process_error() {
+    let mut sys = ping_system(0);
+    sys.send_local_message("client", Message::new("PING", "STOP"));
+    let StepError::Process(err) = *sys.try_step_until_no_events().unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert_eq!(err.proc, "server");
+    assert_eq!(err.error, "stopped");
+}
+
+#[test]
+fn async_process_state() {
+    let mut sys = ping_system(0);
+    let initial_state = sys
+        .get_node("n1")
+        .unwrap()
+        .get_process("client")
+        .unwrap()
+        .state()
+        .unwrap();
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::new("PONG", "1")]);
+
+    // the restored process waits for the ping again
+    sys.get_mut_node("n1")
+        .unwrap()
+        .set_process_state("client", initial_state);
+    sys.send_local_message("client", Message::new("PING", "2"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::new("PONG", "2")]);
+}
+
+#[test]
+fn async_process_in_model_checking() {
+    let sys = ping_system(1);
+    let pongs = rc!(refcell!(Vec::new()));
+    let final_pongs = pongs.clone();
+    let goal = boxed!(move |state: &McState| {
+        let outbox = &state.node_states["n1"].proc_states["client"].local_outbox;
+        if outbox.is_empty() {
+            None
+        } else {
+            final_pongs.borrow_mut().push(outbox.clone());
+            Some("pong".to_string())
+        }
+    });
+    // the client retries infinitely, so the state space is bounded by the number of sent messages
+    let config = StrategyConfig::default()
+        .goal(goal)
+        .prune(prunes::sent_messages_limit(4));
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n1", "client", Message::new("PING", "1"))
+    });
+    assert!(result.is_ok());
+    let pongs = pongs.borrow();
+    assert!(!pongs.is_empty());
+    assert!(pongs.iter().all(|outbox| *outbox == [Message::new("PONG", "1")]));
+}
+
+/// Forwards the local message to the counter and completes.
+fn sender() -> AsyncProcess {
+    AsyncProcess::new(|ctx| async move {
+        let msg = ctx.recv_local(|_| true).await;
+        ctx.send(msg, "counter");
+        Ok(())
+    })
+}
+
+/// Counts the received messages, optionally declaring the count as the state view.
+fn counter(view: bool) -> AsyncProcess {
+    AsyncProcess::new(move |ctx| async move {
+        let mut count = 0;
+        loop {
+            ctx.recv(|_, _| true).await;
+            count += 1;
+            if view {
+                ctx.set_state_view(&count);
+            }
+        }
+    })
+}
+
+/// Returns the number of states explored when the counter receives messages from two senders.
+fn count_states(view: bool) -> usize {
+    let sys = build_system(
+        1,
+        vec![
+            ("sender1", boxed!(sender())),
+            ("sender2", boxed!(sender())),
+            ("counter", boxed!(counter(view))),
+        ],
+    );
+
+    let states = rc!(refcell!(0));
+    let invariant_states = states.clone();
+    let invariant = boxed!(move |_: &McState| {
+        *invariant_states.borrow_mut() += 1;
+        Ok(())
+    });
+    let config = StrategyConfig::default().goal(goals::no_events()).invariant(invariant);
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n1", "sender1", Message::new("MSG", "1"));
+        sys.send_local_message("n2", "sender2", Message::new("MSG", "2"));
+    });
+    assert!(result.is_ok());
+    let states = *states.borrow();
+    states
+}
+
+#[test]
+fn async_state_view() {
+    let history_states = count_states(false);
+    let view_states = count_states(true);
+    // the states reached by delivering the messages in different orders are equal
+    assert!(view_states < history_states, "{view_states} >= {history_states}");
+}
+
+#[test]
+fn async_clone_is_lazy() {
+    let starts = Rc::new(Cell::new(0));
+    let proc_starts = starts.clone();
+    let proc = AsyncProcess::new(move |ctx| {
+        proc_starts.set(proc_starts.get() + 1);
+        async move {
+            loop {
+                let (msg, from) = ctx.recv(|_, _| true).await;
+                ctx.send(msg, &from);
+            }
+        }
+    });
+    let sys = build_system(1, vec![("echo", boxed!(proc))]);
+    assert_eq!(starts.get(), 1);
+
+    // the model checker copies the process without running the function again
+    let mut mc = ModelChecker::new(&sys);
+    assert_eq!(starts.get(), 1);
+    // the function is replayed when the copy handles the first event
+    let config = StrategyConfig::default().goal(goals::no_events());
+    let result = mc.run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n1", "echo", Message::new("PING", "1"));
+    });
+    assert!(result.is_ok());
+    assert!(starts.get() > 1);
+}
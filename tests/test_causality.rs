@@ -0,0 +1,192 @@
+use sugars::boxed;
+
+use anysystem::causality::{CausalityGraph, VectorClock};
+use anysystem::logger::LogEntry;
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::build_system;
+
+/// Forwards received messages to the next process (if any) and reports its Lamport time via local message.
+#[derive(Clone)]
+struct ForwardProcess {
+    next: Option<String>,
+}
+
+impl Process for ForwardProcess {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send_local(Message::new("TIME", &ctx.lamport_time().to_string()));
+        if let Some(next) = &self.next {
+            ctx.send(msg, next.clone());
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        if let Some(next) = &self.next {
+            ctx.send(msg, next.clone());
+        }
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Builds the chain of processes p1 -> p2 -> p3 <- p4.
+fn build_chain() -> System {
+    let procs = [("p1", Some("p2")), ("p2", Some("p3")), ("p3", None), ("p4", Some("p3"))];
+    build_system(
+        42,
+        procs
+            .into_iter()
+            .map(|(proc, next)| {
+                let proc_impl: Box<dyn Process> = boxed!(ForwardProcess {
+                    next: next.map(|s| s.to_string())
+                });
+                (proc, proc_impl)
+            })
+            .collect(),
+    )
+}
+
+fn clock(times: &[(&str, u64)]) -> VectorClock {
+    let mut clock = VectorClock::new();
+    for (proc, time) in times {
+        for _ in 0..*time {
+            clock.increment(proc);
+        }
+    }
+    clock
+}
+
+#[test]
+fn vector_clock_order() {
+    let a = clock(&[("p1", 1)]);
+    let b = clock(&[("p1", 1), ("p2", 1)]);
+    let c = clock(&[("p3", 1)]);
+    assert!(a.happens_before(&b));
+    assert!(!b.happens_before(&a));
+    assert!(!a.happens_before(&a));
+    assert!(a.concurrent_with(&c));
+    assert!(b.concurrent_with(&c));
+
+    let mut merged = b.clone();
+    merged.merge(&c);
+    assert_eq!(merged, clock(&[("p1", 1), ("p2", 1), ("p3", 1)]));
+}
+
+#[test]
+fn message_clocks() {
+    let mut sys = build_chain();
+    sys.send_local_message("p1", Message::new("PING", "{}"));
+    sys.step_until_no_events();
+
+    let clocks: Vec<(&str, u64, VectorClock)> = sys
+        .logger()
+        .trace()
+        .iter()
+        .filter_map(|e| match e {
+            LogEntry::MessageSent {
+                lamport_time,
+                vector_clock,
+                ..
+            } => Some(("sent", *lamport_time, vector_clock.clone())),
+            LogEntry::MessageReceived {
+                lamport_time,
+                vector_clock,
+                ..
+            } => Some(("received", *lamport_time, vector_clock.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        clocks,
+        vec![
+            ("sent", 1, clock(&[("p1", 1)])),
+            ("received", 2, clock(&[("p1", 1), ("p2", 1)])),
+            ("sent", 3, clock(&[("p1", 1), ("p2", 2)])),
+            ("received", 4, clock(&[("p1", 1), ("p2", 2), ("p3", 1)])),
+        ]
+    );
+
+    // processes observe their clocks after receiving the message
+    assert_eq!(sys.read_local_messages("p2")[0].data, "2");
+    assert_eq!(sys.read_local_messages("p3")[0].data, "4");
+}
+
+#[test]
+fn causality_graph_from_simulation() {
+    let mut sys = build_chain();
+    sys.send_local_message("p1", Message::new("PING", "{}"));
+    sys.send_local_message("p4", Message::new("PING", "{}"));
+    sys.step_until_no_events();
+    let trace = sys.logger().trace().to_vec();
+
+    let find = |kind: &str, proc: &str| {
+        trace
+            .iter()
+            .position(|e| e.kind() == kind && e.procs().first() == Some(&proc))
+            .unwrap()
+    };
+    let p1_local = find("LocalMessageReceived", "p1");
+    let p1_sent = find("MessageSent", "p1");
+    let p2_sent = find("MessageSent", "p2");
+    let p4_sent = find("MessageSent", "p4");
+    let p3_received: Vec<usize> = trace
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| matches!(e, LogEntry::MessageReceived { dst_proc, .. } if dst_proc == "p3"))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(p3_received.len(), 2);
+
+    let graph = CausalityGraph::from_trace(&trace);
+    assert!(graph.happens_before(p1_local, p1_sent));
+    assert!(graph.happens_before(p1_sent, p2_sent));
+    assert!(graph.happens_before(p1_sent, *p3_received.last().unwrap()));
+    assert!(!graph.happens_before(p2_sent, p1_sent));
+    assert!(graph.concurrent(p1_sent, p4_sent));
+    assert!(graph.concurrent_events(p4_sent).contains(&p2_sent));
+    assert!(!graph.concurrent_events(p4_sent).contains(&p3_received[1]));
+    assert_eq!(graph.message_edges().len(), 3);
+
+    // node events are not included
+    let node_started = trace.iter().position(|e| e.kind() == "NodeStarted").unwrap();
+    assert!(graph.event(node_started).is_none());
+    assert!(!graph.happens_before(node_started, p1_sent));
+}
+
+#[test]
+fn causality_graph_from_mc_trace() {
+    let ping = Message::new("PING", "{}");
+    let trace = vec![
+        LogEntry::McStarted {},
+        LogEntry::McMessageSent {
+            msg: ping.clone(),
+            src: "p1".to_string(),
+            dst: "p2".to_string(),
+        },
+        LogEntry::McTimerSet {
+            proc: "p2".to_string(),
+            timer: "t".to_string(),
+        },
+        LogEntry::McMessageReceived {
+            msg: ping.clone(),
+            src: "p1".to_string(),
+            dst: "p2".to_string(),
+        },
+        LogEntry::McLocalMessageSent {
+            msg: ping,
+            proc: "p2".to_string(),
+        },
+    ];
+
+    let graph = CausalityGraph::from_trace(&trace);
+    assert_eq!(graph.events().len(), 4);
+    assert_eq!(graph.message_edges(), &[(1, 3)]);
+    assert!(graph.happens_before(1, 4));
+    assert!(graph.happens_before(2, 3));
+    assert!(graph.concurrent(1, 2));
+    assert_eq!(graph.concurrent_events(1), vec![2]);
+}
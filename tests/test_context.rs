@@ -0,0 +1,130 @@
+use sugars::{boxed, rc, refcell};
+
+use anysystem::logger::LoggerConfig;
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::{Context, Message, Process, System};
+
+/// Replies to local messages with the information about the system obtained from the context.
+#[derive(Clone)]
+struct Inspector;
+
+type Info = (String, String, Vec<String>, Vec<String>, Vec<String>, Vec<String>);
+
+impl Inspector {
+    fn info(ctx: &Context) -> Info {
+        (
+            ctx.proc_name().to_string(),
+            ctx.node_name().to_string(),
+            ctx.processes(),
+            ctx.peers(),
+            ctx.group("replicas"),
+            ctx.node_processes(),
+        )
+    }
+}
+
+impl Process for Inspector {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send_local(Message::json("INFO", &Self::info(ctx)));
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn build_system() -> System {
+    let mut sys = System::with_logger_config(1, LoggerConfig::default().quiet());
+    sys.add_node("n1");
+    sys.add_node("n2");
+    sys.add_process("client", boxed!(Inspector), "n1");
+    sys.add_process("replica1", boxed!(Inspector), "n1");
+    sys.add_process("replica2", boxed!(Inspector), "n2");
+    sys.add_group("replicas", &["replica1", "replica2"]);
+    sys
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+fn read_info(sys: &mut System, proc: &str) -> Info {
+    sys.send_local_message(proc, Message::new("GET", "{}"));
+    serde_json::from_str(&sys.read_local_messages(proc)[0].data).unwrap()
+}
+
+#[test]
+fn context_topology() {
+    let mut sys = build_system();
+    assert_eq!(
+        read_info(&mut sys, "replica1"),
+        (
+            "replica1".to_string(),
+            "n1".to_string(),
+            strings(&["client", "replica1", "replica2"]),
+            strings(&["client", "replica2"]),
+            strings(&["replica1", "replica2"]),
+            strings(&["client", "replica1"]),
+        )
+    );
+    let info = read_info(&mut sys, "replica2");
+    assert_eq!(info.1, "n2");
+    assert_eq!(info.5, strings(&["replica2"]));
+}
+
+#[test]
+fn context_topology_after_node_recovery() {
+    let mut sys = build_system();
+    sys.crash_node("n2");
+    sys.recover_node("n2");
+    let info = read_info(&mut sys, "client");
+    assert_eq!(info.2, strings(&["client", "replica1"]));
+    // groups are not changed by the recovery
+    assert_eq!(info.4, strings(&["replica1", "replica2"]));
+
+    sys.add_process("replica3", boxed!(Inspector), "n2");
+    let info = read_info(&mut sys, "replica3");
+    assert_eq!(info.2, strings(&["client", "replica1", "replica3"]));
+    assert_eq!(info.5, strings(&["replica3"]));
+
+    sys.add_group("replicas", &["replica1", "replica3"]);
+    assert_eq!(read_info(&mut sys, "client").4, strings(&["replica1", "replica3"]));
+}
+
+#[test]
+fn context_topology_in_model_checking() {
+    let sys = build_system();
+    let info = rc!(refcell!(None));
+    let final_info = info.clone();
+    let goal = boxed!(move |state: &McState| {
+        let outbox = &state.node_states["n2"].proc_states["replica2"].local_outbox;
+        if outbox.is_empty() {
+            None
+        } else {
+            *final_info.borrow_mut() = Some(serde_json::from_str::<Info>(&outbox[0].data).unwrap());
+            Some("final".to_string())
+        }
+    });
+    let config = StrategyConfig::default().goal(goal);
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n2", "replica2", Message::new("GET", "{}"))
+    });
+    assert!(result.is_ok());
+    assert_eq!(
+        info.borrow().clone().unwrap(),
+        (
+            "replica2".to_string(),
+            "n2".to_string(),
+            strings(&["client", "replica1", "replica2"]),
+            strings(&["client", "replica1"]),
+            strings(&["replica1", "replica2"]),
+            strings(&["replica2"]),
+        )
+    );
+}
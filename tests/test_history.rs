@@ -0,0 +1,355 @@
+use serde_json::{json, Value};
+use sugars::boxed;
+
+use anysystem::causality::CausalityGraph;
+use anysystem::history::{
+    check_causal_consistency, check_linearizability, check_sequential_consistency, check_session_guarantees, History,
+    HistoryRecorder, KvOp, KvStore, Model, Queue, QueueOp, Register, RegisterOp, SessionGuarantee,
+};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::{build_system, KvServer};
+
+#[derive(Clone)]
+struct Client {
+    replica: String,
+    peer: Option<String>,
+}
+
+impl Process for Client {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        let notify = msg.tip == "PUT_OK";
+        ctx.send_local(msg);
+        if let (true, Some(peer)) = (notify, &self.peer) {
+            ctx.send(Message::json("NOTIFY", &json!({})), peer.clone());
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, self.replica.clone());
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Builds a system with two clients, which use the same replica or separate (not replicated) replicas.
+/// The first client can notify the second one about its completed writes.
+fn build_kv_system(shared_replica: bool, notify_peer: bool) -> System {
+    let client = |replica: &str, peer: Option<&str>| -> Box<dyn Process> {
+        boxed!(Client {
+            replica: replica.to_string(),
+            peer: peer.filter(|_| notify_peer).map(|peer| peer.to_string()),
+        })
+    };
+    build_system(
+        123,
+        vec![
+            ("c1", client("r1", Some("c2"))),
+            ("c2", client(if shared_replica { "r1" } else { "r2" }, None)),
+            ("r1", boxed!(KvServer::default())),
+            ("r2", boxed!(KvServer::default())),
+        ],
+    )
+}
+
+fn parse_request(msg: &Message) -> Option<KvOp<String, String>> {
+    let data: Value = serde_json::from_str(&msg.data).ok()?;
+    let key = data["key"].as_str()?.to_string();
+    match msg.tip.as_str() {
+        "GET" => Some(KvOp::Get(key)),
+        "PUT" => Some(KvOp::Put(key, data["value"].as_str()?.to_string())),
+        _ => None,
+    }
+}
+
+fn parse_response(msg: &Message) -> Option<Option<String>> {
+    let data: Value = serde_json::from_str(&msg.data).ok()?;
+    match msg.tip.as_str() {
+        "GET_OK" => Some(data["value"].as_str().map(|s| s.to_string())),
+        "PUT_OK" => Some(None),
+        _ => None,
+    }
+}
+
+fn run_workload(sys: &mut System) {
+    sys.send_local_message("c1", Message::json("PUT", &json!({"key": "x", "value": "1"})));
+    sys.send_local_message("c2", Message::json("GET", &json!({"key": "x"})));
+    sys.step_until_no_events();
+    sys.step_for_duration(1.0);
+    sys.send_local_message("c2", Message::json("GET", &json!({"key": "x"})));
+    sys.step_until_no_events();
+}
+
+#[test]
+fn kv_history_from_trace() {
+    let mut sys = build_kv_system(true, false);
+    run_workload(&mut sys);
+
+    let history = History::from_trace(sys.logger().trace(), parse_request, parse_response);
+    assert_eq!(history.len(), 3);
+    assert!(history.ops().iter().all(|op| !op.is_pending()));
+    assert_eq!(history.ops()[0].client, "c1");
+    assert_eq!(history.ops()[0].input, KvOp::Put("x".to_string(), "1".to_string()));
+    assert_eq!(history.ops()[0].call, 0.);
+    assert_eq!(history.ops()[0].ret, Some(2.));
+    assert_eq!(history.ops()[2].output, Some(Some("1".to_string())));
+
+    let order = check_linearizability(&KvStore::new(), &history).unwrap();
+    assert_eq!(order.len(), 3);
+    assert_eq!(order.last(), Some(&2));
+}
+
+#[test]
+fn kv_not_linearizable() {
+    let mut sys = build_kv_system(false, false);
+    let recorder = HistoryRecorder::new(parse_request, parse_response);
+    let recorded = recorder.history();
+    sys.logger().add_sink(boxed!(recorder));
+    run_workload(&mut sys);
+
+    let history = History::from_trace(sys.logger().trace(), parse_request, parse_response);
+    // the recorder is added after the system is built, so it does not receive the first trace events
+    let recorded = recorded.borrow();
+    let offset = history.ops()[0].call_index.unwrap() - recorded.ops()[0].call_index.unwrap();
+    assert!(offset > 0);
+    assert_eq!(recorded.len(), history.len());
+    for (recorded_op, op) in recorded.ops().iter().zip(history.ops()) {
+        assert_eq!((&recorded_op.input, &recorded_op.output), (&op.input, &op.output));
+        assert_eq!((recorded_op.call, recorded_op.ret), (op.call, op.ret));
+        assert_eq!(recorded_op.call_index.unwrap() + offset, op.call_index.unwrap());
+    }
+
+    let err = check_linearizability(&KvStore::new(), &history).unwrap_err();
+    // the second read of c2 does not observe the completed write of c1
+    assert_eq!(err.ops(), &[0, 2]);
+    assert!(err.message().contains("c1: Put(\"x\", \"1\")"));
+    assert!(err.message().contains("c2: Get(\"x\") -> None"));
+}
+
+#[test]
+fn register_histories() {
+    let register = Register::new(0);
+
+    // stale read after the completed write
+    let mut history = History::new();
+    history.add("a", RegisterOp::Write(1), None, 0., 1.);
+    history.add("b", RegisterOp::Read, Some(0), 2., 3.);
+    history.add("c", RegisterOp::Read, Some(1), 2.5, 4.);
+    let err = check_linearizability(&register, &history).unwrap_err();
+    assert_eq!(err.ops(), &[0, 1]);
+    assert_eq!(history.subset(err.ops()).ops(), &history.ops()[..2]);
+
+    // concurrent write and read may return either value
+    let mut history = History::new();
+    history.add("a", RegisterOp::Write(1), None, 0., 10.);
+    history.add("b", RegisterOp::Read, Some(0), 1., 2.);
+    history.add("c", RegisterOp::Read, Some(1), 3., 4.);
+    assert_eq!(check_linearizability(&register, &history), Ok(vec![1, 0, 2]));
+
+    // pending write may take effect
+    let mut history = History::new();
+    history.invoke("a", RegisterOp::Write(1), 0.);
+    history.add("b", RegisterOp::Read, Some(1), 1., 2.);
+    history.add("c", RegisterOp::Read, Some(1), 3., 4.);
+    assert_eq!(check_linearizability(&register, &history), Ok(vec![0, 1, 2]));
+
+    // pending write may also be omitted, but not after it was observed
+    let mut history = History::new();
+    history.invoke("a", RegisterOp::Write(1), 0.);
+    history.add("b", RegisterOp::Read, Some(0), 1., 2.);
+    assert_eq!(check_linearizability(&register, &history), Ok(vec![1]));
+    history.add("c", RegisterOp::Read, Some(1), 3., 4.);
+    history.add("b", RegisterOp::Read, Some(0), 5., 6.);
+    let err = check_linearizability(&register, &history).unwrap_err();
+    assert_eq!(err.ops(), &[2, 3]);
+}
+
+#[test]
+fn kv_partitions() {
+    let kv = KvStore::new();
+    let put = |key: &str, value: &str| KvOp::Put(key.to_string(), value.to_string());
+    let get = |key: &str| KvOp::Get(key.to_string());
+    assert_eq!(kv.partition(&put("x", "1")), kv.partition(&get("x")));
+    assert_ne!(kv.partition(&get("x")), kv.partition(&get("y")));
+
+    // operations on each key are linearizable only in the order different from the invocation order
+    let mut history = History::new();
+    history.add("a", put("x", "1"), None, 0., 10.);
+    history.add("b", put("y", "1"), None, 1., 2.);
+    history.add("c", get("x"), None, 3., 4.);
+    history.add("b", get("y"), Some("1".to_string()), 5., 6.);
+    history.invoke("d", put("y", "2"), 7.);
+    history.add("c", get("x"), Some("1".to_string()), 11., 12.);
+    let order = check_linearizability(&kv, &history).unwrap();
+    assert_eq!(order.len(), 5);
+    let position = |op: usize| order.iter().position(|o| *o == op).unwrap();
+    for (i, op) in history.ops().iter().enumerate() {
+        for (j, other) in history.ops().iter().enumerate() {
+            if op.ret.is_some_and(|ret| ret < other.call) && order.contains(&j) {
+                assert!(position(i) < position(j));
+            }
+        }
+    }
+    assert!(position(2) < position(0));
+
+    // the stale read is reported together with the operations on the same key only
+    history.add("b", get("y"), Some("1".to_string()), 13., 14.);
+    history.add("d", put("x", "2"), None, 15., 16.);
+    history.add("a", get("x"), Some("1".to_string()), 17., 18.);
+    let err = check_linearizability(&kv, &history).unwrap_err();
+    assert_eq!(err.ops(), &[5, 7, 8]);
+}
+
+#[test]
+fn queue_histories() {
+    let mut history = History::new();
+    history.add("a", QueueOp::Enqueue(1), None, 0., 1.);
+    history.add("b", QueueOp::Enqueue(2), None, 2., 3.);
+    history.add("c", QueueOp::Dequeue, Some(2), 4., 5.);
+    history.add("a", QueueOp::Dequeue, Some(1), 6., 7.);
+    // 1 was enqueued before 2, so 2 can not be dequeued first
+    let err = check_linearizability(&Queue::new(), &history).unwrap_err();
+    assert_eq!(err.ops(), &[0, 2]);
+
+    // concurrent enqueues may take effect in any order
+    let mut history = History::new();
+    history.add("a", QueueOp::Enqueue(1), None, 0., 3.);
+    history.add("b", QueueOp::Enqueue(2), None, 0.5, 1.);
+    history.add("c", QueueOp::Dequeue, Some(2), 2., 4.);
+    history.add("a", QueueOp::Dequeue, Some(1), 5., 6.);
+    history.add("b", QueueOp::Dequeue, None, 7., 8.);
+    assert_eq!(check_linearizability(&Queue::new(), &history), Ok(vec![1, 0, 2, 3, 4]));
+}
+
+#[test]
+fn sequential_consistency() {
+    let register = Register::new(0);
+
+    // stale read is not linearizable, but is sequentially consistent
+    let mut history = History::new();
+    history.add("a", RegisterOp::Write(1), None, 0., 1.);
+    history.add("b", RegisterOp::Read, Some(0), 2., 3.);
+    assert!(check_linearizability(&register, &history).is_err());
+    assert_eq!(check_sequential_consistency(&register, &history), Ok(vec![1, 0]));
+
+    // reads of the same client can not go back
+    history.add("b", RegisterOp::Read, Some(1), 4., 5.);
+    history.add("b", RegisterOp::Read, Some(0), 6., 7.);
+    let err = check_sequential_consistency(&register, &history).unwrap_err();
+    assert_eq!(err.ops(), &[2, 3]);
+    assert_eq!(err.clients(), &["b"]);
+    assert!(err.message().contains("not sequentially consistent"));
+
+    // separate replicas without replication
+    let mut sys = build_kv_system(false, false);
+    run_workload(&mut sys);
+    let history = History::from_trace(sys.logger().trace(), parse_request, parse_response);
+    assert!(check_linearizability(&KvStore::new(), &history).is_err());
+    assert_eq!(
+        check_sequential_consistency(&KvStore::new(), &history),
+        Ok(vec![1, 2, 0])
+    );
+}
+
+#[test]
+fn causal_consistency() {
+    let run = |notify_peer: bool| {
+        let mut sys = build_kv_system(false, notify_peer);
+        sys.send_local_message("c1", Message::json("PUT", &json!({"key": "x", "value": "1"})));
+        sys.step_until_no_events();
+        sys.step_for_duration(1.0);
+        sys.send_local_message("c2", Message::json("GET", &json!({"key": "x"})));
+        sys.step_until_no_events();
+        let trace = sys.logger().trace().to_vec();
+        trace
+    };
+
+    // the read of c2 is concurrent with the write of c1
+    let trace = run(false);
+    let history = History::from_trace(&trace, parse_request, parse_response);
+    assert!(check_linearizability(&KvStore::new(), &history).is_err());
+    assert_eq!(
+        check_causal_consistency(&KvStore::new(), &history, &CausalityGraph::from_trace(&trace)),
+        Ok(())
+    );
+
+    // c2 is notified about the completed write of c1 before reading, but does not observe it
+    let trace = run(true);
+    let history = History::from_trace(&trace, parse_request, parse_response);
+    let err = check_causal_consistency(&KvStore::new(), &history, &CausalityGraph::from_trace(&trace)).unwrap_err();
+    assert_eq!(err.ops(), &[0, 1]);
+    assert_eq!(err.clients(), &["c2", "c1"]);
+    assert!(err.message().contains("session of client c2"));
+
+    // causal order between clients is unknown without the trace
+    let mut history = History::new();
+    history.add("c1", KvOp::Put("x", 1), None, 0., 1.);
+    history.add("c2", KvOp::Get("x"), None, 2., 3.);
+    history.add("c2", KvOp::Get("x"), Some(1), 4., 5.);
+    assert_eq!(
+        check_causal_consistency(&KvStore::new(), &history, &CausalityGraph::from_trace(&[])),
+        Ok(())
+    );
+    // but the session order is respected
+    history.add("c2", KvOp::Get("x"), None, 6., 7.);
+    let err = check_causal_consistency(&KvStore::new(), &history, &CausalityGraph::from_trace(&[])).unwrap_err();
+    assert_eq!(err.ops(), &[2, 3]);
+    assert_eq!(err.clients(), &["c2"]);
+}
+
+#[test]
+fn session_guarantees() {
+    use SessionGuarantee::*;
+    let kv = KvStore::new();
+    let put = |key, value| KvOp::Put(key, value);
+
+    // client does not observe its own write
+    let mut history = History::new();
+    history.add("a", put("x", 1), None, 0., 1.);
+    history.add("a", KvOp::Get("x"), None, 2., 3.);
+    let err = check_session_guarantees(&kv, &history, &[ReadYourWrites]).unwrap_err();
+    assert_eq!(err.ops(), &[0, 1]);
+    assert_eq!(err.clients(), &["a"]);
+    assert!(err
+        .message()
+        .contains("(read-your-writes) are violated in session of client a"));
+    assert!(check_session_guarantees(&kv, &history, &[MonotonicReads, MonotonicWrites, WritesFollowReads]).is_ok());
+
+    // client observes the write of other client and then does not observe it
+    let mut history = History::new();
+    history.add("b", put("x", 1), None, 0., 1.);
+    history.add("a", KvOp::Get("x"), Some(1), 2., 3.);
+    history.add("a", KvOp::Get("x"), None, 4., 5.);
+    let err = check_session_guarantees(&kv, &history, &[MonotonicReads]).unwrap_err();
+    // the write may be relaxed, since the first read can not be explained otherwise
+    assert_eq!(err.ops(), &[1, 2]);
+    assert_eq!(err.clients(), &["a"]);
+    assert!(check_session_guarantees(&kv, &history, &[ReadYourWrites]).is_ok());
+
+    // writes of a client are observed in the other order
+    let mut history = History::new();
+    history.add("a", QueueOp::Enqueue(1), None, 0., 1.);
+    history.add("a", QueueOp::Enqueue(2), None, 2., 3.);
+    history.add("b", QueueOp::Dequeue, Some(2), 4., 5.);
+    let err = check_session_guarantees(&Queue::new(), &history, &[MonotonicWrites]).unwrap_err();
+    assert_eq!(err.ops(), &[0, 2]);
+    assert_eq!(err.clients(), &["b", "a"]);
+    assert!(check_session_guarantees(&Queue::new(), &history, &[ReadYourWrites, MonotonicReads]).is_ok());
+
+    // write of a depends on the write of b observed by a, but c observes only the former
+    let mut history = History::new();
+    history.add("b", put("x", 1), None, 0., 1.);
+    history.add("a", KvOp::Get("x"), Some(1), 2., 3.);
+    history.add("a", put("y", 1), None, 4., 5.);
+    history.add("c", KvOp::Get("y"), Some(1), 6., 7.);
+    history.add("c", KvOp::Get("x"), None, 8., 9.);
+    assert!(check_session_guarantees(&kv, &history, &[MonotonicReads]).is_ok());
+    let err = check_session_guarantees(&kv, &history, &[MonotonicReads, WritesFollowReads]).unwrap_err();
+    assert_eq!(err.ops(), &[1, 3, 4]);
+    assert_eq!(err.clients(), &["c", "a"]);
+    assert!(err.message().contains("(monotonic reads, writes-follow-reads)"));
+}
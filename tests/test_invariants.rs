@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use sugars::{boxed, rc, refcell};
+
+use anysystem::error::{InvariantViolation, StepError};
+use anysystem::logger::LogEntry;
+use anysystem::process::StringProcessState;
+use anysystem::{Context, Message, Process, ProcessState, System};
+
+use crate::common::{build_system, Client};
+
+/// Adds the received numbers.
+#[derive(Clone, Default)]
+struct Counter {
+    sum: u64,
+}
+
+impl Process for Counter {
+    fn on_message(&mut self, msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        self.sum += msg.data.parse::<u64>().unwrap();
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(self.sum.to_string()))
+    }
+}
+
+fn counter_sum(sys: &System) -> u64 {
+    let state = sys
+        .get_node("n2")
+        .unwrap()
+        .get_process("counter")
+        .unwrap()
+        .state()
+        .unwrap();
+    state.downcast_rc::<StringProcessState>().unwrap().parse().unwrap()
+}
+
+/// Builds the system with an invariant limiting the counter sum.
+fn counter_system(limit: u64) -> System {
+    let mut sys = build_system(
+        1,
+        vec![
+            ("client", boxed!(Client::new("counter"))),
+            ("counter", boxed!(Counter::default())),
+        ],
+    );
+    sys.add_invariant("sum limit", move |sys| {
+        let sum = counter_sum(sys);
+        if sum > limit {
+            Err(format!("sum {sum} exceeds {limit}"))
+        } else {
+            Ok(())
+        }
+    });
+    sys
+}
+
+fn send_numbers(sys: &mut System, numbers: &[u64]) -> Result<(), Box<StepError>> {
+    for number in numbers {
+        sys.send_local_message("client", Message::new("ADD", &number.to_string()));
+        sys.try_step_for_duration(2.)?;
+    }
+    Ok(())
+}
+
+fn expect_violation(res: Result<(), Box<StepError>>) -> InvariantViolation {
+    match *res.unwrap_err() {
+        StepError::InvariantViolation(violation) => violation,
+        err => panic!("expected invariant violation, got {err}"),
+    }
+}
+
+#[test]
+fn invariant_checked_after_each_step() {
+    let mut sys = counter_system(5);
+    let checks = rc!(refcell!(0));
+    let invariant_checks = checks.clone();
+    sys.add_invariant("count", move |_| {
+        *invariant_checks.borrow_mut() += 1;
+        Ok(())
+    });
+    send_numbers(&mut sys, &[1, 2]).unwrap();
+    assert_eq!(sys.time(), 4.);
+    assert_eq!(*checks.borrow(), 2);
+
+    let violation = expect_violation(send_numbers(&mut sys, &[3, 4]));
+    assert_eq!(violation.invariant, "sum limit");
+    assert_eq!(violation.error, "sum 6 exceeds 5");
+    assert_eq!(violation.time, 5.);
+    assert_eq!(violation.checkpoint_time, 3.);
+    // the simulation is stopped on the violation
+    assert_eq!(sys.time(), 5.);
+    assert_eq!(sys.sent_message_count("client"), 3);
+    // the trace contains the events since the last check
+    assert!(matches!(violation.trace.as_slice(), [.., LogEntry::MessageReceived { msg, .. }] if msg.data == "3"));
+    assert!(!violation
+        .trace
+        .iter()
+        .any(|entry| matches!(entry, LogEntry::MessageReceived { msg, .. } if msg.data == "2")));
+}
+
+#[test]
+fn invariant_check_interval() {
+    let mut sys = counter_system(6);
+    sys.set_invariant_check_interval(3);
+    let violation = expect_violation(send_numbers(&mut sys, &[1, 2, 3, 4, 5, 6]));
+    // the checks are performed after the 3rd and the 6th delivered messages
+    assert_eq!(violation.error, "sum 21 exceeds 6");
+    assert_eq!(violation.time, 11.);
+    assert_eq!(violation.checkpoint_time, 5.);
+    let received: Vec<_> = violation
+        .trace
+        .iter()
+        .filter_map(|entry| match entry {
+            LogEntry::MessageReceived { msg, .. } => Some(msg.data.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(received, ["4", "5", "6"]);
+}
+
+#[test]
+fn explicit_invariant_check() {
+    let mut sys = counter_system(0);
+    assert!(sys.check_invariants().is_ok());
+    sys.set_invariant_check_interval(100);
+    send_numbers(&mut sys, &[1]).unwrap();
+    let violation = sys.check_invariants().unwrap_err();
+    assert_eq!(violation.error, "sum 1 exceeds 0");
+    assert_eq!(violation.checkpoint_time, 0.);
+}
+
+#[test]
+#[should_panic(expected = "invariant 'sum limit' violated at 1.000 (last checked at 0.000): sum 7 exceeds 5")]
+fn invariant_violation_panics() {
+    let mut sys = counter_system(5);
+    sys.send_local_message("client", Message::new("ADD", "7"));
+    sys.step_until_no_events();
+}
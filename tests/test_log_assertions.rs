@@ -0,0 +1,215 @@
+use serde_json::json;
+use sugars::boxed;
+
+use anysystem::test::{EventLog, EventMatcher};
+use anysystem::{Context, EventLogEntry, Message, Process, ProcessEvent, System};
+
+use crate::common::build_system;
+
+/// Sends a PUT request to the server and retries it on timeout until receiving a response.
+#[derive(Clone, Default)]
+struct Client {
+    request: Option<Message>,
+    attempt: u64,
+}
+
+impl Client {
+    fn send_request(&mut self, ctx: &mut Context) {
+        self.attempt += 1;
+        let key = self.request.as_ref().unwrap().data.clone();
+        ctx.send(
+            Message::json("PUT", &json!({"key": key, "attempt": self.attempt})),
+            "server".to_string(),
+        );
+        ctx.set_timer("retry", 3.);
+    }
+}
+
+impl Process for Client {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.cancel_timer("retry");
+        ctx.log("done", &self.attempt.to_string());
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        self.request = Some(msg);
+        self.send_request(ctx);
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, ctx: &mut Context) -> Result<(), String> {
+        self.send_request(ctx);
+        Ok(())
+    }
+}
+
+/// Replies to requests ignoring the first one.
+#[derive(Clone, Default)]
+struct Server {
+    received: u64,
+}
+
+impl Process for Server {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        self.received += 1;
+        if self.received > 1 {
+            ctx.send(Message::new("PUT_OK", &msg.data), from);
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn run_system() -> System {
+    let mut sys = build_system(
+        1,
+        vec![
+            ("client", boxed!(Client::default())),
+            ("server", boxed!(Server::default())),
+        ],
+    );
+    sys.send_local_message("client", Message::new("PUT", "a"));
+    sys.step_until_no_events();
+    sys
+}
+
+#[test]
+fn event_log_assertions() {
+    let sys = run_system();
+    let log = sys.event_log("client");
+    let log = EventLog::new(&log);
+    let put = EventMatcher::message_sent().tip("PUT").to("server");
+
+    assert_eq!(log.count(&put), 2);
+    log.assert_count(&put, 2).unwrap();
+    log.assert_count(&put.clone().field("attempt", json!(2)), 1).unwrap();
+    let retry = log
+        .assert_contains(&put.clone().field("/key", json!("a")).between(2., 4.))
+        .unwrap();
+    assert_eq!(retry.time, 3.);
+    log.assert_count(&EventMatcher::timer_set().name("retry").before(3.), 2)
+        .unwrap();
+    log.assert_contains(&EventMatcher::user_event().name("done").after(5.))
+        .unwrap();
+    log.assert_contains(&put.clone().data_matches(|data| data["attempt"].as_u64() > Some(1)))
+        .unwrap();
+
+    log.assert_absent(&put.clone().field("attempt", json!(3))).unwrap();
+    log.assert_absent(&put.clone().after(4.)).unwrap();
+    log.assert_absent(&EventMatcher::message_sent().to("client")).unwrap();
+
+    let response = EventMatcher::message_received().tip("PUT_OK").from("server");
+    log.assert_before(&put, &response).unwrap();
+    log.assert_order(&[
+        &EventMatcher::local_message_received(),
+        &put,
+        &EventMatcher::timer_set(),
+        &put,
+        &response,
+        &EventMatcher::local_message_sent().tip("PUT_OK"),
+    ])
+    .unwrap();
+}
+
+#[test]
+fn failure_messages() {
+    let sys = run_system();
+    let log = sys.event_log("client");
+    let log = EventLog::new(&log);
+    let put = EventMatcher::message_sent().tip("PUT").to("server");
+
+    let err = log
+        .assert_contains(&put.clone().field("attempt", json!(3)))
+        .unwrap_err();
+    assert_eq!(
+        err.message,
+        "expected MessageSent PUT to server with /attempt = 3, entries of the same kind:"
+    );
+    assert_eq!(err.excerpt.len(), 3);
+    assert!(err.excerpt[0].starts_with("  #1        0.000 MessageSent client --> server PUT"));
+    assert_eq!(err.excerpt[1], "  ...");
+    assert!(err.excerpt[2].starts_with("  #3        3.000 MessageSent client --> server PUT"));
+
+    let err = log.assert_absent(&EventMatcher::kind("TimerCancelled")).unwrap_err();
+    assert_eq!(err.message, "unexpected TimerCancelled at #6");
+    assert!(err.excerpt[2].starts_with("> #6 "));
+    assert_eq!(err.excerpt.len(), 5);
+
+    let response = EventMatcher::message_received().tip("PUT_OK");
+    let err = log.assert_before(&response, &put).unwrap_err();
+    assert!(err
+        .message
+        .starts_with("expected MessageSent PUT to server after MessageReceived PUT_OK at #"));
+    assert!(err.message.ends_with(", found first at #1"));
+
+    let err = log.assert_count(&put, 1).unwrap_err();
+    assert_eq!(err.message, "expected 1 of MessageSent PUT to server, found 2");
+    assert_eq!(err.excerpt.iter().filter(|line| line.starts_with("> ")).count(), 2);
+
+    // the error can be returned from test functions
+    let res: Result<(), String> = log.assert_absent(&put).map_err(String::from);
+    assert!(res.unwrap_err().contains("\n> #1 "));
+}
+
+#[test]
+fn assert_before_uses_first_entries() {
+    let entries: Vec<_> = ["B", "A", "B"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| EventLogEntry {
+            time: i as f64,
+            event: ProcessEvent::UserEvent {
+                kind: name.to_string(),
+                data: String::new(),
+            },
+        })
+        .collect();
+    let log = EventLog::new(&entries);
+    let a = EventMatcher::user_event().name("A");
+    let b = EventMatcher::user_event().name("B");
+    log.assert_order(&[&a, &b]).unwrap();
+    log.assert_before(&b, &a).unwrap();
+
+    let err = log.assert_before(&a, &b).unwrap_err();
+    assert_eq!(
+        err.message,
+        "expected UserEvent named 'B' after UserEvent named 'A' at #1, found first at #0"
+    );
+    assert_eq!(err.excerpt.iter().filter(|line| line.starts_with("> ")).count(), 2);
+}
+
+#[test]
+fn trace_assertions() {
+    let sys = run_system();
+    let trace = sys.logger().trace().to_vec();
+    let trace = EventLog::new(&trace);
+
+    let put = EventMatcher::message_received().tip("PUT").from("client").to("server");
+    trace.assert_count(&put, 2).unwrap();
+    trace
+        .assert_contains(&EventMatcher::timer_fired().name("retry").from("client"))
+        .unwrap();
+    trace
+        .assert_order(&[
+            &put.clone().field("attempt", json!(1)),
+            &put.clone().field("attempt", json!(2)),
+            &EventMatcher::message_received().tip("PUT_OK").to("client"),
+        ])
+        .unwrap();
+    trace
+        .assert_absent(&EventMatcher::kind("MessageDropped").or_kind("ProcessFailed"))
+        .unwrap();
+
+    let err = trace.assert_contains(&EventMatcher::kind("NodeCrashed")).unwrap_err();
+    assert!(err.message.ends_with("last entries:"));
+    assert_eq!(err.excerpt.len(), 10);
+}
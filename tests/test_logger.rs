@@ -0,0 +1,281 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::BufReader;
+use std::rc::Rc;
+
+use sugars::boxed;
+
+use anysystem::logger::binary::{binary_to_json, json_to_binary, BinaryLogReader};
+use anysystem::logger::{ConsoleOutput, LogEntry, LogFilter, LogFormat, LogSink, LoggerConfig};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::build_system_with_logger_config;
+
+#[derive(Clone)]
+struct EchoProcess {
+    peer: String,
+}
+
+impl EchoProcess {
+    fn new(peer: &str) -> Self {
+        Self { peer: peer.to_string() }
+    }
+}
+
+impl Process for EchoProcess {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.log("echo", &msg.tip);
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, self.peer.clone());
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn build_system(config: LoggerConfig) -> System {
+    let mut sys = build_system_with_logger_config(
+        12345,
+        config,
+        vec![
+            ("proc1", boxed!(EchoProcess::new("proc2"))),
+            ("proc2", boxed!(EchoProcess::new("proc1"))),
+        ],
+    );
+    sys.send_local_message("proc1", Message::new("PING", "{}"));
+    sys.step_until_no_events();
+    sys
+}
+
+#[test]
+fn quiet_logger_keeps_trace() {
+    let sys = build_system(LoggerConfig::default().quiet());
+    let kinds: Vec<&str> = sys.logger().trace().iter().map(|e| e.kind()).collect();
+    assert!(kinds.contains(&"MessageSent"));
+    assert!(kinds.contains(&"LocalMessageSent"));
+}
+
+#[test]
+fn disabled_trace() {
+    let sys = build_system(LoggerConfig::default().console(ConsoleOutput::Stdout).trace(false));
+    assert!(sys.logger().trace().is_empty());
+}
+
+#[test]
+fn process_states_without_log_file() {
+    let sys = build_system(LoggerConfig::default().quiet().process_states(true));
+    assert!(sys
+        .logger()
+        .trace()
+        .iter()
+        .any(|e| matches!(e, LogEntry::ProcessStateUpdated { proc, .. } if proc == "proc2")));
+}
+
+#[test]
+fn log_filter() {
+    let sys = build_system(LoggerConfig::default().quiet());
+    let trace = sys.logger().trace().to_vec();
+
+    let filter = LogFilter::default().procs(["proc2"]).msg_types(["PING"]);
+    let selected: Vec<&str> = trace.iter().filter(|e| filter.matches(e)).map(|e| e.kind()).collect();
+    assert_eq!(selected, vec!["MessageSent", "MessageReceived", "LocalMessageSent"]);
+
+    let filter = LogFilter::default().kinds(["NodeStarted"]).nodes(["n1"]);
+    assert_eq!(trace.iter().filter(|e| filter.matches(e)).count(), 1);
+}
+
+#[test]
+fn trace_limit() {
+    let full_trace = build_system(LoggerConfig::default().quiet()).logger().trace().to_vec();
+    for limit in [0, 1, 3, full_trace.len() + 1] {
+        let sys = build_system(LoggerConfig::default().quiet().trace_limit(limit));
+        let start = full_trace.len().saturating_sub(limit);
+        assert_eq!(sys.logger().trace(), &full_trace[start..]);
+    }
+}
+
+struct CollectingSink {
+    entries: Rc<RefCell<Vec<LogEntry>>>,
+}
+
+impl LogSink for CollectingSink {
+    fn write(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        self.entries.borrow_mut().push(entry.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_sink() {
+    let mut sys = build_system(LoggerConfig::default().quiet().trace(false));
+    let entries = Rc::new(RefCell::new(Vec::new()));
+    sys.logger().add_sink(boxed!(CollectingSink {
+        entries: entries.clone()
+    }));
+    sys.send_local_message("proc2", Message::new("PONG", "{}"));
+    sys.step_until_no_events();
+
+    let entries = entries.borrow();
+    assert!(sys.logger().trace().is_empty());
+    assert_eq!(entries.first().map(|e| e.kind()), Some("LocalMessageReceived"));
+    assert!(entries.iter().any(|e| e.msg().is_some_and(|msg| msg.tip == "PONG")));
+    // process states are not logged unless enabled in the config
+    assert!(!entries.iter().any(|e| e.kind() == "ProcessStateUpdated"));
+}
+
+/// Accepts a limited number of events, then fails.
+struct FailingSink {
+    writes: Rc<RefCell<usize>>,
+    limit: usize,
+}
+
+impl LogSink for FailingSink {
+    fn write(&mut self, _entry: &LogEntry) -> std::io::Result<()> {
+        *self.writes.borrow_mut() += 1;
+        if *self.writes.borrow() > self.limit {
+            return Err(std::io::Error::other("disk full"));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sink_errors() {
+    let mut sys = build_system(LoggerConfig::default().quiet());
+    let writes = Rc::new(RefCell::new(0));
+    let entries = Rc::new(RefCell::new(Vec::new()));
+    sys.logger().add_sink(boxed!(FailingSink {
+        writes: writes.clone(),
+        limit: 2
+    }));
+    sys.logger().add_sink(boxed!(CollectingSink {
+        entries: entries.clone()
+    }));
+    sys.send_local_message("proc2", Message::new("PONG", "{}"));
+    sys.step_until_no_events();
+
+    // the failed sink is not used anymore, while other sinks receive all events
+    assert_eq!(*writes.borrow(), 3);
+    assert!(entries.borrow().len() > 3);
+    let err = sys.flush_log().unwrap_err();
+    assert_eq!(err.to_string(), "disk full");
+    assert!(sys.flush_log().is_err());
+
+    // the log file creation error is returned instead of panicking
+    let path = std::env::temp_dir().join("anysystem-missing-dir").join("log.jsonl");
+    let config = LoggerConfig::default().quiet().log_file(&path);
+    assert!(sys.logger().set_config(config).is_err());
+    sys.send_local_message("proc2", Message::new("PONG", "{}"));
+    sys.step_until_no_events();
+}
+
+#[test]
+fn flush_log() {
+    let path = std::env::temp_dir().join(format!("anysystem-test-{}-flush.jsonl", std::process::id()));
+    let sys = build_system(LoggerConfig::default().quiet().log_file(&path));
+    sys.flush_log().unwrap();
+    let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+    assert_eq!(lines, sys.logger().trace().len());
+    drop(sys);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn binary_log_truncated_string() {
+    // string value with inline reference and the length of 2^62 followed by 3 bytes
+    let mut data = b"ASLOG\x01\x06\x00".to_vec();
+    data.extend([0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40]);
+    data.extend(b"abc");
+    let err = BinaryLogReader::new(&data[..]).unwrap().read_value().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn binary_log_format() {
+    let dir = std::env::temp_dir();
+    let json_path = dir.join(format!("anysystem-test-{}.jsonl", std::process::id()));
+    let bin_path = dir.join(format!("anysystem-test-{}.bin", std::process::id()));
+    let converted_path = dir.join(format!("anysystem-test-{}-converted.jsonl", std::process::id()));
+
+    drop(build_system(LoggerConfig::default().quiet().log_file(&json_path)));
+    drop(build_system(
+        LoggerConfig::default()
+            .quiet()
+            .log_file(&bin_path)
+            .log_format(LogFormat::Binary),
+    ));
+
+    let json_entries: Vec<LogEntry> = std::fs::read_to_string(&json_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let bin_entries: Vec<LogEntry> = BinaryLogReader::new(BufReader::new(File::open(&bin_path).unwrap()))
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    assert!(!json_entries.is_empty());
+    assert_eq!(json_entries, bin_entries);
+    assert!(std::fs::metadata(&bin_path).unwrap().len() < std::fs::metadata(&json_path).unwrap().len());
+
+    // binary -> json
+    let count = binary_to_json(File::open(&bin_path).unwrap(), File::create(&converted_path).unwrap()).unwrap();
+    assert_eq!(count, json_entries.len());
+    let converted: Vec<serde_json::Value> = std::fs::read_to_string(&converted_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let original: Vec<serde_json::Value> = std::fs::read_to_string(&json_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(converted, original);
+
+    // json -> binary
+    let mut buf = Vec::new();
+    let count = json_to_binary(BufReader::new(File::open(&json_path).unwrap()), &mut buf).unwrap();
+    assert_eq!(count, json_entries.len());
+    let entries: Vec<LogEntry> = BinaryLogReader::new(buf.as_slice())
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    assert_eq!(entries, json_entries);
+
+    for path in [json_path, bin_path, converted_path] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn user_events() {
+    let sys = build_system(LoggerConfig::default().quiet());
+    let events: Vec<LogEntry> = sys
+        .logger()
+        .trace()
+        .iter()
+        .filter(|e| e.kind() == "UserEvent")
+        .cloned()
+        .collect();
+    assert_eq!(
+        events,
+        vec![LogEntry::UserEvent {
+            time: 1.0,
+            node: "n2".to_string(),
+            proc: "proc2".to_string(),
+            kind: "echo".to_string(),
+            data: "PING".to_string(),
+        }]
+    );
+    assert_eq!(
+        events[0].console_line(false).unwrap(),
+        "    1.000      proc2 *** echo       PING"
+    );
+}
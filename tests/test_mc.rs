@@ -283,6 +283,60 @@ impl Process for TimerResettingNode {
     }
 }
 
+#[derive(Clone)]
+struct PanickingNode {
+    values: Vec<u64>,
+    broken: bool,
+}
+
+impl PanickingNode {
+    fn get_value(&mut self, msg: Message, ctx: &mut Context) {
+        if msg.tip == "BREAK" {
+            self.broken = true;
+        } else {
+            let index: usize = msg.data.parse().unwrap();
+            ctx.send_local(Message::json("VALUE", &self.values[index]));
+        }
+    }
+}
+
+impl Process for PanickingNode {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        self.get_value(msg, ctx);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        self.get_value(msg, ctx);
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        assert!(!self.broken, "state is broken");
+        Ok(Rc::new(format!("{:?}", self.values)))
+    }
+}
+
+fn build_ping_system_with_panicking_node() -> System {
+    let mut sys = System::new(12345);
+    sys.add_node("node1");
+    sys.add_node("node2");
+    sys.add_process("process1", boxed!(PingMessageNode::new(str_vec!["process2"])), "node1");
+    sys.add_process(
+        "process2",
+        boxed!(PanickingNode {
+            values: vec![1, 2],
+            broken: false
+        }),
+        "node2",
+    );
+    sys
+}
+
 fn build_ping_system() -> System {
     let mut sys = System::new(12345);
     sys.add_node("node1");
@@ -1226,3 +1280,96 @@ fn crash_node(#[case] strategy_name: &str) {
     });
     assert!(result.is_ok());
 }
+
+#[rstest]
+#[case("dfs")]
+#[case("bfs")]
+fn process_panic(#[case] strategy_name: &str) {
+    let prune = boxed!(|_: &McState| None);
+    let goal = build_no_events_left_goal();
+    let invariant = boxed!(|_: &McState| Ok(()));
+    let strategy_config = build_strategy_config(prune, goal, invariant);
+
+    let result = run_mc!(
+        build_ping_system_with_panicking_node(),
+        strategy_config,
+        strategy_name,
+        move |mc_sys| {
+            mc_sys.send_local_message("node1", "process1", Message::new("GET", "2"));
+        }
+    );
+    let err = result.unwrap_err();
+    assert!(err
+        .message()
+        .starts_with("process 'process2' on node 'node2' panicked: index out of bounds"));
+    assert_eq!(
+        err.trace().last(),
+        Some(&LogEntry::McMessageReceived {
+            msg: Message::new("GET", "2"),
+            src: "process1".to_string(),
+            dst: "process2".to_string(),
+        })
+    );
+}
+
+#[rstest]
+#[case("dfs")]
+#[case("bfs")]
+fn process_panic_on_local_message(#[case] strategy_name: &str) {
+    let prune = boxed!(|_: &McState| None);
+    let goal = build_no_events_left_goal();
+    let invariant = boxed!(|_: &McState| Ok(()));
+    let strategy_config = build_strategy_config(prune, goal, invariant);
+
+    let result = run_mc!(
+        build_ping_system_with_panicking_node(),
+        strategy_config,
+        strategy_name,
+        move |mc_sys| {
+            mc_sys.send_local_message("node2", "process2", Message::new("GET", "2"));
+        }
+    );
+    let err = result.unwrap_err();
+    assert!(err
+        .message()
+        .starts_with("process 'process2' on node 'node2' panicked: index out of bounds"));
+    assert_eq!(
+        err.trace().last(),
+        Some(&LogEntry::McLocalMessageReceived {
+            msg: Message::new("GET", "2"),
+            proc: "process2".to_string(),
+        })
+    );
+}
+
+#[rstest]
+#[case("dfs")]
+#[case("bfs")]
+fn process_panic_on_state(#[case] strategy_name: &str) {
+    let prune = boxed!(|_: &McState| None);
+    let goal = build_no_events_left_goal();
+    let invariant = boxed!(|_: &McState| Ok(()));
+    let strategy_config = build_strategy_config(prune, goal, invariant);
+
+    let result = run_mc!(
+        build_ping_system_with_panicking_node(),
+        strategy_config,
+        strategy_name,
+        move |mc_sys| {
+            mc_sys.send_local_message("node1", "process1", Message::new("BREAK", "0"));
+        }
+    );
+    let err = result.unwrap_err();
+    assert_eq!(
+        err.message(),
+        "process 'process2' on node 'node2' panicked: state is broken"
+    );
+    assert_eq!(
+        err.trace().last(),
+        Some(&LogEntry::McMessageReceived {
+            msg: Message::new("BREAK", "0"),
+            src: "process1".to_string(),
+            dst: "process2".to_string(),
+        })
+    );
+}
@@ -0,0 +1,236 @@
+use sugars::boxed;
+
+use anysystem::error::{ProcessErrorPolicy, StepError};
+use anysystem::metrics::{MetricKind, Metrics};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::{build_system, Client};
+
+/// Replies to PING after a timer and counts the received requests.
+#[derive(Clone, Default)]
+struct Server {
+    pending: Vec<(Message, String)>,
+}
+
+impl Process for Server {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.increment_counter("requests", 1.);
+        ctx.set_timer(&format!("reply-{}", self.pending.len()), 1.);
+        self.pending.push((msg, from));
+        ctx.set_gauge("pending_requests", self.pending.len() as f64);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
+        let idx: usize = timer.trim_start_matches("reply-").parse().unwrap();
+        let (msg, from) = self.pending[idx].clone();
+        ctx.send(Message::new("PONG", &msg.data), from);
+        Ok(())
+    }
+}
+
+/// Updates the metric named after the received message as a counter or a gauge depending on the message type.
+#[derive(Clone)]
+struct Reporter;
+
+impl Process for Reporter {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        match msg.tip.as_str() {
+            "COUNTER" => ctx.increment_counter(&msg.data, 1.),
+            _ => ctx.set_gauge(&msg.data, 1.),
+        }
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn ping_system() -> System {
+    build_system(
+        123,
+        vec![
+            ("client", boxed!(Client::new("server"))),
+            ("server", boxed!(Server::default())),
+        ],
+    )
+}
+
+const TO_SERVER: [(&str, &str); 2] = [("src_node", "n1"), ("dst_node", "n2")];
+const TO_CLIENT: [(&str, &str); 2] = [("src_node", "n2"), ("dst_node", "n1")];
+const SERVER: [(&str, &str); 2] = [("node", "n2"), ("proc", "server")];
+
+#[test]
+fn network_and_process_metrics() {
+    let mut sys = ping_system();
+    sys.metrics().set_series_enabled(true);
+    let msg = Message::new("PING", "\"hello\"");
+    for _ in 0..3 {
+        sys.send_local_message("client", msg.clone());
+    }
+
+    sys.step_for_duration(0.5);
+    {
+        let metrics = sys.metrics();
+        assert_eq!(metrics.value("network_messages_sent", &TO_SERVER), Some(3.));
+        assert_eq!(
+            metrics.value("network_bytes_sent", &TO_SERVER),
+            Some(3. * msg.size() as f64)
+        );
+        assert_eq!(metrics.value("network_messages_in_flight", &TO_SERVER), Some(3.));
+        assert_eq!(
+            metrics.get("network_messages_in_flight", &TO_SERVER).unwrap().kind(),
+            MetricKind::Gauge
+        );
+    }
+
+    sys.step_for_duration(1.);
+    {
+        let metrics = sys.metrics();
+        assert_eq!(metrics.value("network_messages_in_flight", &TO_SERVER), Some(0.));
+        assert_eq!(metrics.value("process_messages_received", &SERVER), Some(3.));
+        assert_eq!(metrics.value("timers_set", &SERVER), Some(3.));
+        assert_eq!(metrics.value("timers_pending", &SERVER), Some(3.));
+        assert_eq!(metrics.value("user_requests", &SERVER), Some(3.));
+        assert_eq!(metrics.value("user_pending_requests", &SERVER), Some(3.));
+    }
+
+    sys.step_until_no_events();
+    let metrics = sys.metrics();
+    assert_eq!(metrics.value("timers_fired", &SERVER), Some(3.));
+    assert_eq!(metrics.value("timers_pending", &SERVER), Some(0.));
+    assert_eq!(metrics.value("process_messages_sent", &SERVER), Some(3.));
+    assert_eq!(metrics.value("network_messages_sent", &TO_CLIENT), Some(3.));
+    assert_eq!(metrics.value("network_messages_in_flight", &TO_CLIENT), Some(0.));
+    assert_eq!(metrics.total("network_messages_sent"), 6.);
+    assert_eq!(
+        metrics.total("network_messages_sent"),
+        sys.network().network_message_count() as f64
+    );
+    assert!(metrics.get("network_messages_dropped", &TO_SERVER).is_none());
+
+    // in-flight gauge goes up at send and down at delivery
+    let series = metrics.get("network_messages_in_flight", &TO_SERVER).unwrap().series();
+    assert_eq!(series.first().unwrap().1, 1.);
+    assert_eq!(series.last().unwrap(), &(1., 0.));
+}
+
+#[test]
+fn faults_and_crash() {
+    let mut sys = ping_system();
+    sys.network().set_drop_rate(0.5);
+    sys.network().set_dupl_rate(0.5);
+    for i in 0..100 {
+        sys.send_local_message("client", Message::new("PING", &format!("{i}")));
+    }
+    sys.step_for_duration(0.5);
+    {
+        let metrics = sys.metrics();
+        let dropped = metrics.value("network_messages_dropped", &TO_SERVER).unwrap();
+        let duplicated = metrics.value("network_messages_duplicated", &TO_SERVER).unwrap();
+        assert!(dropped > 0. && duplicated > 0.);
+        assert_eq!(
+            metrics.value("network_messages_in_flight", &TO_SERVER),
+            Some(100. - dropped + duplicated)
+        );
+    }
+
+    // messages to the crashed node and pending timers are discarded
+    sys.step_for_duration(1.);
+    assert!(sys.metrics().value("timers_pending", &SERVER).unwrap() > 0.);
+    sys.send_local_message("client", Message::new("PING", "\"last\""));
+    sys.crash_node("n2");
+    let metrics = sys.metrics();
+    assert_eq!(metrics.value("network_messages_in_flight", &TO_SERVER), Some(0.));
+    assert_eq!(metrics.value("timers_pending", &SERVER), Some(0.));
+    // the value histories are not recorded by default
+    assert!(metrics.iter().all(|(_, _, metric)| metric.series().is_empty()));
+}
+
+#[test]
+fn export() {
+    let mut sys = ping_system();
+    sys.metrics().set_series_enabled(true);
+    sys.send_local_message("client", Message::new("PING", "\"hello\""));
+    sys.step_until_no_events();
+
+    let snapshot = sys.metrics().snapshot(sys.time());
+    let csv = snapshot.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "time,metric,type,labels,value");
+    assert!(lines.contains(&r#"3,network_messages_sent,counter,"dst_node=""n2"";src_node=""n1""",1"#));
+    assert!(lines.contains(&r#"3,user_requests,counter,"node=""n2"";proc=""server""",1"#));
+
+    let text = snapshot.to_prometheus();
+    assert!(text.contains("# TYPE network_messages_in_flight gauge\n"));
+    assert!(text.contains("network_messages_sent{dst_node=\"n1\",src_node=\"n2\"} 1\n"));
+    assert_eq!(text.matches("# TYPE network_messages_sent").count(), 1);
+
+    let series = sys.metrics().series_csv();
+    let lines: Vec<&str> = series.lines().collect();
+    assert_eq!(lines[0], "time,metric,labels,value");
+    assert!(lines.contains(&r#"0,network_messages_in_flight,"dst_node=""n2"";src_node=""n1""",1"#));
+    assert!(lines.contains(&r#"1,network_messages_in_flight,"dst_node=""n2"";src_node=""n1""",0"#));
+}
+
+#[test]
+fn process_metrics_namespace_and_kind_conflict() {
+    let mut sys = build_system(1, vec![("reporter", boxed!(Reporter))]);
+    let labels = [("node", "n1"), ("proc", "reporter")];
+    // the process metric does not clash with the framework metric of another type
+    sys.send_local_message("reporter", Message::new("GAUGE", "timers_set"));
+    sys.send_local_message("reporter", Message::new("COUNTER", "requests"));
+    {
+        let metrics = sys.metrics();
+        assert_eq!(
+            metrics.get("user_timers_set", &labels).unwrap().kind(),
+            MetricKind::Gauge
+        );
+        assert_eq!(metrics.value("user_requests", &labels), Some(1.));
+    }
+
+    // changing the metric type fails the process handler and discards its actions
+    let err = sys.try_send_local_message("reporter", Message::new("GAUGE", "requests"));
+    let StepError::Process(err) = *err.unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert_eq!(err.error, "metric requests is already updated as counter");
+    assert_eq!(sys.read_local_messages("reporter").len(), 2);
+
+    sys.set_process_error_policy(ProcessErrorPolicy::CrashProcess);
+    sys.send_local_message("reporter", Message::new("COUNTER", "timers_set"));
+    assert!(sys.proc_is_crashed("reporter"));
+    assert_eq!(sys.metrics().value("user_requests", &labels), Some(1.));
+}
+
+#[test]
+fn export_escapes_names_and_labels() {
+    let mut metrics = Metrics::new();
+    metrics.set_series_enabled(true);
+    metrics.increment("requests,total", &[("proc", "a;b=\"c\"\n")], 1., 2.);
+    let snapshot = metrics.snapshot(2.);
+
+    let csv = snapshot.to_csv();
+    assert_eq!(
+        csv.lines().nth(1),
+        Some(r#"2,"requests,total",counter,"proc=""a;b=\""c\""\n""",1"#)
+    );
+    assert!(snapshot
+        .to_prometheus()
+        .contains("requests_total{proc=\"a;b=\\\"c\\\"\\n\"} 1\n"));
+    let series = metrics.series_csv();
+    assert_eq!(
+        series.lines().nth(1),
+        Some(r#"2,"requests,total","proc=""a;b=\""c\""\n""",1"#)
+    );
+}
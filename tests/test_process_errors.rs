@@ -0,0 +1,223 @@
+use sugars::boxed;
+
+use anysystem::error::{HandledEvent, ProcessErrorPolicy, StepError};
+use anysystem::logger::LogEntry;
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::{build_system, Client};
+
+/// Replies to requests, fails on bad requests and periodically sends heartbeats to the client.
+#[derive(Clone)]
+struct Server;
+
+impl Process for Server {
+    fn on_start(&mut self, ctx: &mut Context) -> Result<(), String> {
+        ctx.set_timer("heartbeat", 5.);
+        Ok(())
+    }
+
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        if msg.tip == "BAD" {
+            return Err(format!("bad request {}", msg.data));
+        }
+        ctx.send(Message::new("REPLY", &msg.data), from);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(Message::new("HEARTBEAT", "{}"), "client".to_string());
+        ctx.set_timer(&timer, 5.);
+        Ok(())
+    }
+}
+
+/// Panics on requests for missing values.
+#[derive(Clone)]
+struct Storage {
+    values: Vec<u64>,
+}
+
+impl Process for Storage {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        let index: usize = msg.data.parse().unwrap();
+        ctx.send(Message::json("REPLY", &self.values[index]), from);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Fails to handle local messages and optionally to start.
+#[derive(Clone)]
+struct Faulty {
+    fail_start: bool,
+}
+
+impl Process for Faulty {
+    fn on_start(&mut self, _ctx: &mut Context) -> Result<(), String> {
+        if self.fail_start {
+            return Err("cannot start".to_string());
+        }
+        Ok(())
+    }
+
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Err(format!("cannot handle {}", msg.tip))
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn build_failing_server() -> System {
+    build_system(
+        1,
+        vec![("client", boxed!(Client::new("server"))), ("server", boxed!(Server))],
+    )
+}
+
+#[test]
+fn try_step_returns_process_error() {
+    let mut sys = build_failing_server();
+    sys.send_local_message("client", Message::new("BAD", "1"));
+    let StepError::Process(err) = *sys.try_step_for_duration(10.).unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert_eq!(err.proc, "server");
+    assert_eq!(err.node, "n2");
+    assert_eq!(
+        err.event,
+        HandledEvent::Message {
+            msg: Message::new("BAD", "1"),
+            from: "client".to_string(),
+        }
+    );
+    assert_eq!(err.error, "bad request 1");
+    assert_eq!(err.time, 1.);
+    assert_eq!(sys.time(), 1.);
+    assert!(err
+        .to_string()
+        .starts_with("process 'server' on node 'n2' failed at 1.000"));
+    assert_eq!(sys.process_errors(), [err]);
+
+    // the simulation can be continued after the error
+    sys.send_local_message("client", Message::new("GOOD", "2"));
+    assert_eq!(sys.try_steps(2), Ok(true));
+    assert_eq!(sys.read_local_messages("client"), [Message::new("REPLY", "2")]);
+    assert!(!sys.proc_is_crashed("server"));
+}
+
+#[test]
+fn try_methods_return_process_errors() {
+    let mut sys = build_system(1, vec![("faulty", boxed!(Faulty { fail_start: false }))]);
+    let err = sys.try_send_local_message("faulty", Message::new("PING", "1"));
+    let StepError::Process(err) = *err.unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert_eq!(
+        err.event,
+        HandledEvent::LocalMessage {
+            msg: Message::new("PING", "1")
+        }
+    );
+    assert_eq!(err.error, "cannot handle PING");
+
+    let err = sys.try_add_process("starting", boxed!(Faulty { fail_start: true }), "n1");
+    let StepError::Process(err) = *err.unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert_eq!(err.proc, "starting");
+    assert_eq!(err.event, HandledEvent::Start);
+    assert_eq!(err.error, "cannot start");
+    assert_eq!(sys.process_errors().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Error when calling process")]
+fn step_panics_on_process_error() {
+    let mut sys = build_failing_server();
+    sys.send_local_message("client", Message::new("BAD", "1"));
+    sys.step_until_no_events();
+}
+
+#[test]
+fn failed_process_is_crashed() {
+    let mut sys = build_failing_server();
+    sys.set_process_error_policy(ProcessErrorPolicy::CrashProcess);
+    sys.send_local_message("client", Message::new("GOOD", "1"));
+    sys.send_local_message("client", Message::new("BAD", "2"));
+    sys.send_local_message("client", Message::new("GOOD", "3"));
+
+    // the crashed server does not reply and its heartbeat timer is cancelled
+    assert_eq!(sys.try_step_until_no_events(), Ok(()));
+    assert!(sys.proc_is_crashed("server"));
+    assert!(!sys.proc_node_is_crashed("server"));
+    assert_eq!(sys.read_local_messages("client"), [Message::new("REPLY", "1")]);
+    assert_eq!(sys.time(), 2.);
+
+    let errors = sys.process_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error, "bad request 2");
+    let trace = sys.logger().trace().to_vec();
+    let failed = trace
+        .iter()
+        .position(|e| matches!(e, LogEntry::ProcessFailed { proc, .. } if proc == "server"))
+        .unwrap();
+    assert!(matches!(
+        &trace[failed + 1],
+        LogEntry::MessageDropped { msg, .. } if msg.data == "3"
+    ));
+    // the trace of the error ends with the failure
+    assert_eq!(errors[0].trace, trace[..=failed]);
+}
+
+#[test]
+fn process_panic_is_caught() {
+    let mut sys = build_system(
+        1,
+        vec![
+            ("client", boxed!(Client::new("server"))),
+            ("server", boxed!(Storage { values: vec![10, 20] })),
+        ],
+    );
+    sys.send_local_message("client", Message::new("GET", "5"));
+
+    let StepError::Process(err) = *sys.try_step_until_no_events().unwrap_err() else {
+        panic!("expected process error");
+    };
+    assert!(err.panicked);
+    assert_eq!(err.proc, "server");
+    assert!(err.error.starts_with("index out of bounds"), "{}", err.error);
+    assert!(err.to_string().contains("panicked at"));
+    assert!(matches!(
+        err.trace.last(),
+        Some(LogEntry::ProcessFailed { error, .. }) if error.starts_with("panicked: index out of bounds")
+    ));
+    assert!(err
+        .trace
+        .iter()
+        .any(|e| matches!(e, LogEntry::MessageReceived { msg, .. } if msg.data == "5")));
+
+    // the panicked process is crashed and the simulation keeps running
+    sys.set_process_error_policy(ProcessErrorPolicy::CrashProcess);
+    sys.send_local_message("client", Message::new("GET", "7"));
+    sys.step_until_no_events();
+    assert!(sys.proc_is_crashed("server"));
+    assert_eq!(sys.process_errors().len(), 2);
+    assert!(sys.process_errors()[1].panicked);
+}
@@ -0,0 +1,239 @@
+use sugars::boxed;
+
+use anysystem::logger::LogEntry;
+use anysystem::test::{FaultKind, PropertyTest, Schedule, ScheduleConfig, ScheduleEntry, ScheduledAction, TestResult};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::build_system;
+
+/// Applies updates received from a client and forwards them to the backup without retries.
+#[derive(Clone)]
+struct Primary;
+
+impl Process for Primary {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg.clone(), "backup".to_string());
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct Backup;
+
+impl Process for Backup {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Rejects local messages with odd values.
+#[derive(Clone)]
+struct EvenOnly;
+
+impl Process for EvenOnly {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        if msg.data.parse::<u32>().unwrap() % 2 == 1 {
+            return Err(format!("odd value {}", msg.data));
+        }
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Builds a system with the primary and backup on nodes n1 and n2, and an empty node n3.
+fn build_replicated(seed: u64) -> System {
+    let mut sys = build_system(seed, vec![("primary", boxed!(Primary)), ("backup", boxed!(Backup))]);
+    sys.add_node("n3");
+    sys
+}
+
+fn check_replicated(sys: &mut System) -> TestResult {
+    let primary = sys.read_local_messages("primary").len();
+    let backup = sys.read_local_messages("backup").len();
+    if primary != backup {
+        return Err(format!("backup applied {backup} of {primary} updates"));
+    }
+    Ok(true)
+}
+
+fn config() -> ScheduleConfig {
+    ScheduleConfig::default()
+        .nodes(["n1", "n2", "n3"])
+        .clients(["primary"], |rng| {
+            Message::new("PUT", &format!("{}", rng.next_u32() % 100))
+        })
+        .operations(20)
+        .faults(10, &[FaultKind::Disconnect, FaultKind::Partition, FaultKind::Link])
+        .max_fault_duration(20.)
+}
+
+#[test]
+fn failing_schedule_is_shrunk() {
+    let failure = PropertyTest::new(config())
+        .run(0..10, build_replicated, check_replicated)
+        .unwrap_err();
+    assert!(failure.original.entries.len() > 2);
+    assert!(failure.shrink_runs > 0);
+
+    // a single update lost due to a single fault
+    let entries = &failure.schedule.entries;
+    assert_eq!(entries.len(), 2, "{failure}");
+    let updates = entries
+        .iter()
+        .filter(|e| matches!(e.action, ScheduledAction::LocalMessage { .. }))
+        .count();
+    assert_eq!(updates, 1);
+    assert!(entries.iter().any(|e| e.action.duration().is_some()));
+    assert_eq!(failure.error, "backup applied 0 of 1 updates");
+    assert!(failure
+        .trace
+        .iter()
+        .any(|entry| matches!(entry, LogEntry::MessageDropped { .. })));
+
+    let (result, trace) = PropertyTest::replay(failure.seed, &failure.schedule, build_replicated, check_replicated);
+    assert_eq!(result, Err(failure.error.clone()));
+    assert_eq!(trace.len(), failure.trace.len());
+
+    let rerun = PropertyTest::new(config())
+        .run(0..10, build_replicated, check_replicated)
+        .unwrap_err();
+    assert_eq!(rerun.seed, failure.seed);
+    assert_eq!(rerun.schedule, failure.schedule);
+}
+
+#[test]
+fn passing_schedules() {
+    let config = config().faults(0, &[]);
+    assert!(PropertyTest::new(config)
+        .run(0..10, build_replicated, check_replicated)
+        .is_ok());
+}
+
+#[test]
+fn overlapping_faults() {
+    let put = |time: f64| ScheduleEntry {
+        time,
+        action: ScheduledAction::LocalMessage {
+            proc: "primary".to_string(),
+            msg: Message::new("PUT", "1"),
+        },
+    };
+    let disconnect = |time: f64, duration: f64| ScheduleEntry {
+        time,
+        action: ScheduledAction::DisconnectNode {
+            node: "n2".to_string(),
+            duration,
+        },
+    };
+    let schedule = Schedule {
+        entries: vec![put(1.), disconnect(2., 10.), disconnect(5., 15.), put(15.), put(25.)],
+        duration: 30.,
+    };
+
+    let mut sys = build_replicated(1);
+    assert!(schedule.run(&mut sys).is_ok());
+    assert_eq!(sys.time(), 30.);
+    assert_eq!(sys.read_local_messages("primary").len(), 3);
+    // the update at 15 is lost because the second disconnection is still active
+    assert_eq!(sys.read_local_messages("backup").len(), 2);
+    assert!(sys.network().get_drop_incoming().is_empty());
+}
+
+#[test]
+fn recorded_run_is_reproduced() {
+    let mut sys = build_replicated(3);
+    let (schedule, run) = config().run_random(&mut sys, 3);
+    assert!(run.is_ok());
+    let result = check_replicated(&mut sys);
+    let trace = sys.logger().trace().to_vec();
+    assert!(!schedule.entries.is_empty());
+
+    let (replayed, replayed_trace) = PropertyTest::replay(3, &schedule, build_replicated, check_replicated);
+    assert_eq!(replayed, result);
+    assert_eq!(replayed_trace, trace);
+}
+
+#[test]
+fn faults_depend_on_system_state() {
+    let config = config().faults(10, &[FaultKind::Crash]);
+    let mut sys = build_replicated(1);
+    let (schedule, run) = config.run_random(&mut sys, 1);
+    assert!(run.is_ok());
+    let crashed: Vec<&str> = schedule
+        .entries
+        .iter()
+        .filter_map(|e| match &e.action {
+            ScheduledAction::CrashNode { node } => Some(node.as_str()),
+            _ => None,
+        })
+        .collect();
+    // the crashed nodes are not chosen again
+    assert_eq!(crashed.len(), 3);
+    let mut distinct = crashed.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 3);
+    // no local messages are sent to the client on the crashed node
+    let primary_crash = schedule
+        .entries
+        .iter()
+        .find(|e| e.action == ScheduledAction::CrashNode { node: "n1".to_string() })
+        .unwrap()
+        .time;
+    assert!(!schedule
+        .entries
+        .iter()
+        .any(|e| matches!(e.action, ScheduledAction::LocalMessage { .. }) && e.time > primary_crash));
+}
+
+#[test]
+fn process_error_fails_run() {
+    let build = |seed| build_system(seed, vec![("even", boxed!(EvenOnly))]);
+    let config = ScheduleConfig::default()
+        .clients(["even"], |rng| {
+            Message::new("PUT", &format!("{}", rng.next_u32() % 100))
+        })
+        .faults(0, &[]);
+    let failure = PropertyTest::new(config).run(0..10, build, |_| Ok(true)).unwrap_err();
+
+    // the run is stopped by the rejected message, which is the only action left after shrinking
+    let entries = &failure.schedule.entries;
+    assert_eq!(entries.len(), 1, "{failure}");
+    let ScheduledAction::LocalMessage { msg, .. } = &entries[0].action else {
+        panic!("unexpected action {}", entries[0].action);
+    };
+    assert!(
+        failure.error.ends_with(&format!("odd value {}", msg.data)),
+        "{}",
+        failure.error
+    );
+    assert!(failure
+        .trace
+        .iter()
+        .any(|entry| matches!(entry, LogEntry::ProcessFailed { .. })));
+}
@@ -3,13 +3,14 @@ use std::collections::HashSet;
 use std::env;
 use sugars::boxed;
 
+use anysystem::logger::LogEntry;
 use anysystem::process::StringProcessState;
 use anysystem::python::PyProcessFactory;
 use anysystem::{Message, System};
 
 use anysystem::mc::predicates::{goals, invariants, prunes};
 use anysystem::mc::strategies::{Bfs, Dfs};
-use anysystem::mc::{ModelChecker, StrategyConfig};
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
 use anysystem::run_mc;
 
 fn build_system(impl_file: &str) -> System {
@@ -86,3 +87,33 @@ fn python_runtime_error(#[case] strategy_name: &str) {
         system.send_local_message("client-node", "client", Message::new("PING", &data2));
     });
 }
+
+#[rstest]
+#[case("dfs")]
+#[case("bfs")]
+fn python_user_events(#[case] strategy_name: &str) {
+    let mut system = build_system("user_events.py");
+    let data = r#"{"value": 0}"#.to_string();
+
+    system.send_local_message("client", Message::new("PING", &data));
+    system.step_until_no_events();
+    assert!(system.logger().trace().iter().any(|e| matches!(e,
+        LogEntry::UserEvent { proc, kind, data, .. } if proc == "client" && kind == "pong" && data == r#"{"value": 0}"#)));
+
+    let system = build_system("user_events.py");
+    let strategy_config = StrategyConfig::default()
+        .prune(prunes::sent_messages_limit(4))
+        .goal(boxed!(|state: &McState| {
+            state
+                .current_run_trace()
+                .iter()
+                .any(|e| matches!(e, LogEntry::McUserEvent { proc, kind, .. } if proc == "client" && kind == "pong"))
+                .then(|| "pong logged".to_string())
+        }))
+        .invariant(invariants::state_depth(20));
+
+    let result = run_mc!(&system, strategy_config, strategy_name, |system| {
+        system.send_local_message("client-node", "client", Message::new("PING", &data));
+    });
+    assert!(result.is_ok());
+}
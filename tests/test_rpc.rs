@@ -0,0 +1,210 @@
+use std::collections::BTreeSet;
+
+use sugars::{boxed, rc, refcell};
+
+use anysystem::logger::LogEntry;
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::rpc::CallTag;
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::build_system;
+
+/// Calls the server on each local message and retries the call on timeout, forwards plain messages to local.
+#[derive(Clone)]
+struct Client {
+    timeout: f64,
+    max_retries: u64,
+    retries: u64,
+}
+
+impl Client {
+    fn new(timeout: f64, max_retries: u64) -> Self {
+        Self {
+            timeout,
+            max_retries,
+            retries: 0,
+        }
+    }
+}
+
+impl Process for Client {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send_local(msg);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.call("server", msg, self.timeout);
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_response(&mut self, call_id: u64, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        assert_eq!(from, "server");
+        ctx.send_local(Message::json(&msg.tip, &(call_id, msg.data)));
+        Ok(())
+    }
+
+    fn on_call_timeout(
+        &mut self,
+        call_id: u64,
+        request: Message,
+        dst: String,
+        ctx: &mut Context,
+    ) -> Result<(), String> {
+        if self.retries < self.max_retries {
+            self.retries += 1;
+            ctx.call(&dst, request, self.timeout);
+        } else {
+            ctx.send_local(Message::json("TIMEOUT", &(call_id, request.data)));
+        }
+        Ok(())
+    }
+}
+
+/// Replies to requests, ignores the first `drop_count` requests and delays the replies by `reply_delay`.
+/// Local messages are sent to the client as plain messages.
+#[derive(Clone)]
+struct Server {
+    drop_count: u64,
+    reply_delay: f64,
+    deferred: Vec<(String, u64, String)>,
+}
+
+impl Server {
+    fn new(drop_count: u64, reply_delay: f64) -> Self {
+        Self {
+            drop_count,
+            reply_delay,
+            deferred: Vec::new(),
+        }
+    }
+}
+
+impl Process for Server {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        assert_eq!(msg.tip, "PING");
+        if self.drop_count > 0 {
+            self.drop_count -= 1;
+        } else if self.reply_delay > 0. {
+            self.deferred.push((from, ctx.current_call().unwrap(), msg.data));
+            ctx.set_timer("reply", self.reply_delay);
+        } else {
+            ctx.reply(Message::new("PONG", &msg.data));
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, "client".to_string());
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, ctx: &mut Context) -> Result<(), String> {
+        for (from, call_id, data) in self.deferred.drain(..) {
+            ctx.reply_to(&from, call_id, Message::new("PONG", &data));
+        }
+        Ok(())
+    }
+}
+
+fn call_system(client: Client, server: Server) -> System {
+    build_system(1, vec![("client", boxed!(client)), ("server", boxed!(server))])
+}
+
+#[test]
+fn call_response() {
+    let mut sys = call_system(Client::new(3., 0), Server::new(0, 0.));
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    sys.send_local_message("client", Message::new("PING", "2"));
+    sys.step_until_no_events();
+    assert_eq!(
+        sys.read_local_messages("client"),
+        [Message::json("PONG", &(0, "1")), Message::json("PONG", &(1, "2"))]
+    );
+    // the timeout timers are cancelled on responses
+    assert_eq!(sys.time(), 4.);
+    assert!(sys
+        .logger()
+        .trace()
+        .iter()
+        .any(|e| matches!(e, LogEntry::MessageSent { msg, call, .. }
+            if msg.tip == "PING" && *call == Some(CallTag::Request(1)))));
+}
+
+#[test]
+fn plain_messages_are_not_responses() {
+    let mut sys = call_system(Client::new(3., 0), Server::new(0, 0.));
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.send_local_message("server", Message::new("PONG#reply-0", "fake"));
+    sys.step_until_no_events();
+    // the message type is delivered unchanged and does not complete the pending call
+    assert_eq!(
+        sys.read_local_messages("client"),
+        [Message::new("PONG#reply-0", "fake"), Message::json("PONG", &(0, "1"))]
+    );
+}
+
+#[test]
+fn call_timeout_and_retry() {
+    let mut sys = call_system(Client::new(3., 2), Server::new(2, 0.));
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::json("PONG", &(2, "1"))]);
+    assert_eq!(sys.time(), 8.);
+
+    let mut sys = call_system(Client::new(3., 1), Server::new(2, 0.));
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::json("TIMEOUT", &(1, "1"))]);
+    assert_eq!(sys.time(), 6.);
+}
+
+#[test]
+fn late_response_is_ignored() {
+    let mut sys = call_system(Client::new(3., 0), Server::new(0, 5.));
+    sys.send_local_message("client", Message::new("PING", "1"));
+    sys.step_until_no_events();
+    assert_eq!(sys.read_local_messages("client"), [Message::json("TIMEOUT", &(0, "1"))]);
+    // the deferred response is delivered but not passed to the client
+    assert_eq!(sys.time(), 7.);
+    assert_eq!(sys.received_message_count("client"), 1);
+}
+
+#[test]
+fn call_in_model_checking() {
+    let sys = call_system(Client::new(3., 1), Server::new(0, 0.));
+    let outcomes = rc!(refcell!(BTreeSet::new()));
+    let final_outcomes = outcomes.clone();
+    let goal = boxed!(move |state: &McState| {
+        if state.events.is_empty() {
+            let outbox = &state.node_states["n1"].proc_states["client"].local_outbox;
+            final_outcomes.borrow_mut().insert(outbox.clone());
+            Some("final".to_string())
+        } else {
+            None
+        }
+    });
+    // the client completes each call exactly once
+    let invariant = boxed!(|state: &McState| {
+        let outbox = &state.node_states["n1"].proc_states["client"].local_outbox;
+        if outbox.len() > 1 {
+            Err(format!("multiple call results: {outbox:?}"))
+        } else {
+            Ok(())
+        }
+    });
+    let config = StrategyConfig::default().goal(goal).invariant(invariant);
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n1", "client", Message::new("PING", "1"))
+    });
+    assert!(result.is_ok());
+    let outcomes = outcomes.borrow();
+    assert!(outcomes.iter().all(|outbox| outbox.len() == 1), "{outcomes:?}");
+    assert!(outcomes.contains(&vec![Message::json("PONG", &(0, "1"))]));
+}
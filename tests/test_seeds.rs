@@ -0,0 +1,67 @@
+use sugars::boxed;
+
+use anysystem::test::{SeedRunner, TestResult};
+use anysystem::Message;
+
+use crate::common::{build_system, Client, EchoServer};
+
+/// Sends a message over an unreliable network and fails if the reply is lost.
+fn echo(seed: u64) -> TestResult {
+    let mut sys = build_system(
+        seed,
+        vec![
+            ("client", boxed!(Client::new("server"))),
+            ("server", boxed!(EchoServer)),
+        ],
+    );
+    sys.network().set_drop_rate(0.3);
+    sys.send_local_message("client", Message::new("PING", "\"hello\""));
+    sys.step_until_no_events();
+    if sys.read_local_messages("client").is_empty() {
+        return Err(format!("reply is lost at {}", sys.time()));
+    }
+    Ok(true)
+}
+
+#[test]
+fn parallel_runs_are_deterministic() {
+    let serial = SeedRunner::new(0..200).threads(1).run(echo);
+    let parallel = SeedRunner::new(0..200).threads(4).run(echo);
+    assert_eq!(serial, parallel);
+
+    assert_eq!(parallel.results().len(), 200);
+    assert_eq!(parallel.passed_count() + parallel.failed_count(), 200);
+    assert!(parallel.failed_count() > 0 && parallel.passed_count() > 0);
+    for (seed, error) in parallel.failures() {
+        assert_eq!(Err(error.to_string()), echo(seed));
+    }
+
+    let summary = parallel.to_string();
+    assert!(summary.starts_with(&format!(
+        "Passed {} from 200 seeds\nFailed seeds:\n",
+        parallel.passed_count()
+    )));
+    assert_eq!(summary.lines().count(), 2 + parallel.failed_count());
+}
+
+#[test]
+fn panicking_seed_is_reported() {
+    let report = SeedRunner::new(0..10).threads(2).run(|seed| {
+        assert_ne!(seed, 3, "unlucky seed");
+        Ok(true)
+    });
+    assert_eq!(report.passed_count(), 9);
+    let failures = report.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, 3);
+    assert!(failures[0]
+        .1
+        .starts_with("panicked: assertion `left != right` failed: unlucky seed"));
+}
+
+#[test]
+fn all_seeds_pass() {
+    let report = SeedRunner::new([1, 5, 7]).run(|_| Ok(true));
+    assert!(report.all_passed());
+    assert_eq!(report.to_string(), "Passed 3 from 3 seeds");
+}
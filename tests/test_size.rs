@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, HashMap};
+use std::mem::size_of;
+use std::rc::Rc;
+
+use sugars::boxed;
+
+use anysystem::mc::predicates::{goals, invariants};
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{ModelChecker, StrategyConfig};
+use anysystem::process::StringProcessState;
+use anysystem::size::{serialized_size, SizeOf};
+use anysystem::{Context, Message, Process, ProcessState, System};
+
+use crate::common::build_system;
+
+/// Stores the data of received messages until receiving a local CLEAR message.
+#[derive(Clone, Default)]
+struct Storage {
+    values: Vec<String>,
+}
+
+impl Process for Storage {
+    fn on_message(&mut self, msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        self.values.push(msg.data);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        if msg.tip == "CLEAR" {
+            self.values.clear();
+        } else {
+            ctx.send(msg, "storage".to_string());
+        }
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn size(&self) -> u64 {
+        self.values.size_of()
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(self.values.join(",")))
+    }
+
+    fn set_state(&mut self, state: Rc<dyn ProcessState>) -> Result<(), String> {
+        let state = state.downcast_rc::<StringProcessState>().unwrap();
+        self.values = state.split(',').filter(|v| !v.is_empty()).map(String::from).collect();
+        Ok(())
+    }
+}
+
+/// Counts the received messages, reports the maximum size itself instead of implementing `Process::size`.
+#[derive(Clone, Default)]
+struct SelfTracking {
+    received: u64,
+}
+
+impl Process for SelfTracking {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        self.received += 1;
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn max_size(&mut self) -> u64 {
+        self.received * 100
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(self.received.to_string()))
+    }
+
+    fn set_state(&mut self, state: Rc<dyn ProcessState>) -> Result<(), String> {
+        self.received = state.downcast_rc::<StringProcessState>().unwrap().parse().unwrap();
+        Ok(())
+    }
+}
+
+fn storage_system() -> System {
+    build_system(
+        1,
+        vec![
+            ("client", boxed!(Storage::default())),
+            ("storage", boxed!(Storage::default())),
+        ],
+    )
+}
+
+#[test]
+fn size_of_values() {
+    let string_size = size_of::<String>() as u64;
+    let vec_size = size_of::<Vec<String>>() as u64;
+    assert_eq!(42u64.size_of(), 8);
+    assert_eq!("abc".to_string().size_of(), string_size + 3);
+    assert_eq!(
+        vec!["ab".to_string(), "c".to_string()].size_of(),
+        vec_size + 2 * string_size + 3
+    );
+    assert_eq!(Some(1u8).size_of(), 2);
+    assert_eq!(None::<String>.size_of(), size_of::<Option<String>>() as u64);
+    assert_eq!((1u32, 2u16).size_of(), 6);
+    assert_eq!(Message::new("PING", "data").size_of(), 2 * string_size + 8);
+
+    let map = HashMap::from([(1u64, "a".to_string()), (2, "bc".to_string())]);
+    assert_eq!(
+        map.size_of(),
+        size_of::<HashMap<u64, String>>() as u64 + 16 + 2 * string_size + 3
+    );
+    let map = BTreeMap::from([(1u64, vec![1u32, 2, 3])]);
+    assert_eq!(
+        map.size_of(),
+        size_of::<BTreeMap<u64, Vec<u32>>>() as u64 + 8 + size_of::<Vec<u32>>() as u64 + 12
+    );
+
+    assert_eq!(serialized_size(&vec!["ab", "c"]), r#"["ab","c"]"#.len() as u64);
+}
+
+#[test]
+fn max_size_is_tracked() {
+    let mut sys = storage_system();
+    assert_eq!(sys.max_size("storage"), size_of::<Vec<String>>() as u64);
+    for data in ["a", "bb", "ccc"] {
+        sys.send_local_message("client", Message::new("PUT", data));
+    }
+    sys.step_until_no_events();
+    let max_size = size_of::<Vec<String>>() as u64 + 3 * size_of::<String>() as u64 + 6;
+    assert_eq!(sys.max_size("storage"), max_size);
+
+    // the maximum is kept after the data is removed
+    sys.send_local_message("storage", Message::new("CLEAR", ""));
+    assert_eq!(sys.max_size("storage"), max_size);
+}
+
+#[test]
+fn process_size_limit_invariant() {
+    let sys = storage_system();
+    let limit = size_of::<Vec<String>>() as u64 + 2 * size_of::<String>() as u64 + 10;
+    let run = |count: usize| {
+        let config = StrategyConfig::default()
+            .goal(goals::no_events())
+            .invariant(invariants::process_size_limit(limit));
+        ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+            for _ in 0..count {
+                sys.send_local_message("n1", "client", Message::new("PUT", "data"));
+            }
+        })
+    };
+    assert!(run(2).is_ok());
+
+    let err = run(3).unwrap_err();
+    assert!(err
+        .message()
+        .starts_with("size of process 'storage' on node 'n2' exceeds the limit"));
+}
+
+#[test]
+fn process_size_limit_with_reported_max_size() {
+    let sys = build_system(
+        1,
+        vec![
+            ("client", boxed!(Storage::default())),
+            ("storage", boxed!(SelfTracking::default())),
+        ],
+    );
+    let run = |count: usize| {
+        let config = StrategyConfig::default()
+            .goal(goals::no_events())
+            .invariant(invariants::process_size_limit(250));
+        ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+            for _ in 0..count {
+                sys.send_local_message("n1", "client", Message::new("PUT", "data"));
+            }
+        })
+    };
+    assert!(run(2).is_ok());
+
+    let err = run(3).unwrap_err();
+    assert_eq!(
+        err.message(),
+        "size of process 'storage' on node 'n2' exceeds the limit: 300 > 250 bytes"
+    );
+}
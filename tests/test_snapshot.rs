@@ -0,0 +1,191 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+use sugars::{boxed, rc, refcell};
+
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::process::Snapshot;
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::{build_system, Client};
+
+/// Reports the order of received messages after receiving the expected number of them.
+///
+/// Does not implement `Process::state` and `Process::set_state`.
+#[derive(Clone, Serialize)]
+struct Receiver {
+    expected: usize,
+    received: Vec<String>,
+}
+
+impl Process for Receiver {
+    fn on_message(&mut self, msg: Message, _from: String, ctx: &mut Context) -> Result<(), String> {
+        self.received.push(msg.data);
+        if self.received.len() == self.expected {
+            ctx.send_local(Message::new("DONE", &self.received.join(",")));
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Counts the received messages by key.
+#[derive(Clone, Serialize)]
+struct Tally<K> {
+    counts: HashMap<K, u64>,
+}
+
+impl<K: Clone + Serialize + 'static> Process for Tally<K> {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Builds a system with three senders on nodes n1, n2, n3 and the receiver on node n4.
+fn receiver_system(receiver: Box<dyn Process>) -> System {
+    build_system(
+        1,
+        vec![
+            ("sender1", boxed!(Client::new("receiver"))),
+            ("sender2", boxed!(Client::new("receiver"))),
+            ("sender3", boxed!(Client::new("receiver"))),
+            ("receiver", receiver),
+        ],
+    )
+}
+
+fn receiver() -> Receiver {
+    Receiver {
+        expected: 3,
+        received: Vec::new(),
+    }
+}
+
+/// Returns the reported orders of messages and the number of explored intermediate states.
+fn run_mc(sys: &System) -> (BTreeSet<String>, usize) {
+    let orders = rc!(refcell!(BTreeSet::new()));
+    let states = rc!(refcell!(0));
+    let (goal_orders, invariant_states) = (orders.clone(), states.clone());
+    let goal = boxed!(move |state: &McState| {
+        let outbox = &state.node_states["n4"].proc_states["receiver"].local_outbox;
+        let done = outbox.first()?;
+        goal_orders.borrow_mut().insert(done.data.clone());
+        Some("done".to_string())
+    });
+    let invariant = boxed!(move |_: &McState| {
+        *invariant_states.borrow_mut() += 1;
+        Ok(())
+    });
+    let config = StrategyConfig::default().goal(goal).invariant(invariant);
+    let result = ModelChecker::new(sys).run_with_change::<Bfs>(config, |sys| {
+        for (i, (node, proc)) in [("n1", "sender1"), ("n2", "sender2"), ("n3", "sender3")]
+            .iter()
+            .enumerate()
+        {
+            sys.send_local_message(*node, *proc, Message::new("MSG", &i.to_string()));
+        }
+    });
+    assert!(result.is_ok());
+    let orders = orders.borrow().clone();
+    let states = *states.borrow();
+    (orders, states)
+}
+
+#[test]
+fn snapshot_restores_process_state() {
+    let sys = receiver_system(boxed!(Snapshot::new(receiver())));
+    let (orders, _) = run_mc(&sys);
+    // all orders are reported, so the receiver state is correctly restored when switching between branches
+    assert_eq!(orders.len(), 6);
+    assert!(orders.iter().all(|order| order.split(',').count() == 3));
+}
+
+#[test]
+fn snapshot_view() {
+    let (_, serialized_states) = run_mc(&receiver_system(boxed!(Snapshot::new(receiver()))));
+
+    // the states which differ only in the order of received messages are considered equal
+    let view = |proc: &Receiver| proc.received.iter().cloned().collect::<BTreeSet<_>>();
+    let (orders, view_states) = run_mc(&receiver_system(boxed!(Snapshot::with_view(receiver(), view))));
+    assert!(view_states < serialized_states, "{view_states} >= {serialized_states}");
+    assert!(!orders.is_empty());
+}
+
+#[test]
+fn snapshot_in_simulation() {
+    let mut sys = receiver_system(boxed!(Snapshot::new(receiver())));
+    let state = sys
+        .get_node("n4")
+        .unwrap()
+        .get_process("receiver")
+        .unwrap()
+        .state()
+        .unwrap();
+    assert_eq!(format!("{state:?}"), r#""{\"expected\":3,\"received\":[]}""#);
+
+    sys.send_local_message("sender1", Message::new("MSG", "1"));
+    sys.step_until_no_events();
+    let received = sys
+        .get_node("n4")
+        .unwrap()
+        .get_process("receiver")
+        .unwrap()
+        .state()
+        .unwrap();
+    assert_ne!(format!("{received:?}"), format!("{state:?}"));
+
+    sys.get_mut_node("n4")
+        .unwrap()
+        .set_process_state("receiver", state.clone());
+    let restored = sys
+        .get_node("n4")
+        .unwrap()
+        .get_process("receiver")
+        .unwrap()
+        .state()
+        .unwrap();
+    assert!(restored.eq_with_dyn(&*state));
+}
+
+#[test]
+fn snapshot_view_is_canonical() {
+    let keys: Vec<_> = (0..20).map(|i| format!("key{i}")).collect();
+    let tally1 = Tally {
+        counts: keys.iter().map(|key| (key.clone(), 1)).collect(),
+    };
+    let tally2 = Tally {
+        counts: keys.iter().rev().map(|key| (key.clone(), 1)).collect(),
+    };
+    let state1 = Snapshot::new(tally1).state().unwrap();
+    let state2 = Snapshot::new(tally2).state().unwrap();
+    assert!(state1.eq_with_dyn(&*state2));
+    assert_eq!(format!("{state1:?}"), format!("{state2:?}"));
+}
+
+#[test]
+fn snapshot_view_error() {
+    let tally = Tally {
+        counts: HashMap::from([((1, 2), 1)]),
+    };
+    let err = Snapshot::new(tally).state().unwrap_err();
+    assert!(
+        err.starts_with("failed to serialize process: key must be a string"),
+        "{err}"
+    );
+}
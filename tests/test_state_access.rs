@@ -0,0 +1,168 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use sugars::boxed;
+
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::process::ProcessAccessError;
+use anysystem::{Context, Message, Process, ProcessState, System};
+
+use crate::common::build_system;
+
+/// Forwards local messages to the counters, the state is the number of sent messages encoded in JSON.
+#[derive(Clone, Default)]
+struct Client {
+    sent: u64,
+}
+
+impl Process for Client {
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        for counter in ["counter1", "counter2"] {
+            ctx.send(msg.clone(), counter.to_string());
+            self.sent += 1;
+        }
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(format!(r#"{{"sent": {}}}"#, self.sent)))
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientState {
+    sent: u64,
+}
+
+/// Adds the received numbers.
+#[derive(Clone, Debug, Default)]
+struct Counter {
+    sum: u64,
+}
+
+impl Process for Counter {
+    fn on_message(&mut self, msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        self.sum += msg.data.parse::<u64>().unwrap();
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Rc<dyn ProcessState>, String> {
+        Ok(Rc::new(self.sum))
+    }
+
+    fn set_state(&mut self, state: Rc<dyn ProcessState>) -> Result<(), String> {
+        self.sum = *state.downcast_rc::<u64>().unwrap();
+        Ok(())
+    }
+}
+
+fn counters_system() -> System {
+    build_system(
+        1,
+        vec![
+            ("client", boxed!(Client::default())),
+            ("counter1", boxed!(Counter::default())),
+            ("counter2", boxed!(Counter::default())),
+        ],
+    )
+}
+
+#[test]
+fn typed_access_in_simulation() {
+    let mut sys = counters_system();
+    sys.send_local_message("client", Message::new("ADD", "3"));
+    sys.send_local_message("client", Message::new("ADD", "4"));
+    sys.step_until_no_events();
+
+    assert_eq!(sys.process::<Counter>("counter1").unwrap().sum, 7);
+    assert_eq!(*sys.proc_state::<u64>("counter2").unwrap(), 7);
+    assert_eq!(sys.proc_state_json::<ClientState>("client").unwrap().sent, 4);
+    assert_eq!(
+        sys.proc_state_json::<serde_json::Value>("client").unwrap(),
+        serde_json::json!({"sent": 4})
+    );
+
+    let counters: Vec<_> = sys
+        .processes_of_type::<Counter>()
+        .map(|(proc, counter)| (proc.to_string(), counter.sum))
+        .collect();
+    assert_eq!(counters, [("counter1".to_string(), 7), ("counter2".to_string(), 7)]);
+    assert_eq!(sys.processes_of_type::<Client>().count(), 1);
+}
+
+#[test]
+fn typed_access_errors() {
+    let sys = counters_system();
+    let err = sys.process::<Counter>("unknown").unwrap_err();
+    assert_eq!(
+        err,
+        ProcessAccessError::UnknownProcess {
+            proc: "unknown".to_string()
+        }
+    );
+    assert_eq!(err.to_string(), "unknown process 'unknown'");
+
+    let err = sys.process::<Counter>("client").unwrap_err();
+    assert!(matches!(err, ProcessAccessError::WrongProcessType { .. }));
+    assert!(
+        err.to_string().starts_with("process 'client' is not of type ") && err.to_string().ends_with("Counter"),
+        "{err}"
+    );
+
+    let err = sys.proc_state::<String>("counter1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "state of process 'counter1' is not of type alloc::string::String"
+    );
+    assert!(matches!(
+        sys.proc_state_json::<ClientState>("counter1"),
+        Err(ProcessAccessError::WrongStateType { .. })
+    ));
+
+    let err = sys.proc_state_json::<Vec<u64>>("client").unwrap_err();
+    assert!(matches!(err, ProcessAccessError::InvalidJson { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("state of process 'client' is not a valid JSON of type alloc::vec::Vec<u64>"));
+}
+
+#[test]
+fn typed_access_in_model_checking() {
+    let sys = counters_system();
+    let goal = boxed!(|state: &McState| {
+        let sent = state.proc_state_json::<ClientState>("client").unwrap().sent;
+        let sums: Vec<_> = state.proc_states_of_type::<u64>().map(|(_, sum)| *sum).collect();
+        (sent == 2 && sums == [5, 5]).then(|| "done".to_string())
+    });
+    let invariant = boxed!(|state: &McState| {
+        assert!(state.proc_state::<u64>("unknown").is_err());
+        let sum = state.proc_state::<u64>("counter1").map_err(|err| err.to_string())?;
+        if *sum <= 5 {
+            Ok(())
+        } else {
+            Err(format!("unexpected sum {sum}"))
+        }
+    });
+    let config = StrategyConfig::default().goal(goal).invariant(invariant);
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| {
+        sys.send_local_message("n1", "client", Message::new("ADD", "5"));
+    });
+    assert!(result.is_ok());
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use sugars::boxed;
+
+use anysystem::stats::{OperationStats, StatsRecorder};
+use anysystem::{Context, Message, Process};
+
+use crate::common::{build_system, Client};
+
+/// Replies to PING immediately, to SLOW after 3 time units, and ignores other requests.
+#[derive(Clone, Default)]
+struct Server {
+    pending: HashMap<String, (Message, String)>,
+}
+
+impl Process for Server {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        match msg.tip.as_str() {
+            "PING" => ctx.send(Message::new("PONG", &msg.data), from),
+            "SLOW" => {
+                let timer = format!("reply-{}", self.pending.len());
+                ctx.set_timer(&timer, 3.);
+                self.pending.insert(timer, (msg, from));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, _msg: Message, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_timer(&mut self, timer: String, ctx: &mut Context) -> Result<(), String> {
+        let (msg, from) = self.pending.remove(&timer).unwrap();
+        ctx.send(Message::new("PONG", &msg.data), from);
+        Ok(())
+    }
+}
+
+fn request_id(msg: &Message) -> Option<String> {
+    let data: Value = serde_json::from_str(&msg.data).ok()?;
+    data["id"].as_u64().map(|id| id.to_string())
+}
+
+fn response_id(msg: &Message) -> Option<String> {
+    if msg.tip == "PONG" {
+        request_id(msg)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn latency_and_throughput() {
+    let mut sys = build_system(
+        123,
+        vec![
+            ("client", boxed!(Client::new("server"))),
+            ("server", boxed!(Server::default())),
+        ],
+    );
+    let recorder = StatsRecorder::new(request_id, response_id);
+    let recorded = recorder.stats();
+    sys.logger().add_sink(boxed!(recorder));
+
+    for id in 0..10 {
+        sys.send_local_message("client", Message::json("PING", &json!({ "id": id })));
+        sys.step_for_duration(1.);
+    }
+    sys.send_local_message("client", Message::json("SLOW", &json!({"id": 10})));
+    sys.send_local_message("client", Message::json("SLOW", &json!({"id": 11})));
+    sys.send_local_message("client", Message::json("DROP", &json!({"id": 12})));
+    sys.step_until_no_events();
+
+    let stats = OperationStats::from_trace(sys.logger().trace(), request_id, response_id);
+    assert_eq!(recorded.borrow().records(), stats.records());
+    assert_eq!(stats.records().len(), 13);
+    assert_eq!(stats.records()[0].latency(), Some(2.));
+    assert_eq!(stats.records()[12].latency(), None);
+    assert_eq!(stats.latencies(Some("SLOW")), vec![5., 5.]);
+    assert_eq!(stats.latencies(None).len(), 12);
+
+    let report = stats.report(&sys.network(), 5.);
+    let ops: Vec<_> = report
+        .latency
+        .iter()
+        .map(|s| (s.op.as_str(), s.count, s.completed, s.p50, s.p95, s.max))
+        .collect();
+    assert_eq!(
+        ops,
+        vec![
+            ("DROP", 1, 0, 0., 0., 0.),
+            ("PING", 10, 10, 2., 2., 2.),
+            ("SLOW", 2, 2, 5., 5., 5.),
+        ]
+    );
+    let total = &report.total;
+    assert_eq!((total.count, total.completed, total.p50, total.p95), (13, 12, 2., 5.));
+    assert_eq!(total.mean, 2.5);
+
+    // PING responses are received at times 2..11, SLOW responses at time 15
+    let windows: Vec<_> = report.throughput.iter().map(|w| (w.start, w.completed)).collect();
+    assert_eq!(windows, vec![(0., 3), (5., 5), (10., 2), (15., 2)]);
+    assert_eq!(report.throughput[1].throughput, 1.);
+
+    // each completed operation sends a request and a response, the dropped one only a request
+    assert_eq!(report.network_messages, 25);
+    assert_eq!(report.messages_per_op, 25. / 12.);
+    assert_eq!(report.traffic, sys.network().traffic());
+
+    let json: Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["latency"][1]["op"], "PING");
+    assert_eq!(json["total"]["count"], 13);
+    assert_eq!(json["throughput"].as_array().unwrap().len(), 4);
+    let csv = report.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "op,count,completed,mean,p50,p95,p99,max");
+    assert_eq!(lines[2], "PING,10,10,2,2,2,2,2");
+    let summary = report.summary_csv();
+    assert!(summary.starts_with("count,completed,mean,p50,p95,p99,max,network_messages,traffic,"));
+    assert!(summary.lines().nth(1).unwrap().starts_with("13,12,2.5,2,5,5,5,25,"));
+    assert_eq!(report.throughput_csv().lines().nth(1), Some("0,5,3,0.6"));
+}
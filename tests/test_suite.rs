@@ -0,0 +1,286 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde_json::Value;
+use sugars::boxed;
+
+use anysystem::logger::{ConsoleOutput, LoggerConfig};
+use anysystem::mc::strategies::Dfs;
+use anysystem::mc::{ModelChecker, StrategyConfig};
+use anysystem::test::{PassCriterion, TestResult, TestSuite};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::{build_system, build_system_with_logger_config};
+
+/// Bounces each received message back to the sender forever.
+#[derive(Clone)]
+struct Bouncer {
+    peer: String,
+}
+
+impl Process for Bouncer {
+    fn on_message(&mut self, msg: Message, from: String, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, from);
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        ctx.send(msg, self.peer.clone());
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn build_bouncing(console: ConsoleOutput) -> System {
+    let mut sys = build_system_with_logger_config(
+        1,
+        LoggerConfig::default().console(console).colored(false),
+        vec![
+            ("a", boxed!(Bouncer { peer: "b".to_string() })),
+            ("b", boxed!(Bouncer { peer: "a".to_string() })),
+        ],
+    );
+    sys.send_local_message("a", Message::new("BALL", "{}"));
+    sys
+}
+
+fn endless(_: &()) -> TestResult {
+    let mut sys = build_bouncing(ConsoleOutput::Disabled);
+    sys.step_until_no_events();
+    Ok(true)
+}
+
+fn endless_model_checking(_: &()) -> TestResult {
+    let sys = build_bouncing(ConsoleOutput::Disabled);
+    ModelChecker::new(&sys)
+        .run::<Dfs>(StrategyConfig::default())
+        .map_err(|err| err.message())?;
+    Ok(true)
+}
+
+fn bounded(steps: &u64) -> TestResult {
+    let mut sys = build_bouncing(ConsoleOutput::Stdout);
+    sys.steps(*steps);
+    Ok(true)
+}
+
+fn failing(steps: &u64) -> TestResult {
+    bounded(steps)?;
+    Err("ball is lost".to_string())
+}
+
+fn panicking(_: &u64) -> TestResult {
+    let values: Vec<u64> = Vec::new();
+    assert_eq!(values.len(), 1, "no values");
+    Ok(true)
+}
+
+#[test]
+fn limits() {
+    let mut tests = TestSuite::new();
+    tests.add("steps", endless, ()).step_limit(100);
+    tests.add("time", endless, ()).time_limit(Duration::from_millis(50));
+    let report = tests.run_report();
+    assert_eq!(report.failed_count(), 2);
+    assert_eq!(
+        report.tests[0].error.as_deref(),
+        Some("step limit of 100 steps exceeded")
+    );
+    assert_eq!(report.tests[0].steps, 101);
+    assert_eq!(report.tests[1].error.as_deref(), Some("time limit of 0.050s exceeded"));
+    assert!(report.tests[1].duration >= 0.05);
+
+    // suite limit applies to tests without own limits
+    tests.set_step_limit(10);
+    let report = tests.run_report();
+    assert_eq!(
+        report.tests[0].error.as_deref(),
+        Some("step limit of 100 steps exceeded")
+    );
+    assert_eq!(
+        report.tests[1].error.as_deref(),
+        Some("step limit of 10 steps exceeded")
+    );
+}
+
+#[test]
+fn model_checking_limits() {
+    let mut tests = TestSuite::new();
+    tests.add("mc", endless_model_checking, ()).step_limit(100);
+    let report = tests.run_report();
+    assert_eq!(
+        report.tests[0].error.as_deref(),
+        Some("step limit of 100 steps exceeded")
+    );
+    assert_eq!(report.tests[0].steps, 101);
+}
+
+#[test]
+fn filtering() {
+    let mut tests = TestSuite::new();
+    tests.add("ping 1", bounded, 1).tag("fast");
+    tests.add("ping 2", bounded, 2).tag("slow");
+    tests.add("pong 1", bounded, 1).tag("fast").tag("pong");
+
+    tests.set_filter("p?ng *");
+    assert_eq!(tests.run_report().tests.len(), 3);
+    tests.set_filter("ping*");
+    let names: Vec<String> = tests.run_report().tests.into_iter().map(|t| t.name).collect();
+    assert_eq!(names, ["ping 1", "ping 2"]);
+    tests.set_tags(["fast"]);
+    let (passed, results) = tests.run();
+    assert!(passed);
+    assert_eq!(results.keys().collect::<Vec<_>>(), ["ping 1"]);
+}
+
+static SETUPS: AtomicUsize = AtomicUsize::new(0);
+static TEARDOWNS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn hooks_and_failures() {
+    let mut tests = TestSuite::new();
+    tests.add("passing", bounded, 3);
+    tests.add("failing", failing, 3);
+    tests.add("panicking", panicking, 0);
+    tests.add("not set up", bounded, 100);
+    tests.set_setup(|steps| {
+        SETUPS.fetch_add(1, Ordering::SeqCst);
+        if *steps > 10 {
+            return Err("too many steps".to_string());
+        }
+        Ok(())
+    });
+    tests.set_teardown(|_| {
+        TEARDOWNS.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let report = tests.run_report();
+    assert_eq!(SETUPS.load(Ordering::SeqCst), 4);
+    assert_eq!(TEARDOWNS.load(Ordering::SeqCst), 4);
+    assert_eq!(report.passed_count(), 1);
+    assert_eq!(report.tests[1].error.as_deref(), Some("ball is lost"));
+    assert!(report.tests[2]
+        .error
+        .as_ref()
+        .unwrap()
+        .starts_with("panicked: assertion `left == right` failed: no values"));
+    assert_eq!(report.tests[3].error.as_deref(), Some("setup failed: too many steps"));
+    assert_eq!(report.tests[3].steps, 0);
+}
+
+#[test]
+fn reports() {
+    let mut tests = TestSuite::new();
+    tests.set_name("bouncing");
+    tests.set_capture_output(true);
+    tests.add("passing", bounded, 3).tag("smoke");
+    tests.add("failing <1>", failing, 1);
+
+    let report = tests.run_report();
+    assert_eq!(report.tests[0].steps, 3);
+    let output = &report.tests[0].output;
+    assert!(output
+        .lines()
+        .any(|line| line.contains("a --> b") && line.ends_with("BALL {}")));
+    assert!(!output.contains('\u{1b}'));
+    assert!(!report.tests[1].output.is_empty());
+
+    let json: Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["name"], "bouncing");
+    assert_eq!(json["tests"][0]["tags"][0], "smoke");
+    assert_eq!(json["tests"][0]["passed"], true);
+    assert_eq!(json["tests"][0]["error"], Value::Null);
+    assert_eq!(json["tests"][1]["error"], "ball is lost");
+
+    let xml = report.to_junit_xml();
+    assert!(xml.starts_with(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"bouncing\" tests=\"2\" failures=\"1\""
+    ));
+    assert!(xml.contains("<testcase name=\"failing &lt;1&gt;\" classname=\"bouncing\""));
+    assert!(xml.contains("<property name=\"tags\" value=\"smoke\"/>"));
+    assert!(xml.contains("<failure message=\"ball is lost\">ball is lost</failure>"));
+    assert_eq!(xml.matches("<system-out>").count(), 2);
+    assert!(xml.ends_with("</testsuite>\n"));
+}
+
+fn delivered(_: &(), (seed, drop_rate): &(u64, f64)) -> TestResult {
+    let mut sys = build_system(
+        *seed,
+        vec![
+            ("a", boxed!(Bouncer { peer: "b".to_string() })),
+            ("b", boxed!(Bouncer { peer: "a".to_string() })),
+        ],
+    );
+    sys.network().set_drop_rate(*drop_rate);
+    sys.send_local_message("a", Message::new("BALL", "{}"));
+    sys.step_for_duration(1.);
+    if sys.received_message_count("b") == 0 {
+        return Err("ball is dropped".to_string());
+    }
+    Ok(true)
+}
+
+#[test]
+fn parameterized() {
+    let mut tests = TestSuite::new();
+    tests.add_parameterized("reliable", delivered, (), (0..10).map(|seed| (seed, 0.)));
+    tests.add_parameterized("unreliable", delivered, (), (0..100).map(|seed| (seed, 0.3)));
+    tests
+        .add_parameterized("unreliable 50%", delivered, (), (0..100).map(|seed| (seed, 0.3)))
+        .pass_criterion(PassCriterion::MinPassRate(0.5));
+    tests
+        .add_parameterized("unreliable 90%", delivered, (), (0..100).map(|seed| (seed, 0.3)))
+        .pass_criterion(PassCriterion::MinPassRate(0.9));
+    tests
+        .add_parameterized("lossy", delivered, (), [(1, 1.), (2, 0.)])
+        .pass_criterion(PassCriterion::MinPassed(1));
+
+    let report = tests.run_report();
+    let passed: Vec<bool> = report.tests.iter().map(|t| t.passed).collect();
+    assert_eq!(passed, [true, false, true, false, true]);
+
+    let reliable = &report.tests[0];
+    assert_eq!(reliable.cases.len(), 10);
+    assert_eq!(reliable.cases[3].params, "(3, 0.0)");
+    assert_eq!(reliable.steps, reliable.cases.iter().map(|c| c.steps).sum::<u64>());
+
+    // results of each case are reported and do not depend on the criterion
+    let unreliable = &report.tests[1];
+    let failed: Vec<&str> = unreliable
+        .cases
+        .iter()
+        .filter(|c| !c.passed)
+        .map(|c| c.params.as_str())
+        .collect();
+    assert!(!failed.is_empty() && failed.len() < 50);
+    let error = unreliable.error.as_ref().unwrap();
+    assert!(error.starts_with(&format!("failed on {} of 100 cases\n", failed.len())));
+    assert!(error.contains(&format!("- {}: ball is dropped", failed[0])));
+    assert_eq!(report.tests[2].cases.iter().filter(|c| !c.passed).count(), failed.len());
+    let rate = 100. - failed.len() as f64;
+    assert!(report.tests[3]
+        .error
+        .as_ref()
+        .unwrap()
+        .starts_with(&format!("passed on {rate:.1}% of 100 cases, required 90.0%")));
+
+    // each case is a separate test case, failures tolerated by the criterion are skipped
+    let xml = report.to_junit_xml();
+    let failed_cases = |idx: usize| report.tests[idx].cases.iter().filter(|c| !c.passed).count();
+    assert!(xml.contains(&format!(
+        " tests=\"312\" failures=\"{}\" errors=\"0\" skipped=\"{}\"",
+        failed_cases(1) + failed_cases(3),
+        failed_cases(2) + 1
+    )));
+    assert_eq!(xml.matches("<testcase ").count(), 312);
+    assert!(xml.contains("<testcase name=\"reliable[(3, 0.0)]\""));
+    assert!(xml.contains(&format!("<testcase name=\"unreliable[{}]\"", failed[0])));
+    assert!(xml.contains("<skipped message=\"failure is tolerated by the pass criterion: ball is dropped\"/>"));
+
+    let (all_passed, results) = tests.run();
+    assert!(!all_passed);
+    assert_eq!(results["lossy"], Ok(true));
+}
@@ -0,0 +1,141 @@
+use sugars::{boxed, rc, refcell};
+
+use anysystem::mc::strategies::Bfs;
+use anysystem::mc::{McState, ModelChecker, StrategyConfig};
+use anysystem::{Context, Message, Process, System};
+
+use crate::common::build_system;
+
+/// Sends a heartbeat on each firing of the periodic timer until the limit is reached.
+#[derive(Clone)]
+struct Heartbeat {
+    period: f64,
+    jitter: f64,
+    limit: u64,
+    count: u64,
+}
+
+impl Heartbeat {
+    fn new(period: f64, jitter: f64, limit: u64) -> Self {
+        Self {
+            period,
+            jitter,
+            limit,
+            count: 0,
+        }
+    }
+}
+
+impl Process for Heartbeat {
+    fn on_start(&mut self, ctx: &mut Context) -> Result<(), String> {
+        ctx.set_periodic_timer("heartbeat", self.period, self.jitter);
+        Ok(())
+    }
+
+    fn on_message(&mut self, _msg: Message, _from: String, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_local_message(&mut self, msg: Message, ctx: &mut Context) -> Result<(), String> {
+        if msg.tip == "SET" {
+            ctx.set_timer("timeout", 5.);
+        }
+        let status = (
+            ctx.is_timer_set("heartbeat"),
+            ctx.timer_remaining("heartbeat"),
+            ctx.timer_remaining("timeout"),
+        );
+        ctx.send_local(Message::json("STATUS", &status));
+        Ok(())
+    }
+
+    fn on_timer(&mut self, _timer: String, ctx: &mut Context) -> Result<(), String> {
+        self.count += 1;
+        ctx.send_local(Message::json("HEARTBEAT", &ctx.time()));
+        if self.count == self.limit {
+            ctx.cancel_timer("heartbeat");
+        }
+        Ok(())
+    }
+}
+
+fn heartbeat_system(proc: Heartbeat) -> System {
+    build_system(1, vec![("proc", boxed!(proc))])
+}
+
+fn heartbeat_times(sys: &mut System) -> Vec<f64> {
+    sys.read_local_messages("proc")
+        .iter()
+        .map(|msg| serde_json::from_str(&msg.data).unwrap())
+        .collect()
+}
+
+#[test]
+fn periodic_timer() {
+    let mut sys = heartbeat_system(Heartbeat::new(2., 0., 3));
+    sys.step_until_no_events();
+    assert_eq!(heartbeat_times(&mut sys), [2., 4., 6.]);
+    assert_eq!(sys.time(), 6.);
+}
+
+#[test]
+fn periodic_timer_jitter() {
+    let mut sys = heartbeat_system(Heartbeat::new(1., 0.5, 10));
+    sys.step_until_no_events();
+    let times = heartbeat_times(&mut sys);
+    assert_eq!(times.len(), 10);
+    let mut prev = 0.;
+    for time in &times {
+        assert!((1.0..1.5).contains(&(time - prev)), "{times:?}");
+        prev = *time;
+    }
+    assert!(times.iter().any(|time| time.fract() != 0.));
+
+    let mut sys = heartbeat_system(Heartbeat::new(1., 0.5, 10));
+    sys.step_until_no_events();
+    assert_eq!(heartbeat_times(&mut sys), times);
+}
+
+#[test]
+fn timer_introspection() {
+    let mut sys = heartbeat_system(Heartbeat::new(2., 0., 3));
+    sys.step_for_duration(3.);
+    sys.read_local_messages("proc");
+
+    sys.send_local_message("proc", Message::new("GET", "{}"));
+    sys.send_local_message("proc", Message::new("SET", "{}"));
+    let statuses: Vec<(bool, Option<f64>, Option<f64>)> = sys
+        .read_local_messages("proc")
+        .iter()
+        .map(|msg| serde_json::from_str(&msg.data).unwrap())
+        .collect();
+    assert_eq!(statuses, [(true, Some(1.), None), (true, Some(1.), Some(5.))]);
+
+    sys.step_until_no_events();
+    sys.read_local_messages("proc");
+    sys.send_local_message("proc", Message::new("GET", "{}"));
+    assert_eq!(
+        sys.read_local_messages("proc")[0].data,
+        serde_json::to_string(&(false, None::<f64>, None::<f64>)).unwrap()
+    );
+}
+
+#[test]
+fn periodic_timer_in_model_checking() {
+    let sys = heartbeat_system(Heartbeat::new(1., 0., 100));
+    let outbox_len = rc!(refcell!(0));
+    let final_outbox_len = outbox_len.clone();
+    let goal = boxed!(move |state: &McState| {
+        if state.events.is_empty() {
+            *final_outbox_len.borrow_mut() = state.node_states["n1"].proc_states["proc"].local_outbox.len();
+            Some("final".to_string())
+        } else {
+            None
+        }
+    });
+    let config = StrategyConfig::default().goal(goal);
+    let result = ModelChecker::new(&sys).run_with_change::<Bfs>(config, |sys| sys.set_periodic_timer_limit(2));
+    assert!(result.is_ok());
+    // the first firing and two re-arms
+    assert_eq!(*outbox_len.borrow(), 3);
+}
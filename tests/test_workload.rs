@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use sugars::boxed;
+
+use anysystem::history::{check_linearizability, KvOp, KvStore};
+use anysystem::workload::{Arrival, KeyDistribution, KvHistory, Workload, WorkloadConfig};
+use anysystem::{Message, System};
+
+use crate::common::{build_system, Client, KvServer};
+
+/// Builds a system with the key-value server and three clients c1, c2, c3 running on nodes n2, n3, n4.
+fn build_kv_system(seed: u64) -> System {
+    let sys = build_system(
+        seed,
+        vec![
+            ("server", boxed!(KvServer::default())),
+            ("c1", boxed!(Client::new("server"))),
+            ("c2", boxed!(Client::new("server"))),
+            ("c3", boxed!(Client::new("server"))),
+        ],
+    );
+    sys.network().set_delays(0.5, 1.5);
+    sys
+}
+
+fn encode(op: &KvOp<String, String>) -> Message {
+    match op {
+        KvOp::Get(key) => Message::json("GET", &json!({ "key": key })),
+        KvOp::Put(key, value) => Message::json("PUT", &json!({"key": key, "value": value})),
+        KvOp::Delete(_) => unreachable!(),
+    }
+}
+
+fn decode(msg: &Message) -> Option<Option<String>> {
+    let data: Value = serde_json::from_str(&msg.data).ok()?;
+    match msg.tip.as_str() {
+        "GET_OK" => Some(data["value"].as_str().map(|s| s.to_string())),
+        "PUT_OK" => Some(None),
+        _ => None,
+    }
+}
+
+fn run(seed: u64, config: WorkloadConfig) -> KvHistory {
+    let mut sys = build_kv_system(seed);
+    Workload::new(config, encode, decode).run(&mut sys)
+}
+
+#[test]
+fn closed_loop() {
+    let config = WorkloadConfig::default()
+        .clients(["c1", "c2", "c3"])
+        .operations(20)
+        .arrival(Arrival::Closed { think_time: 0.5 });
+    let history = run(123, config.clone());
+    assert_eq!(history.len(), 60);
+    assert!(history.ops().iter().all(|op| !op.is_pending()));
+    assert!(history.ops().iter().any(|op| matches!(op.input, KvOp::Get(_))));
+    assert!(history.ops().iter().any(|op| matches!(op.input, KvOp::Put(_, _))));
+    assert!(check_linearizability(&KvStore::new(), &history).is_ok());
+
+    // next operation of a client is invoked after the response and think time
+    for client in ["c1", "c2", "c3"] {
+        let ops: Vec<_> = history.ops().iter().filter(|op| op.client == client).collect();
+        assert_eq!(ops.len(), 20);
+        assert_eq!(ops[0].call, 0.);
+        for pair in ops.windows(2) {
+            assert_eq!(pair[1].call, pair[0].ret.unwrap() + 0.5);
+        }
+    }
+
+    // the workload is deterministic for the same seed
+    assert_eq!(run(123, config.clone()), history);
+    assert_ne!(run(321, config), history);
+}
+
+#[test]
+fn open_loop() {
+    let config = WorkloadConfig::default()
+        .clients(["c1", "c2"])
+        .operations(None)
+        .duration(50.)
+        .arrival(Arrival::Open { rate: 2. });
+    let history = run(123, config);
+    // about 2 operations per time unit at each client
+    assert!((150..250).contains(&history.len()), "{}", history.len());
+    assert!(history.ops().iter().all(|op| op.call <= 50. && !op.is_pending()));
+    // operations are invoked without waiting for responses
+    let c1: Vec<_> = history.ops().iter().filter(|op| op.client == "c1").collect();
+    assert!(c1.windows(2).any(|pair| pair[1].call < pair[0].ret.unwrap()));
+}
+
+#[test]
+fn operation_mix() {
+    let config = WorkloadConfig::default()
+        .clients(["c1"])
+        .operations(500)
+        .read_ratio(0.8)
+        .keys(10, KeyDistribution::Zipfian(1.5));
+    let history = run(123, config);
+    let mut reads = 0;
+    let mut key_counts = HashMap::new();
+    for op in history.ops() {
+        let key = match &op.input {
+            KvOp::Get(key) => {
+                reads += 1;
+                key
+            }
+            KvOp::Put(key, _) => key,
+            KvOp::Delete(key) => key,
+        };
+        *key_counts.entry(key.clone()).or_insert(0) += 1;
+    }
+    assert!((350..450).contains(&reads), "{reads}");
+    // with exponent 1.5 about half of operations access the most popular key
+    assert!((200..300).contains(&key_counts["key0"]), "{:?}", key_counts);
+    assert!(key_counts["key0"] > key_counts["key1"]);
+    assert!(key_counts.get("key9").copied().unwrap_or(0) < 20);
+
+    // write values are unique
+    let values: Vec<_> = history
+        .ops()
+        .iter()
+        .filter_map(|op| match &op.input {
+            KvOp::Put(_, value) => Some(value.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(values.first().map(|v| v.as_str()), Some("v1"));
+    assert_eq!(
+        values.iter().collect::<std::collections::HashSet<_>>().len(),
+        values.len()
+    );
+}
+
+#[test]
+fn crashed_client() {
+    let mut sys = build_kv_system(123);
+    sys.crash_node("n3");
+    let config = WorkloadConfig::default()
+        .clients(["c1", "c2"])
+        .operations(None)
+        .duration(10.)
+        .arrival(Arrival::Open { rate: 1. });
+    let history = Workload::new(config, encode, decode).start(&mut sys);
+    sys.step_for_duration(5.);
+    sys.recover_node("n3");
+    sys.add_process("c2", boxed!(Client::new("server")), "n3");
+    sys.step_until_no_events();
+
+    let history = history.borrow();
+    let c2: Vec<_> = history.ops().iter().filter(|op| op.client == "c2").collect();
+    assert!(!c2.is_empty());
+    assert!(c2.iter().all(|op| op.call > 5.));
+    assert!(history.ops().iter().any(|op| op.client == "c1" && op.call < 5.));
+}